@@ -1,8 +1,9 @@
 use sp_core::{Pair, Public, sr25519};
 use substrate_dex_runtime::{
-	AccountId, AuraConfig, BalancesConfig, GenesisConfig, GrandpaConfig,
-	SudoConfig, SystemConfig, WASM_BINARY, Signature
+	AccountId, AuraConfig, Balance, BalancesConfig, GenesisConfig, GrandpaConfig,
+	SudoConfig, SystemConfig, TokenModuleConfig, TradeModuleConfig, WASM_BINARY, Signature
 };
+use substrate_dex_runtime::trade::OrderType;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{Verify, IdentifyAccount};
@@ -117,8 +118,38 @@ fn testnet_genesis(initial_authorities: Vec<(AuraId, GrandpaId)>,
 		grandpa: Some(GrandpaConfig {
 			authorities: initial_authorities.iter().map(|x| (x.1.clone(), 1)).collect(),
 		}),
+		token_module: Some(TokenModuleConfig {
+			tokens: vec![
+				(b"BTC".to_vec(), root_key.clone(), 21_000_000, None),
+				(b"USDT".to_vec(), root_key.clone(), 1_000_000_000, None),
+			],
+			balances: vec![
+				token_balances(b"BTC", 21_000_000, &root_key, &endowed_accounts),
+				token_balances(b"USDT", 1_000_000_000, &root_key, &endowed_accounts),
+			].concat(),
+		}),
+		trade_module: Some(TradeModuleConfig {
+			trade_pairs: vec![
+				(b"USDT".to_vec(), b"BTC".to_vec(), 8),
+			],
+			// 1 BTC for 9_000 USDT and 11_000 USDT
+			orders: vec![
+				(root_key.clone(), b"USDT".to_vec(), b"BTC".to_vec(), OrderType::Buy, 9_000 * 100_000_000, 900_000),
+				(root_key.clone(), b"USDT".to_vec(), b"BTC".to_vec(), OrderType::Sell, 11_000 * 100_000_000, 100),
+			],
+		}),
 		sudo: Some(SudoConfig {
 			key: root_key,
 		}),
 	}
 }
+
+/// Share the supply of the token between the accounts, the owner gets the remainder
+fn token_balances(symbol: &[u8], supply: Balance, owner: &AccountId, accounts: &[AccountId]) -> Vec<(Vec<u8>, AccountId, Balance)> {
+	let holders = accounts.iter().filter(|&a| a != owner).collect::<Vec<_>>();
+	let share = supply / (holders.len() as Balance + 1);
+
+	let mut balances = holders.into_iter().map(|a| (symbol.to_vec(), a.clone(), share)).collect::<Vec<_>>();
+	balances.push((symbol.to_vec(), owner.clone(), supply - share * balances.len() as Balance));
+	balances
+}
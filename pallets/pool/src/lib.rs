@@ -0,0 +1,509 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::convert::{TryInto, TryFrom};
+
+use sp_core::U256;
+use sp_std::{prelude::*, result};
+use sp_runtime::{ModuleId, Permill, traits::{AccountIdConversion, Hash, Zero, CheckedAdd, CheckedSub}};
+
+use frame_support::{decl_module, decl_storage, decl_event, decl_error, StorageValue, StorageMap,
+                    ensure, dispatch, traits::Get};
+
+use system::ensure_signed;
+use codec::{Encode, Decode};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+/// The account holding the reserves of all the pools
+const POOL_MODULE_ID: ModuleId = ModuleId(*b"dex/pool");
+
+/// LP shares locked by the first liquidity provider, so that a pool can never be emptied
+pub const MINIMUM_LIQUIDITY: u128 = 1_000;
+
+pub trait Trait: token::Trait + system::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Fee taken from the input amount of a swap, it stays in the pool for the liquidity providers
+    type LpFee: Get<Permill>;
+}
+
+/// A constant product pool, token0 < token1
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Pool<T> where T: Trait {
+    pub hash: T::Hash,
+    pub token0: T::Hash,
+    pub token1: T::Hash,
+    pub reserve0: T::Balance,
+    pub reserve1: T::Balance,
+    pub lp_token: T::Hash,
+}
+
+impl<T> Pool<T> where T: Trait {
+    /// (reserve_in, reserve_out) of a swap selling `token_in`
+    pub fn reserves(&self, token_in: T::Hash) -> (T::Balance, T::Balance) {
+        if token_in == self.token0 {
+            (self.reserve0, self.reserve1)
+        } else {
+            (self.reserve1, self.reserve0)
+        }
+    }
+
+    fn set_reserves(&mut self, token_in: T::Hash, reserve_in: T::Balance, reserve_out: T::Balance) {
+        if token_in == self.token0 {
+            self.reserve0 = reserve_in;
+            self.reserve1 = reserve_out;
+        } else {
+            self.reserve1 = reserve_in;
+            self.reserve0 = reserve_out;
+        }
+    }
+}
+
+decl_error! {
+	/// Error for the pool module.
+	pub enum Error for Module<T: Trait> {
+		/// The two tokens of a pool are the same
+		SameToken,
+		/// There is no match token
+		NoMatchingToken,
+		/// The pool of the tokens already exists
+		PoolAlreadyExists,
+		/// There is no pool of the tokens
+		NoMatchingPool,
+		/// Amount is zero
+		ZeroAmount,
+		/// The pool has not enough liquidity
+		InsufficientLiquidity,
+		/// The initial liquidity is not more than MINIMUM_LIQUIDITY
+		InsufficientInitialLiquidity,
+		/// The amount is beyond the slippage limit
+		SlippageExceeded,
+		/// Number cast error
+		NumberCastError,
+		/// Amount overflow
+		AmountOverflow,
+		/// The LP fee takes the whole input amount
+		InvalidLpFee,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as PoolModule {
+		/// PoolHash => Pool
+		Pools get(fn pool): map hasher(blake2_128_concat) T::Hash => Option<Pool<T>>;
+		/// (Token0Hash, Token1Hash) => PoolHash
+		PoolsHashByTokens get(fn pool_hash_by_tokens): map hasher(blake2_128_concat) (T::Hash, T::Hash) => Option<T::Hash>;
+		/// Index => PoolHash
+		PoolsHashByIndex get(fn pool_hash_by_index): map hasher(blake2_128_concat) u64 => Option<T::Hash>;
+		/// Index
+		PoolsIndex get(fn pool_index): u64;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+	{
+		// (accountId, poolHash, token0Hash, token1Hash, lpTokenHash)
+		PoolCreated(AccountId, Hash, Hash, Hash, Hash),
+
+		// (accountId, poolHash, token0Amount, token1Amount, lpAmount)
+		LiquidityAdded(AccountId, Hash, Balance, Balance, Balance),
+
+		// (accountId, poolHash, token0Amount, token1Amount, lpAmount)
+		LiquidityRemoved(AccountId, Hash, Balance, Balance, Balance),
+
+		// (accountId, poolHash, tokenInHash, amountIn, tokenOutHash, amountOut)
+		Swapped(AccountId, Hash, Hash, Balance, Hash, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		type Error = Error<T>;
+
+		#[weight = 1_000_000]
+		pub fn create_pool(origin, token_a: T::Hash, token_b: T::Hash) -> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_create_pool(sender, token_a, token_b)?;
+
+			Ok(())
+		}
+
+		#[weight = 1_000_000]
+		pub fn add_liquidity(origin, token_a: T::Hash, token_b: T::Hash, amount_a_desired: T::Balance, amount_b_desired: T::Balance,
+			amount_a_min: T::Balance, amount_b_min: T::Balance) -> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_add_liquidity(sender, token_a, token_b, amount_a_desired, amount_b_desired, amount_a_min, amount_b_min)
+		}
+
+		#[weight = 1_000_000]
+		pub fn remove_liquidity(origin, token_a: T::Hash, token_b: T::Hash, liquidity: T::Balance,
+			amount_a_min: T::Balance, amount_b_min: T::Balance) -> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_remove_liquidity(sender, token_a, token_b, liquidity, amount_a_min, amount_b_min)
+		}
+
+		#[weight = 1_000_000]
+		pub fn swap_exact_in(origin, token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance, min_amount_out: T::Balance)
+			-> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_swap_exact_in(sender, token_in, token_out, amount_in, min_amount_out)?;
+
+			Ok(())
+		}
+
+		#[weight = 1_000_000]
+		pub fn swap_exact_out(origin, token_in: T::Hash, token_out: T::Hash, amount_out: T::Balance, max_amount_in: T::Balance)
+			-> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_swap_exact_out(sender, token_in, token_out, amount_out, max_amount_in)?;
+
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+    /// The account holding the reserves of all the pools
+    pub fn account_id() -> T::AccountId {
+        POOL_MODULE_ID.into_account()
+    }
+
+    fn sort_tokens(token_a: T::Hash, token_b: T::Hash) -> (T::Hash, T::Hash) {
+        if token_a < token_b {
+            (token_a, token_b)
+        } else {
+            (token_b, token_a)
+        }
+    }
+
+    /// The pool of the two tokens in any order
+    pub fn pool_of(token_a: T::Hash, token_b: T::Hash) -> Option<Pool<T>> {
+        Self::pool_hash_by_tokens(Self::sort_tokens(token_a, token_b)).and_then(|hash| Self::pool(hash))
+    }
+
+    fn ensure_pool(token_a: T::Hash, token_b: T::Hash) -> result::Result<Pool<T>, dispatch::DispatchError> {
+        ensure!(token_a != token_b, Error::<T>::SameToken);
+        Self::pool_of(token_a, token_b).ok_or(Error::<T>::NoMatchingPool.into())
+    }
+
+    fn into_128<A: TryInto<u128>>(i: A) -> result::Result<u128, dispatch::DispatchError> {
+        TryInto::<u128>::try_into(i).map_err(|_| Error::<T>::NumberCastError.into())
+    }
+
+    fn from_u256<A: TryFrom<u128>>(i: U256) -> result::Result<A, dispatch::DispatchError> {
+        ensure!(i <= U256::from(u128::max_value()), Error::<T>::NumberCastError);
+        TryFrom::<u128>::try_from(i.low_u128()).map_err(|_| Error::<T>::NumberCastError.into())
+    }
+
+    fn u256<A: TryInto<u128>>(i: A) -> result::Result<U256, dispatch::DispatchError> {
+        Ok(U256::from(Self::into_128(i)?))
+    }
+
+    fn sqrt(y: U256) -> U256 {
+        if y <= U256::from(3u32) {
+            return if y.is_zero() { y } else { U256::one() };
+        }
+
+        let mut x = y;
+        let mut z = (y >> 1) + U256::one();
+        while z < x {
+            x = z;
+            z = (y / z + z) >> 1;
+        }
+
+        x
+    }
+
+    // "LP-<index>"
+    fn lp_symbol(index: u64) -> Vec<u8> {
+        let mut digits = Vec::new();
+        let mut n = index;
+        loop {
+            digits.push(b'0' + (n % 10) as u8);
+            n /= 10;
+            if n == 0 {
+                break;
+            }
+        }
+        digits.reverse();
+
+        let mut symbol = b"LP-".to_vec();
+        symbol.extend(digits);
+        symbol
+    }
+
+    // 1e6 minus the LP fee in parts per million
+    fn fee_factor() -> result::Result<U256, dispatch::DispatchError> {
+        let fee = T::LpFee::get();
+        ensure!(fee < Permill::one(), Error::<T>::InvalidLpFee);
+
+        Ok(U256::from(1_000_000 - fee.deconstruct()))
+    }
+
+    /// Amount out of a swap selling `amount_in`, the LP fee is taken from `amount_in`
+    pub fn get_amount_out(amount_in: T::Balance, reserve_in: T::Balance, reserve_out: T::Balance)
+        -> result::Result<T::Balance, dispatch::DispatchError> {
+        ensure!(amount_in > Zero::zero(), Error::<T>::ZeroAmount);
+        ensure!(reserve_in > Zero::zero() && reserve_out > Zero::zero(), Error::<T>::InsufficientLiquidity);
+
+        let fee_factor = Self::fee_factor()?;
+        let amount_in_with_fee = Self::u256(amount_in)? * fee_factor;
+        let numerator = amount_in_with_fee * Self::u256(reserve_out)?;
+        let denominator = Self::u256(reserve_in)? * U256::from(1_000_000u32) + amount_in_with_fee;
+
+        Self::from_u256(numerator / denominator)
+    }
+
+    /// Amount in of a swap buying `amount_out`, rounded up
+    pub fn get_amount_in(amount_out: T::Balance, reserve_in: T::Balance, reserve_out: T::Balance)
+        -> result::Result<T::Balance, dispatch::DispatchError> {
+        ensure!(amount_out > Zero::zero(), Error::<T>::ZeroAmount);
+        ensure!(reserve_in > Zero::zero() && amount_out < reserve_out, Error::<T>::InsufficientLiquidity);
+
+        let fee_factor = Self::fee_factor()?;
+        let numerator = Self::u256(reserve_in)? * Self::u256(amount_out)? * U256::from(1_000_000u32);
+        let denominator = (Self::u256(reserve_out)? - Self::u256(amount_out)?) * fee_factor;
+
+        Self::from_u256(numerator / denominator + U256::one())
+    }
+
+    /// Amount in which moves the marginal rate of the pool (out per in, after the LP fee) down to
+    /// `numerator / denominator`, zero if the marginal rate is not better than it already
+    pub fn get_amount_in_to_rate(reserve_in: T::Balance, reserve_out: T::Balance, numerator: U256, denominator: U256)
+        -> result::Result<T::Balance, dispatch::DispatchError> {
+        ensure!(!numerator.is_zero() && !denominator.is_zero(), Error::<T>::ZeroAmount);
+        ensure!(reserve_in > Zero::zero() && reserve_out > Zero::zero(), Error::<T>::InsufficientLiquidity);
+
+        // the marginal rate after x in is reserve_in * reserve_out * f * 1e6 / (reserve_in * 1e6 + f * x)^2
+        let fee_factor = Self::fee_factor()?;
+        let scaled_reserve_in = Self::u256(reserve_in)? * U256::from(1_000_000u32);
+        let square = Self::u256(reserve_in)?
+            .checked_mul(Self::u256(reserve_out)?)
+            .and_then(|v| v.checked_mul(fee_factor * U256::from(1_000_000u32)))
+            .and_then(|v| v.checked_mul(denominator))
+            .ok_or(Error::<T>::AmountOverflow)? / numerator;
+
+        let root = Self::sqrt(square);
+        if root <= scaled_reserve_in {
+            return Ok(Zero::zero());
+        }
+
+        Self::from_u256(((root - scaled_reserve_in) / fee_factor).min(U256::from(u128::max_value())))
+    }
+
+    pub fn do_create_pool(sender: T::AccountId, token_a: T::Hash, token_b: T::Hash) -> result::Result<T::Hash, dispatch::DispatchError> {
+        ensure!(token_a != token_b, Error::<T>::SameToken);
+        ensure!(<token::Module<T>>::token(token_a).is_some(), Error::<T>::NoMatchingToken);
+        ensure!(<token::Module<T>>::token(token_b).is_some(), Error::<T>::NoMatchingToken);
+
+        let (token0, token1) = Self::sort_tokens(token_a, token_b);
+        ensure!(Self::pool_hash_by_tokens((token0, token1)).is_none(), Error::<T>::PoolAlreadyExists);
+
+        let hash = (POOL_MODULE_ID, token0, token1).using_encoded(<T as system::Trait>::Hashing::hash);
+
+        let index = Self::pool_index();
+        let lp_token = <token::Module<T>>::do_create(Self::account_id(), Self::lp_symbol(index), Zero::zero())?;
+
+        let pool = Pool::<T> {
+            hash, token0, token1, lp_token,
+            reserve0: Zero::zero(),
+            reserve1: Zero::zero(),
+        };
+
+        Pools::<T>::insert(hash, pool);
+        PoolsHashByTokens::<T>::insert((token0, token1), hash);
+        PoolsHashByIndex::<T>::insert(index, hash);
+        PoolsIndex::mutate(|n| *n += 1);
+
+        Self::deposit_event(RawEvent::PoolCreated(sender, hash, token0, token1, lp_token));
+
+        Ok(hash)
+    }
+
+    fn do_add_liquidity(sender: T::AccountId, token_a: T::Hash, token_b: T::Hash, amount_a_desired: T::Balance,
+                        amount_b_desired: T::Balance, amount_a_min: T::Balance, amount_b_min: T::Balance) -> dispatch::DispatchResult {
+        let mut pool = Self::ensure_pool(token_a, token_b)?;
+        ensure!(amount_a_desired > Zero::zero() && amount_b_desired > Zero::zero(), Error::<T>::ZeroAmount);
+
+        let (reserve_a, reserve_b) = pool.reserves(token_a);
+        let lp_supply = <token::Module<T>>::token(pool.lp_token).ok_or(Error::<T>::NoMatchingToken)?.total_supply;
+
+        let amount_a: T::Balance;
+        let amount_b: T::Balance;
+        let liquidity: U256;
+        if lp_supply == Zero::zero() {
+            amount_a = amount_a_desired;
+            amount_b = amount_b_desired;
+
+            let root = Self::sqrt(Self::u256(amount_a)? * Self::u256(amount_b)?);
+            ensure!(root > U256::from(MINIMUM_LIQUIDITY), Error::<T>::InsufficientInitialLiquidity);
+            liquidity = root - U256::from(MINIMUM_LIQUIDITY);
+        } else {
+            let amount_b_optimal: T::Balance =
+                Self::from_u256(Self::u256(amount_a_desired)? * Self::u256(reserve_b)? / Self::u256(reserve_a)?)?;
+            if amount_b_optimal <= amount_b_desired {
+                ensure!(amount_b_optimal >= amount_b_min, Error::<T>::SlippageExceeded);
+                amount_a = amount_a_desired;
+                amount_b = amount_b_optimal;
+            } else {
+                let amount_a_optimal: T::Balance =
+                    Self::from_u256(Self::u256(amount_b_desired)? * Self::u256(reserve_a)? / Self::u256(reserve_b)?)?;
+                ensure!(amount_a_optimal >= amount_a_min, Error::<T>::SlippageExceeded);
+                amount_a = amount_a_optimal;
+                amount_b = amount_b_desired;
+            }
+
+            let liquidity_a = Self::u256(amount_a)? * Self::u256(lp_supply)? / Self::u256(reserve_a)?;
+            let liquidity_b = Self::u256(amount_b)? * Self::u256(lp_supply)? / Self::u256(reserve_b)?;
+            liquidity = liquidity_a.min(liquidity_b);
+        }
+
+        ensure!(amount_a >= amount_a_min && amount_b >= amount_b_min, Error::<T>::SlippageExceeded);
+        ensure!(!liquidity.is_zero(), Error::<T>::InsufficientLiquidity);
+        let liquidity: T::Balance = Self::from_u256(liquidity)?;
+
+        let new_reserve_a = reserve_a.checked_add(&amount_a).ok_or(Error::<T>::AmountOverflow)?;
+        let new_reserve_b = reserve_b.checked_add(&amount_b).ok_or(Error::<T>::AmountOverflow)?;
+
+        // every check is done before the first write, the storage is not rolled back on error
+        let minimum_liquidity: T::Balance = Self::from_u256(U256::from(MINIMUM_LIQUIDITY))?;
+        let locked_liquidity = if lp_supply == Zero::zero() { minimum_liquidity } else { Zero::zero() };
+        let minted = liquidity.checked_add(&locked_liquidity).ok_or(Error::<T>::AmountOverflow)?;
+        <token::Module<T>>::ensure_can_transfer(&sender, token_a, &Self::account_id(), amount_a)?;
+        <token::Module<T>>::ensure_can_transfer(&sender, token_b, &Self::account_id(), amount_b)?;
+        <token::Module<T>>::ensure_can_mint(&sender, pool.lp_token, minted)?;
+        <token::Module<T>>::ensure_transferable(pool.lp_token, &Self::account_id())?;
+
+        <token::Module<T>>::do_transfer(sender.clone(), token_a, Self::account_id(), amount_a, None)?;
+        <token::Module<T>>::do_transfer(sender.clone(), token_b, Self::account_id(), amount_b, None)?;
+        if !locked_liquidity.is_zero() {
+            <token::Module<T>>::do_mint(Self::account_id(), pool.lp_token, locked_liquidity)?;
+        }
+        <token::Module<T>>::do_mint(sender.clone(), pool.lp_token, liquidity)?;
+
+        pool.set_reserves(token_a, new_reserve_a, new_reserve_b);
+        Pools::<T>::insert(pool.hash, pool.clone());
+
+        let (amount0, amount1) = if token_a == pool.token0 { (amount_a, amount_b) } else { (amount_b, amount_a) };
+        Self::deposit_event(RawEvent::LiquidityAdded(sender, pool.hash, amount0, amount1, liquidity));
+
+        Ok(())
+    }
+
+    fn do_remove_liquidity(sender: T::AccountId, token_a: T::Hash, token_b: T::Hash, liquidity: T::Balance,
+                           amount_a_min: T::Balance, amount_b_min: T::Balance) -> dispatch::DispatchResult {
+        let mut pool = Self::ensure_pool(token_a, token_b)?;
+        ensure!(liquidity > Zero::zero(), Error::<T>::ZeroAmount);
+
+        let (reserve_a, reserve_b) = pool.reserves(token_a);
+        let lp_supply = <token::Module<T>>::token(pool.lp_token).ok_or(Error::<T>::NoMatchingToken)?.total_supply;
+        ensure!(liquidity < lp_supply, Error::<T>::InsufficientLiquidity);
+
+        let amount_a: T::Balance = Self::from_u256(Self::u256(liquidity)? * Self::u256(reserve_a)? / Self::u256(lp_supply)?)?;
+        let amount_b: T::Balance = Self::from_u256(Self::u256(liquidity)? * Self::u256(reserve_b)? / Self::u256(lp_supply)?)?;
+        ensure!(amount_a > Zero::zero() && amount_b > Zero::zero(), Error::<T>::InsufficientLiquidity);
+        ensure!(amount_a >= amount_a_min && amount_b >= amount_b_min, Error::<T>::SlippageExceeded);
+
+        <token::Module<T>>::ensure_free_balance(sender.clone(), pool.lp_token, liquidity)?;
+        <token::Module<T>>::ensure_can_transfer(&Self::account_id(), token_a, &sender, amount_a)?;
+        <token::Module<T>>::ensure_can_transfer(&Self::account_id(), token_b, &sender, amount_b)?;
+
+        <token::Module<T>>::do_burn(sender.clone(), pool.lp_token, liquidity)?;
+        <token::Module<T>>::do_transfer(Self::account_id(), token_a, sender.clone(), amount_a, None)?;
+        <token::Module<T>>::do_transfer(Self::account_id(), token_b, sender.clone(), amount_b, None)?;
+
+        pool.set_reserves(token_a, reserve_a - amount_a, reserve_b - amount_b);
+        Pools::<T>::insert(pool.hash, pool.clone());
+
+        let (amount0, amount1) = if token_a == pool.token0 { (amount_a, amount_b) } else { (amount_b, amount_a) };
+        Self::deposit_event(RawEvent::LiquidityRemoved(sender, pool.hash, amount0, amount1, liquidity));
+
+        Ok(())
+    }
+
+    // the checks of a swap except the balance in of the sender, returns the new reserves in and out
+    fn ensure_can_swap(sender: &T::AccountId, pool: &Pool<T>, token_in: T::Hash, token_out: T::Hash,
+                       amount_in: T::Balance, amount_out: T::Balance) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
+        let (reserve_in, reserve_out) = pool.reserves(token_in);
+        let new_reserve_in = reserve_in.checked_add(&amount_in).ok_or(Error::<T>::AmountOverflow)?;
+        let new_reserve_out = reserve_out.checked_sub(&amount_out).ok_or(Error::<T>::InsufficientLiquidity)?;
+        <token::Module<T>>::ensure_transferable(token_in, sender)?;
+        <token::Module<T>>::ensure_transferable(token_in, &Self::account_id())?;
+        <token::Module<T>>::ensure_can_transfer(&Self::account_id(), token_out, sender, amount_out)?;
+
+        Ok((new_reserve_in, new_reserve_out))
+    }
+
+    fn do_swap(sender: T::AccountId, mut pool: Pool<T>, token_in: T::Hash, token_out: T::Hash,
+               amount_in: T::Balance, amount_out: T::Balance) -> dispatch::DispatchResult {
+        let (new_reserve_in, new_reserve_out) = Self::ensure_can_swap(&sender, &pool, token_in, token_out, amount_in, amount_out)?;
+        <token::Module<T>>::ensure_can_transfer(&sender, token_in, &Self::account_id(), amount_in)?;
+
+        <token::Module<T>>::do_transfer(sender.clone(), token_in, Self::account_id(), amount_in, None)?;
+        <token::Module<T>>::do_transfer(Self::account_id(), token_out, sender.clone(), amount_out, None)?;
+
+        pool.set_reserves(token_in, new_reserve_in, new_reserve_out);
+        Pools::<T>::insert(pool.hash, pool.clone());
+
+        Self::deposit_event(RawEvent::Swapped(sender, pool.hash, token_in, amount_in, token_out, amount_out));
+
+        Ok(())
+    }
+
+    /// All the checks of `do_swap_exact_in` except the balance of `token_in` of the sender, so that a caller
+    /// could validate the swap before the sender gets the amount in. Returns the amount of `token_out` bought
+    pub fn ensure_can_swap_exact_in(sender: &T::AccountId, token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance,
+                                    min_amount_out: T::Balance) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let pool = Self::ensure_pool(token_in, token_out)?;
+
+        let (reserve_in, reserve_out) = pool.reserves(token_in);
+        let amount_out = Self::get_amount_out(amount_in, reserve_in, reserve_out)?;
+        ensure!(amount_out > Zero::zero(), Error::<T>::InsufficientLiquidity);
+        ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+        Self::ensure_can_swap(sender, &pool, token_in, token_out, amount_in, amount_out)?;
+
+        Ok(amount_out)
+    }
+
+    /// Sell exactly `amount_in` of `token_in`, returns the amount of `token_out` bought
+    pub fn do_swap_exact_in(sender: T::AccountId, token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance,
+                            min_amount_out: T::Balance) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let amount_out = Self::ensure_can_swap_exact_in(&sender, token_in, token_out, amount_in, min_amount_out)?;
+        let pool = Self::ensure_pool(token_in, token_out)?;
+
+        Self::do_swap(sender, pool, token_in, token_out, amount_in, amount_out)?;
+
+        Ok(amount_out)
+    }
+
+    /// Buy exactly `amount_out` of `token_out`, returns the amount of `token_in` sold
+    pub fn do_swap_exact_out(sender: T::AccountId, token_in: T::Hash, token_out: T::Hash, amount_out: T::Balance,
+                             max_amount_in: T::Balance) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let pool = Self::ensure_pool(token_in, token_out)?;
+
+        let (reserve_in, reserve_out) = pool.reserves(token_in);
+        let amount_in = Self::get_amount_in(amount_out, reserve_in, reserve_out)?;
+        ensure!(amount_in <= max_amount_in, Error::<T>::SlippageExceeded);
+
+        Self::do_swap(sender, pool, token_in, token_out, amount_in, amount_out)?;
+
+        Ok(amount_in)
+    }
+}
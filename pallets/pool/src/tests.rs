@@ -0,0 +1,175 @@
+use super::*;
+
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize}};
+use sp_core::H256;
+
+type System = system::Module<Test>;
+type Balances = balances::Module<Test>;
+type TokenModule = token::Module<Test>;
+type PoolModule = super::Module<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into()
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		Balances::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Balances::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn run_to_block_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(System::block_number(), 0);
+		run_to_block(10);
+		assert_eq!(System::block_number(), 10);
+	});
+}
+
+#[test]
+fn create_pool_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token_a = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token_b = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_err!(PoolModule::create_pool(Origin::signed(alice), token_a, token_a), Error::<Test>::SameToken);
+		assert_err!(PoolModule::create_pool(Origin::signed(alice), token_a, H256::from_low_u64_be(0)), Error::<Test>::NoMatchingToken);
+
+		assert_ok!(PoolModule::create_pool(Origin::signed(alice), token_a, token_b));
+		assert_err!(PoolModule::create_pool(Origin::signed(bob), token_b, token_a), Error::<Test>::PoolAlreadyExists);
+
+		let pool = PoolModule::pool_of(token_b, token_a).unwrap();
+		assert_eq!(PoolModule::pool_hash_by_index(0), Some(pool.hash));
+		assert_eq!(PoolModule::pool_index(), 1);
+		assert!(pool.token0 < pool.token1);
+		assert_eq!(pool.reserves(token_a), (0, 0));
+
+		let lp_token = TokenModule::token(pool.lp_token).unwrap();
+		assert_eq!(lp_token.symbol, b"LP-0".to_vec());
+		assert_eq!(lp_token.total_supply, 0);
+		assert_eq!(TokenModule::owner(pool.lp_token), Some(PoolModule::account_id()));
+	});
+}
+
+#[test]
+fn liquidity_and_swap_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let pool_account = PoolModule::account_id();
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token_a = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token_b = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), token_b, alice, 1000000, None));
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_a, bob, 100000, None));
+
+		assert_err!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_b, 100000, 400000, 0, 0),
+			Error::<Test>::NoMatchingPool);
+		assert_ok!(PoolModule::create_pool(Origin::signed(alice), token_a, token_b));
+		let pool = PoolModule::pool_of(token_a, token_b).unwrap();
+
+		assert_err!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_b, 10, 40, 0, 0),
+			Error::<Test>::InsufficientInitialLiquidity);
+
+		// the first provider sets the price, MINIMUM_LIQUIDITY of the shares are locked
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_b, 100000, 400000, 0, 0));
+		assert_eq!(TokenModule::balance_of((alice, pool.lp_token)), 200000 - 1000);
+		assert_eq!(TokenModule::balance_of((pool_account, pool.lp_token)), 1000);
+		assert_eq!(PoolModule::pool(pool.hash).unwrap().reserves(token_a), (100000, 400000));
+
+		assert_err!(PoolModule::add_liquidity(Origin::signed(bob), token_a, token_b, 10000, 50000, 0, 45000),
+			Error::<Test>::SlippageExceeded);
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(bob), token_a, token_b, 10000, 50000, 0, 0));
+		assert_eq!(TokenModule::balance_of((bob, pool.lp_token)), 20000);
+		assert_eq!(TokenModule::balance_of((bob, token_a)), 90000);
+		assert_eq!(TokenModule::balance_of((bob, token_b)), 10000000 - 1000000 - 40000);
+		assert_eq!(PoolModule::pool(pool.hash).unwrap().reserves(token_b), (440000, 110000));
+
+		// 0.3% of the amount in is left in the pool
+		assert_err!(PoolModule::swap_exact_in(Origin::signed(bob), token_a, token_b, 1000, 3953), Error::<Test>::SlippageExceeded);
+		assert_ok!(PoolModule::swap_exact_in(Origin::signed(bob), token_a, token_b, 1000, 3952));
+		assert_eq!(TokenModule::balance_of((bob, token_a)), 89000);
+		assert_eq!(TokenModule::balance_of((bob, token_b)), 8960000 + 3952);
+
+		assert_err!(PoolModule::swap_exact_out(Origin::signed(bob), token_b, token_a, 2000, 8024), Error::<Test>::SlippageExceeded);
+		assert_err!(PoolModule::swap_exact_out(Origin::signed(bob), token_b, token_a, 111000, 1000000000), Error::<Test>::InsufficientLiquidity);
+		assert_ok!(PoolModule::swap_exact_out(Origin::signed(bob), token_b, token_a, 2000, 8025));
+		assert_eq!(TokenModule::balance_of((bob, token_a)), 91000);
+		assert_eq!(TokenModule::balance_of((bob, token_b)), 8963952 - 8025);
+
+		let reserves = PoolModule::pool(pool.hash).unwrap().reserves(token_a);
+		assert_eq!(reserves, (109000, 444073));
+		assert!(reserves.0 * reserves.1 >= 110000 * 440000);
+		assert_eq!(TokenModule::balance_of((pool_account, token_a)), 109000);
+		assert_eq!(TokenModule::balance_of((pool_account, token_b)), 444073);
+
+		assert_err!(PoolModule::swap_exact_in(Origin::signed(bob), token_a, H256::from_low_u64_be(0), 1000, 0), Error::<Test>::NoMatchingPool);
+		assert_err!(PoolModule::swap_exact_in(Origin::signed(bob), token_a, token_b, 0, 0), Error::<Test>::ZeroAmount);
+
+		// withdraw in proportion of the shares
+		assert_err!(PoolModule::remove_liquidity(Origin::signed(alice), token_a, token_b, 99000, 49051, 0),
+			Error::<Test>::SlippageExceeded);
+		assert_ok!(PoolModule::remove_liquidity(Origin::signed(alice), token_a, token_b, 99000, 49050, 199832));
+		assert_eq!(TokenModule::balance_of((alice, pool.lp_token)), 199000 - 99000);
+		assert_eq!(TokenModule::token(pool.lp_token).unwrap().total_supply, 220000 - 99000);
+		assert_eq!(TokenModule::balance_of((alice, token_a)), 21000000 - 100000 - 100000 + 49050);
+		assert_eq!(TokenModule::balance_of((alice, token_b)), 1000000 - 400000 + 199832);
+		assert_eq!(PoolModule::pool(pool.hash).unwrap().reserves(token_a), (109000 - 49050, 444073 - 199832));
+
+		assert_err!(PoolModule::remove_liquidity(Origin::signed(bob), token_a, token_b, 20001, 0, 0),
+			token::Error::<Test>::BalanceNotEnough);
+	});
+}
+
+#[test]
+fn add_liquidity_checks_before_writes_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token_a = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token_b = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), token_b, alice, 1000000, None));
+		assert_ok!(PoolModule::create_pool(Origin::signed(alice), token_a, token_b));
+		let pool = PoolModule::pool_of(token_a, token_b).unwrap();
+
+		assert_ok!(TokenModule::set_role(Origin::signed(bob), token_b, token::TokenRole::Freezer, Some(bob)));
+		assert_ok!(TokenModule::freeze_account(Origin::signed(bob), token_b, alice));
+
+		// the second leg fails, nothing of the first leg or of the locked shares is written
+		assert_err!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_b, 100000, 400000, 0, 0),
+			token::Error::<Test>::AccountFrozen);
+		assert_eq!(TokenModule::token(pool.lp_token).unwrap().total_supply, 0);
+		assert_eq!(TokenModule::balance_of((PoolModule::account_id(), pool.lp_token)), 0);
+		assert_eq!(TokenModule::balance_of((alice, token_a)), 21000000);
+		assert_eq!(PoolModule::pool(pool.hash).unwrap().reserves(token_a), (0, 0));
+
+		assert_ok!(TokenModule::thaw_account(Origin::signed(bob), token_b, alice));
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_b, 100000, 400000, 0, 0));
+		assert_eq!(TokenModule::token(pool.lp_token).unwrap().total_supply, 200000);
+	});
+}
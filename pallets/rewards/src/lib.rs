@@ -0,0 +1,223 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_core::U256;
+use sp_std::{prelude::*, collections::btree_map::BTreeMap};
+use sp_runtime::{Permill, SaturatedConversion, traits::{Zero, Saturating}};
+
+use frame_support::{decl_module, decl_storage, decl_event, decl_error, StorageValue, StorageMap,
+                    ensure, dispatch, traits::Get, weights::Weight};
+
+use system::{ensure_signed, ensure_root};
+
+use trade::OrderType;
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Trait: trade::Trait + token::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Resting orders priced within this distance of the mid price earn the rewards
+    type RewardBand: Get<Permill>;
+    /// Max number of the price levels of each side sampled per block
+    type MaxSampledLevels: Get<u32>;
+    /// Max number of the orders of each side sampled per block
+    type MaxSampledOrders: Get<u32>;
+}
+
+decl_error! {
+	/// Error for the rewards module.
+	pub enum Error for Module<T: Trait> {
+		/// No matching trade pair
+		NoMatchingTradePair,
+		/// No matching token
+		NoMatchingToken,
+		/// The reward token is not set yet
+		RewardTokenNotSet,
+		/// Nothing to claim
+		NoRewards,
+	}
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as RewardsModule {
+		/// The token minted to pay the rewards
+		RewardToken get(fn reward_token): Option<T::Hash>;
+
+		/// Reward emission of the trade pair per block
+		Emissions get(fn emission): map hasher(blake2_128_concat) T::Hash => T::Balance;
+		/// Trade pairs with an emission
+		RewardedTradePairs get(fn rewarded_trade_pairs): Vec<T::Hash>;
+
+		/// Rewards earned and not claimed yet
+		PendingRewards get(fn pending_rewards): map hasher(blake2_128_concat) T::AccountId => T::Balance;
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+	{
+		// (tokenHash)
+		RewardTokenSet(Hash),
+
+		// (tradePairHash, emissionPerBlock)
+		EmissionSet(Hash, Balance),
+
+		// (accountId, tokenHash, amount)
+		RewardsClaimed(AccountId, Hash, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		type Error = Error<T>;
+
+		#[weight = 1_000_000]
+		pub fn set_reward_token(origin, token_hash: T::Hash) -> dispatch::DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<token::Module<T>>::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+
+			RewardToken::<T>::put(token_hash);
+			Self::deposit_event(RawEvent::RewardTokenSet(token_hash));
+
+			Ok(())
+		}
+
+		/// Zero emission stops the rewards of the trade pair
+		#[weight = 1_000_000]
+		pub fn set_emission(origin, tp_hash: T::Hash, emission: T::Balance) -> dispatch::DispatchResult {
+			ensure_root(origin)?;
+			ensure!(<trade::Module<T>>::trade_pair(tp_hash).is_some(), Error::<T>::NoMatchingTradePair);
+
+			let mut tp_hashes = Self::rewarded_trade_pairs();
+			if emission.is_zero() {
+				tp_hashes.retain(|&h| h != tp_hash);
+				Emissions::<T>::remove(tp_hash);
+			} else {
+				if !tp_hashes.contains(&tp_hash) {
+					tp_hashes.push(tp_hash);
+				}
+				Emissions::<T>::insert(tp_hash, emission);
+			}
+			RewardedTradePairs::<T>::put(tp_hashes);
+
+			Self::deposit_event(RawEvent::EmissionSet(tp_hash, emission));
+
+			Ok(())
+		}
+
+		#[weight = 1_000_000]
+		pub fn claim_rewards(origin) -> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			let token_hash = Self::reward_token().ok_or(Error::<T>::RewardTokenNotSet)?;
+			let amount = Self::pending_rewards(sender.clone());
+			ensure!(!amount.is_zero(), Error::<T>::NoRewards);
+
+			<token::Module<T>>::do_mint(sender.clone(), token_hash, amount)?;
+			PendingRewards::<T>::remove(sender.clone());
+
+			Self::deposit_event(RawEvent::RewardsClaimed(sender, token_hash, amount));
+
+			Ok(())
+		}
+
+		// on_finalize reads the sampled levels and orders of both sides of each rewarded trade pair,
+		// with the pause and freeze state of the owners of the orders, and credits them
+		fn on_initialize(_block_number: T::BlockNumber) -> Weight {
+			let reads = T::MaxSampledLevels::get() as Weight + 6 * T::MaxSampledOrders::get() as Weight;
+			Self::rewarded_trade_pairs().len() as Weight * 2 * reads * 100_000
+		}
+
+		fn on_finalize(_block_number: T::BlockNumber) {
+			for tp_hash in Self::rewarded_trade_pairs() {
+				Self::distribute(tp_hash, Self::emission(tp_hash));
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+    /// The quote amount of the resting orders priced within RewardBand of the mid price, per owner.
+    /// At most MaxSampledLevels levels and MaxSampledOrders orders of each side are sampled, the orders
+    /// the matching would skip for a paused token or a frozen owner earn nothing
+    pub fn sample(tp_hash: T::Hash) -> Vec<(T::AccountId, T::Balance)> {
+        let tp = match <trade::Module<T>>::trade_pair(tp_hash) {
+            Some(tp) => tp,
+            None => return Vec::new(),
+        };
+        let max_levels = T::MaxSampledLevels::get() as usize;
+        let bids = <trade::Module<T>>::top_market_levels(tp_hash, OrderType::Sell, max_levels);
+        let asks = <trade::Module<T>>::top_market_levels(tp_hash, OrderType::Buy, max_levels);
+
+        let (bid, ask): (u128, u128) = match (bids.first(), asks.first()) {
+            (Some(&(bid, _)), Some(&(ask, _))) => (bid.into(), ask.into()),
+            // no mid price with one side only
+            _ => return Vec::new(),
+        };
+
+        let mid = bid + ask.saturating_sub(bid) / 2;
+        let band = T::RewardBand::get() * mid;
+
+        let bids = bids.into_iter().map(|(price, _)| price).take_while(|&price| Into::<u128>::into(price) >= mid - band);
+        let asks = asks.into_iter().map(|(price, _)| price).take_while(|&price| Into::<u128>::into(price) <= mid.saturating_add(band));
+
+        let mut sizes = BTreeMap::new();
+        Self::sample_levels(tp_hash, &tp, bids, &mut sizes);
+        Self::sample_levels(tp_hash, &tp, asks, &mut sizes);
+
+        sizes.into_iter().collect()
+    }
+
+    // add the quote amounts of the orders at the price levels up to MaxSampledOrders orders, the buy orders
+    // buy quote, the sell orders sell quote
+    fn sample_levels(tp_hash: T::Hash, tp: &trade::TradePair<T>, prices: impl Iterator<Item = T::Price>, sizes: &mut BTreeMap<T::AccountId, T::Balance>) {
+        let mut orders = 0;
+        for price in prices {
+            for order_hash in <trade::Module<T>>::market_orders_at(tp_hash, price) {
+                if orders >= T::MaxSampledOrders::get() {
+                    return;
+                }
+                orders += 1;
+
+                let order = match <trade::Module<T>>::order(order_hash) {
+                    Some(order) if !order.is_finished() && <trade::Module<T>>::is_fillable(tp, &order) => order,
+                    _ => continue,
+                };
+                let size = match order.otype {
+                    OrderType::Buy => order.remained_buy_amount,
+                    OrderType::Sell => order.remained_sell_amount,
+                };
+
+                let total = sizes.entry(order.owner).or_insert_with(Zero::zero);
+                *total = total.saturating_add(size);
+            }
+        }
+    }
+
+    // share the emission of the block pro rata to the sampled sizes
+    fn distribute(tp_hash: T::Hash, emission: T::Balance) {
+        let sizes = Self::sample(tp_hash);
+        let total = sizes.iter().fold(0u128, |total, (_, size)| total.saturating_add((*size).saturated_into::<u128>()));
+        if total == 0 {
+            return;
+        }
+
+        let emission = U256::from(emission.saturated_into::<u128>());
+        for (owner, size) in sizes {
+            let share = emission * U256::from(size.saturated_into::<u128>()) / U256::from(total);
+            let share: T::Balance = share.low_u128().saturated_into();
+            if !share.is_zero() {
+                PendingRewards::<T>::mutate(owner, |pending| *pending = pending.saturating_add(share));
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use super::*;
+
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize}};
+use sp_core::H256;
+use sp_runtime::traits::BadOrigin;
+
+type System = system::Module<Test>;
+type Balances = balances::Module<Test>;
+type TokenModule = token::Module<Test>;
+type TradeModule = trade::Module<Test>;
+type RewardsModule = super::Module<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into()
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		RewardsModule::on_finalize(System::block_number());
+		TradeModule::on_finalize(System::block_number());
+		Balances::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Balances::on_initialize(System::block_number());
+		TradeModule::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn run_to_block_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(System::block_number(), 0);
+		run_to_block(10);
+		assert_eq!(System::block_number(), 10);
+	});
+}
+
+#[test]
+fn rewards_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"88".to_vec(), 10000000));
+		let reward = TokenModule::owned_token((alice, 1)).unwrap();
+
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, bob, 10000, None));
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		// the mid price is 0.98, the band is [0.882, 1.078]
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, trade::OrderType::Buy, 96_000_000, 960));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 100_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 120_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Buy, 96_000_000, 480));
+
+		assert_eq!(RewardsModule::sample(tp_hash), vec![(alice, 1000), (bob, 1500)]);
+
+		// MaxSampledOrders is 2, the third buy order is not sampled
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, trade::OrderType::Buy, 95_000_000, 950));
+		assert_eq!(RewardsModule::sample(tp_hash), vec![(alice, 1000), (bob, 1500)]);
+
+		// the orders of a frozen owner are skipped like by the matching
+		assert_ok!(TokenModule::freeze_account(Origin::signed(alice), base, bob));
+		assert_eq!(RewardsModule::sample(tp_hash), vec![(alice, 1000)]);
+		assert_ok!(TokenModule::thaw_account(Origin::signed(alice), base, bob));
+		assert_eq!(RewardsModule::sample(tp_hash), vec![(alice, 1000), (bob, 1500)]);
+
+		assert_err!(RewardsModule::set_emission(Origin::signed(alice), tp_hash, 100), BadOrigin);
+		assert_err!(RewardsModule::set_emission(system::RawOrigin::Root.into(), H256::from_low_u64_be(0), 100), Error::<Test>::NoMatchingTradePair);
+		assert_ok!(RewardsModule::set_emission(system::RawOrigin::Root.into(), tp_hash, 100));
+		assert_eq!(RewardsModule::rewarded_trade_pairs(), vec![tp_hash]);
+
+		run_to_block(12);
+		assert_eq!(RewardsModule::pending_rewards(alice), 80);
+		assert_eq!(RewardsModule::pending_rewards(bob), 120);
+
+		assert_err!(RewardsModule::claim_rewards(Origin::signed(alice)), Error::<Test>::RewardTokenNotSet);
+		assert_err!(RewardsModule::set_reward_token(system::RawOrigin::Root.into(), H256::from_low_u64_be(0)), Error::<Test>::NoMatchingToken);
+		assert_ok!(RewardsModule::set_reward_token(system::RawOrigin::Root.into(), reward));
+
+		assert_ok!(RewardsModule::claim_rewards(Origin::signed(alice)));
+		assert_eq!(TokenModule::balance_of((alice, reward)), 10000000 + 80);
+		assert_eq!(TokenModule::token(reward).unwrap().total_supply, 10000000 + 80);
+		assert_eq!(RewardsModule::pending_rewards(alice), 0);
+		assert_err!(RewardsModule::claim_rewards(Origin::signed(alice)), Error::<Test>::NoRewards);
+
+		// no mid price without the sell orders
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), TradeModule::owned_order((bob, 0)).unwrap()));
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), TradeModule::owned_order((bob, 1)).unwrap()));
+		assert_eq!(RewardsModule::sample(tp_hash), vec![]);
+		run_to_block(14);
+		assert_eq!(RewardsModule::pending_rewards(alice), 0);
+		assert_eq!(RewardsModule::pending_rewards(bob), 120);
+
+		assert_ok!(RewardsModule::set_emission(system::RawOrigin::Root.into(), tp_hash, 0));
+		assert_eq!(RewardsModule::rewarded_trade_pairs(), vec![]);
+		assert_eq!(RewardsModule::emission(tp_hash), 0);
+
+		// the rewards are kept while the reward token is paused or the account is frozen
+		assert_ok!(TokenModule::pause_token(Origin::signed(alice), reward));
+		assert_err!(RewardsModule::claim_rewards(Origin::signed(bob)), token::Error::<Test>::TokenPaused);
+		assert_ok!(TokenModule::unpause_token(Origin::signed(alice), reward));
+		assert_ok!(TokenModule::freeze_account(Origin::signed(alice), reward, bob));
+		assert_err!(RewardsModule::claim_rewards(Origin::signed(bob)), token::Error::<Test>::AccountFrozen);
+		assert_eq!(RewardsModule::pending_rewards(bob), 120);
+		assert_ok!(TokenModule::thaw_account(Origin::signed(alice), reward, bob));
+
+		assert_ok!(RewardsModule::claim_rewards(Origin::signed(bob)));
+		assert_eq!(TokenModule::balance_of((bob, reward)), 120);
+	});
+}
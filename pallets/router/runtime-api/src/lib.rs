@@ -0,0 +1,26 @@
+//! Runtime API definition for the router pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+use sp_runtime::DispatchError;
+
+pub use router::SwapQuote;
+
+sp_api::decl_runtime_apis! {
+	pub trait RouterApi<Hash, Price, Balance> where
+		Hash: Codec,
+		Price: Codec,
+		Balance: Codec,
+	{
+		/// Split the swap between the market orders and the pool for the best average price
+		fn quote_swap(token_in: Hash, token_out: Hash, amount_in: Balance) -> Result<SwapQuote<Price, Balance>, DispatchError>;
+
+		/// The quotes of each hop of the path, the amount in of a hop is the amount out of the previous one
+		fn quote_swap_path(path: Vec<Hash>, amount_in: Balance) -> Result<Vec<SwapQuote<Price, Balance>>, DispatchError>;
+
+		/// The path through the trade pairs and the pools with the most amount out, and the amount out
+		fn best_path(token_in: Hash, token_out: Hash, amount_in: Balance) -> Option<(Vec<Hash>, Balance)>;
+	}
+}
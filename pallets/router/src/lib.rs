@@ -0,0 +1,488 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_core::U256;
+use sp_std::{prelude::*, result, collections::btree_map::BTreeMap};
+use sp_runtime::traits::Zero;
+
+use frame_support::{decl_module, decl_event, decl_error, ensure, dispatch, traits::Get,
+                    weights::{Weight, FunctionOf, DispatchClass}};
+
+use system::ensure_signed;
+use codec::{Encode, Decode};
+
+use token::MultiCurrency;
+use trade::{OrderType, OrderQuote};
+
+#[cfg(test)]
+mod mock;
+
+#[cfg(test)]
+mod tests;
+
+pub trait Trait: pool::Trait + trade::Trait {
+    type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// Max number of tokens in a swap path, including the first and the last
+    type MaxPathLength: Get<u32>;
+    /// Max number of price levels of the market orders a swap walks in each hop
+    type MaxRouteLevels: Get<u32>;
+    /// Max number of hops `best_path` quotes while searching
+    type MaxPathSearchQuotes: Get<u32>;
+}
+
+/// A swap split between the market orders of the trade pair and the pool
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SwapQuote<Price, Balance> {
+    /// The market order part with the per level fill breakdown, None if it takes nothing
+    pub book: Option<OrderQuote<Price, Balance>>,
+    pub book_amount_in: Balance,
+    pub book_amount_out: Balance,
+    pub pool_amount_in: Balance,
+    pub pool_amount_out: Balance,
+    pub amount_in: Balance,
+    pub amount_out: Balance,
+    /// Amount in which neither the market orders nor the pool can take
+    pub remained_amount_in: Balance,
+}
+
+// the market order part of a swap
+struct BookRoute<T> where T: Trait {
+    tp_hash: T::Hash,
+    /// The number of the best price levels the market order reaches
+    levels: usize,
+    base: T::Hash,
+    quote: T::Hash,
+    otype: OrderType,
+    price: T::Price,
+    sell_amount: T::Balance,
+}
+
+// the state of a `best_path` search
+struct PathSearch<T> where T: Trait {
+    /// Token => the tokens which trade with it through a trade pair or a pool
+    adjacency: BTreeMap<T::Hash, Vec<T::Hash>>,
+    token_out: T::Hash,
+    /// The hops which can still be quoted
+    quotes_left: u32,
+    /// Token => (hops, amount) of the paths which reached it, none of them reaches it with more hops and less amount
+    reached: BTreeMap<T::Hash, Vec<(usize, T::Balance)>>,
+    best: Option<(Vec<T::Hash>, T::Balance)>,
+}
+
+decl_error! {
+	/// Error for the router module.
+	pub enum Error for Module<T: Trait> {
+		/// Token in equals to token out
+		SameToken,
+		/// Amount in is zero
+		ZeroAmount,
+		/// Neither a trade pair nor a pool of the two tokens
+		NoRoute,
+		/// The market orders and the pool can not take all of the amount in
+		InsufficientLiquidity,
+		/// Amount out is less than the minimum
+		SlippageExceeded,
+		/// The path has less than two tokens or a token more than once
+		InvalidPath,
+		/// The path has more tokens than MaxPathLength
+		PathTooLong,
+		/// The swap would match the market orders of the sender
+		SelfMatch,
+	}
+}
+
+decl_event!(
+	pub enum Event<T>
+	where
+		<T as system::Trait>::AccountId,
+		<T as system::Trait>::Hash,
+		<T as balances::Trait>::Balance,
+	{
+		// (accountId, tokenInHash, amountIn, tokenOutHash, amountOut, bookAmountIn, poolAmountIn)
+		Swapped(AccountId, Hash, Balance, Hash, Balance, Balance, Balance),
+
+		// (accountId, path, amountIn, amountOut)
+		PathSwapped(AccountId, Vec<Hash>, Balance, Balance),
+	}
+);
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn deposit_event() = default;
+
+		type Error = Error<T>;
+
+		/// Sell exactly `amount_in` of `token_in` at the market orders and the pool, whichever is better
+		#[weight = Module::<T>::swap_weight(1)]
+		pub fn swap(origin, token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance, min_amount_out: T::Balance)
+			-> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_swap(sender, token_in, token_out, amount_in, min_amount_out)?;
+			Ok(())
+		}
+
+		/// Sell exactly `amount_in` of the first token of the path for the last one, swapping through each
+		/// token in between
+		#[weight = FunctionOf(
+			|args: (&Vec<T::Hash>, &T::Balance, &T::Balance)| Module::<T>::swap_weight(args.0.len().saturating_sub(1)),
+			DispatchClass::Normal,
+			true
+		)]
+		pub fn swap_path(origin, path: Vec<T::Hash>, amount_in: T::Balance, min_amount_out: T::Balance)
+			-> dispatch::DispatchResult {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_swap_path(sender, path, amount_in, min_amount_out)?;
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+    // each hop reads at most MaxRouteLevels price levels of the market orders
+    fn swap_weight(hops: usize) -> Weight {
+        let hop_weight = 1_000_000 + T::MaxRouteLevels::get() as Weight * 100_000;
+        (hops as Weight).saturating_mul(hop_weight)
+    }
+
+    // (trade pair hash, base, quote, order type) of the market order selling token_in
+    fn book_of(token_in: T::Hash, token_out: T::Hash) -> Option<(T::Hash, T::Hash, T::Hash, OrderType)> {
+        if let Some(tp_hash) = <trade::Module<T>>::trade_pair_hash_by_base_quote((token_in, token_out)) {
+            return Some((tp_hash, token_in, token_out, OrderType::Buy));
+        }
+
+        <trade::Module<T>>::trade_pair_hash_by_base_quote((token_out, token_in))
+            .map(|tp_hash| (tp_hash, token_out, token_in, OrderType::Sell))
+    }
+
+    // the rate (out per in) of a price level as numerator and denominator
+    fn level_rate(otype: OrderType, price: T::Price, price_factor: u128) -> (U256, U256) {
+        let price: u128 = price.into();
+        match otype {
+            OrderType::Buy => (U256::from(price_factor), U256::from(price)),
+            OrderType::Sell => (U256::from(price), U256::from(price_factor)),
+        }
+    }
+
+    // walk at most MaxRouteLevels price levels best first, the pool takes the amount in while its marginal rate
+    // is better than the level's, then the level takes up to what its maker orders buy
+    fn route(token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance)
+        -> result::Result<(Option<BookRoute<T>>, SwapQuote<T::Price, T::Balance>), dispatch::DispatchError> {
+        ensure!(token_in != token_out, Error::<T>::SameToken);
+        ensure!(amount_in > Zero::zero(), Error::<T>::ZeroAmount);
+
+        let book = Self::book_of(token_in, token_out);
+        let reserves = <pool::Module<T>>::pool_of(token_in, token_out)
+            .map(|pool| pool.reserves(token_in))
+            .filter(|&(reserve_in, reserve_out)| reserve_in > Zero::zero() && reserve_out > Zero::zero());
+        ensure!(book.is_some() || reserves.is_some(), Error::<T>::NoRoute);
+
+        let mut remained_amount_in = amount_in;
+        let mut book_sell_amount: T::Balance = Zero::zero();
+        let mut book_price = None;
+        let mut book_levels = 0;
+        let mut pool_amount_in: T::Balance = Zero::zero();
+
+        if let Some((tp_hash, _, _, otype)) = book {
+            let tp = <trade::Module<T>>::trade_pair(tp_hash).ok_or(Error::<T>::NoRoute)?;
+
+            let levels = <trade::Module<T>>::iter_market_levels(tp_hash, otype).take(T::MaxRouteLevels::get() as usize);
+            for (index, (price, amount)) in levels.enumerate() {
+                if let Some((reserve_in, reserve_out)) = reserves {
+                    let (numerator, denominator) = Self::level_rate(otype, price, tp.price_factor());
+                    let pool_target = <pool::Module<T>>::get_amount_in_to_rate(reserve_in, reserve_out, numerator, denominator)?;
+                    if pool_target > pool_amount_in {
+                        let take = (pool_target - pool_amount_in).min(remained_amount_in);
+                        pool_amount_in = pool_amount_in + take;
+                        remained_amount_in = remained_amount_in - take;
+                    }
+                }
+
+                if remained_amount_in.is_zero() {
+                    break;
+                }
+
+                let take = amount.min(remained_amount_in);
+                book_sell_amount = book_sell_amount + take;
+                remained_amount_in = remained_amount_in - take;
+                book_price = Some(price);
+                book_levels = index + 1;
+
+                if remained_amount_in.is_zero() {
+                    break;
+                }
+            }
+        }
+
+        let mut book_route = None;
+        let mut book_quote = None;
+        let mut book_amount_in = Zero::zero();
+        let mut book_amount_out = Zero::zero();
+
+        if let (Some((tp_hash, base, quote, otype)), Some(price)) = (book, book_price) {
+            match <trade::Module<T>>::quote_market_order(tp_hash, otype, price, book_sell_amount) {
+                Ok(order_quote) => {
+                    book_amount_in = book_sell_amount - order_quote.remained_sell_amount;
+                    book_amount_out = match otype {
+                        OrderType::Buy => order_quote.quote_amount,
+                        OrderType::Sell => order_quote.base_amount,
+                    };
+                    // the amount too small to buy anything at the levels
+                    remained_amount_in = remained_amount_in + order_quote.remained_sell_amount;
+
+                    if !book_amount_in.is_zero() {
+                        book_route = Some(BookRoute {
+                            tp_hash, levels: book_levels, base, quote, otype, price, sell_amount: book_sell_amount,
+                        });
+                        book_quote = Some(order_quote);
+                    }
+                },
+                Err(_) if reserves.is_some() => {
+                    remained_amount_in = remained_amount_in + book_sell_amount;
+                },
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut pool_amount_out = Zero::zero();
+        if let Some((reserve_in, reserve_out)) = reserves {
+            pool_amount_in = pool_amount_in + remained_amount_in;
+            remained_amount_in = Zero::zero();
+
+            if !pool_amount_in.is_zero() {
+                pool_amount_out = <pool::Module<T>>::get_amount_out(pool_amount_in, reserve_in, reserve_out)?;
+            }
+        }
+
+        Ok((book_route, SwapQuote {
+            book: book_quote,
+            book_amount_in,
+            book_amount_out,
+            pool_amount_in,
+            pool_amount_out,
+            amount_in: book_amount_in + pool_amount_in,
+            amount_out: book_amount_out + pool_amount_out,
+            remained_amount_in,
+        }))
+    }
+
+    /// Split `amount_in` between the market orders and the pool for the best average price, without changing any state
+    pub fn quote_swap(token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance)
+        -> result::Result<SwapQuote<T::Price, T::Balance>, dispatch::DispatchError> {
+        Self::route(token_in, token_out, amount_in).map(|(_, quote)| quote)
+    }
+
+    fn ensure_fillable(quote: &SwapQuote<T::Price, T::Balance>) -> dispatch::DispatchResult {
+        ensure!(quote.remained_amount_in.is_zero(), Error::<T>::InsufficientLiquidity);
+        ensure!(quote.pool_amount_in.is_zero() || !quote.pool_amount_out.is_zero(), Error::<T>::InsufficientLiquidity);
+        ensure!(!quote.amount_out.is_zero(), Error::<T>::InsufficientLiquidity);
+        Ok(())
+    }
+
+    // the checks of the quoted swap except the balance in of the sender, the pool part runs after the market
+    // orders are filled so it must not fail then
+    fn ensure_can_execute(sender: &T::AccountId, token_in: T::Hash, token_out: T::Hash,
+                          quote: &SwapQuote<T::Price, T::Balance>) -> dispatch::DispatchResult {
+        T::Currency::ensure_transferable(token_in, sender)?;
+        T::Currency::ensure_transferable(token_out, sender)?;
+        T::Currency::ensure_can_deposit(token_out, sender)?;
+        if !quote.pool_amount_in.is_zero() {
+            <pool::Module<T>>::ensure_can_swap_exact_in(sender, token_in, token_out, quote.pool_amount_in, quote.pool_amount_out)?;
+        }
+        Ok(())
+    }
+
+    // the quote simulates a taker of no account, so the swap must not reach the market orders of the sender
+    fn ensure_no_self_match(sender: &T::AccountId, book_route: &Option<BookRoute<T>>) -> dispatch::DispatchResult {
+        if let Some(route) = book_route {
+            for (price, _) in <trade::Module<T>>::iter_market_levels(route.tp_hash, route.otype).take(route.levels) {
+                for order_hash in <trade::Module<T>>::market_orders_at(route.tp_hash, price) {
+                    let owned = <trade::Module<T>>::order(order_hash).map_or(false, |order| &order.owner == sender);
+                    ensure!(!owned, Error::<T>::SelfMatch);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // fill the quoted swap, the caller has done all the checks. The market orders fill as quoted, since the
+    // quote and the matching share the per level fill computation and nothing changes the market in between
+    fn execute(sender: T::AccountId, token_in: T::Hash, token_out: T::Hash, book_route: Option<BookRoute<T>>,
+               quote: &SwapQuote<T::Price, T::Balance>) -> dispatch::DispatchResult {
+        let (sold, bought) = match book_route {
+            Some(route) => <trade::Module<T>>::do_create_market_order(sender.clone(), route.base, route.quote, route.otype,
+                                                                      route.price, route.sell_amount)?,
+            None => (Zero::zero(), Zero::zero()),
+        };
+
+        if !quote.pool_amount_in.is_zero() {
+            <pool::Module<T>>::do_swap_exact_in(sender.clone(), token_in, token_out, quote.pool_amount_in, quote.pool_amount_out)?;
+        }
+
+        Self::deposit_event(RawEvent::Swapped(sender, token_in, sold + quote.pool_amount_in, token_out,
+                                              bought + quote.pool_amount_out, sold, quote.pool_amount_in));
+
+        Ok(())
+    }
+
+    /// Sell exactly `amount_in` of `token_in` as quoted by `quote_swap`, returns the amount of `token_out` bought
+    pub fn do_swap(sender: T::AccountId, token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance,
+                   min_amount_out: T::Balance) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let (book_route, quote) = Self::route(token_in, token_out, amount_in)?;
+
+        Self::ensure_fillable(&quote)?;
+        ensure!(quote.amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+        T::Currency::ensure_can_withdraw(token_in, &sender, amount_in)?;
+        Self::ensure_can_execute(&sender, token_in, token_out, &quote)?;
+        Self::ensure_no_self_match(&sender, &book_route)?;
+
+        // the market orders and the pool do not depend on each other, the market orders fill as quoted and
+        // the pool part is checked above, before the first write
+        Self::execute(sender, token_in, token_out, book_route, &quote)?;
+
+        Ok(quote.amount_out)
+    }
+
+    // route each hop of the path with the amount out of the previous one
+    fn route_path(path: &[T::Hash], amount_in: T::Balance)
+        -> result::Result<Vec<(Option<BookRoute<T>>, SwapQuote<T::Price, T::Balance>)>, dispatch::DispatchError> {
+        ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+        ensure!(path.len() <= T::MaxPathLength::get() as usize, Error::<T>::PathTooLong);
+        for (i, token) in path.iter().enumerate() {
+            ensure!(!path[i + 1..].contains(token), Error::<T>::InvalidPath);
+        }
+
+        let mut hops = Vec::new();
+        let mut amount = amount_in;
+        for hop in path.windows(2) {
+            let (book_route, quote) = Self::route(hop[0], hop[1], amount)?;
+            Self::ensure_fillable(&quote)?;
+            amount = quote.amount_out;
+            hops.push((book_route, quote));
+        }
+
+        Ok(hops)
+    }
+
+    /// Quotes of each hop of the path, without changing any state
+    pub fn quote_swap_path(path: Vec<T::Hash>, amount_in: T::Balance)
+        -> result::Result<Vec<SwapQuote<T::Price, T::Balance>>, dispatch::DispatchError> {
+        Self::route_path(&path, amount_in).map(|hops| hops.into_iter().map(|(_, quote)| quote).collect())
+    }
+
+    /// Sell exactly `amount_in` of the first token of the path as quoted by `quote_swap_path`,
+    /// returns the amount of the last token bought
+    pub fn do_swap_path(sender: T::AccountId, path: Vec<T::Hash>, amount_in: T::Balance,
+                        min_amount_out: T::Balance) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let hops = Self::route_path(&path, amount_in)?;
+
+        let amount_out = hops.last().map(|(_, quote)| quote.amount_out).unwrap_or_else(Zero::zero);
+        ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+        T::Currency::ensure_can_withdraw(path[0], &sender, amount_in)?;
+        // the amount in of a later hop is bought by the previous one, everything else of every hop is checked
+        // before the first one fills, so the path does not stop half way
+        for (hop, (book_route, quote)) in path.windows(2).zip(hops.iter()) {
+            Self::ensure_can_execute(&sender, hop[0], hop[1], quote)?;
+            Self::ensure_no_self_match(&sender, book_route)?;
+        }
+
+        // a token shows up in the path only once, so no two hops share a trade pair or a pool,
+        // and each hop fills exactly as quoted with the amount bought by the previous one
+        for (hop, (book_route, quote)) in path.windows(2).zip(hops.into_iter()) {
+            Self::execute(sender.clone(), hop[0], hop[1], book_route, &quote)?;
+        }
+
+        Self::deposit_event(RawEvent::PathSwapped(sender, path, amount_in, amount_out));
+
+        Ok(amount_out)
+    }
+
+    // the tokens which trade with each other through a trade pair or a pool, read once for a search
+    fn adjacency() -> BTreeMap<T::Hash, Vec<T::Hash>> {
+        let mut pairs = Vec::new();
+        for index in 0 .. <trade::Module<T>>::trade_pair_index() {
+            if let Some(tp) = <trade::Module<T>>::trade_pair_hash_by_index(index).and_then(<trade::Module<T>>::trade_pair) {
+                pairs.push((tp.base(), tp.quote()));
+            }
+        }
+        for index in 0 .. <pool::Module<T>>::pool_index() {
+            if let Some(pool) = <pool::Module<T>>::pool_hash_by_index(index).and_then(<pool::Module<T>>::pool) {
+                pairs.push((pool.token0, pool.token1));
+            }
+        }
+
+        let mut adjacency = BTreeMap::new();
+        for (a, b) in pairs {
+            for &(token, other) in [(a, b), (b, a)].iter() {
+                let neighbors = adjacency.entry(token).or_insert_with(Vec::new);
+                if !neighbors.contains(&other) {
+                    neighbors.push(other);
+                }
+            }
+        }
+
+        adjacency
+    }
+
+    // extend the path hop by hop with the amount bought so far, a path which reaches a token with no fewer hops
+    // and no more amount than another one is not extended
+    fn search_path(search: &mut PathSearch<T>, path: &mut Vec<T::Hash>, amount: T::Balance) {
+        let last = match path.last() {
+            Some(&last) => last,
+            None => return,
+        };
+
+        if last == search.token_out {
+            if search.best.as_ref().map_or(true, |(_, best_amount_out)| amount > *best_amount_out) {
+                search.best = Some((path.clone(), amount));
+            }
+            return;
+        }
+
+        if path.len() >= T::MaxPathLength::get() as usize {
+            return;
+        }
+
+        let neighbors = search.adjacency.get(&last).cloned().unwrap_or_default();
+        for next in neighbors {
+            if path.contains(&next) {
+                continue;
+            }
+            if search.quotes_left == 0 {
+                return;
+            }
+            search.quotes_left -= 1;
+
+            let amount_out = match Self::route(last, next, amount) {
+                Ok((_, quote)) if Self::ensure_fillable(&quote).is_ok() => quote.amount_out,
+                _ => continue,
+            };
+
+            let hops = path.len();
+            let reached = search.reached.entry(next).or_insert_with(Vec::new);
+            if reached.iter().any(|&(reached_hops, reached_amount)| reached_hops <= hops && reached_amount >= amount_out) {
+                continue;
+            }
+            reached.push((hops, amount_out));
+
+            path.push(next);
+            Self::search_path(search, path, amount_out);
+            path.pop();
+        }
+    }
+
+    /// The path with the most amount out within MaxPathLength tokens, and the amount out. The search quotes at
+    /// most MaxPathSearchQuotes hops
+    pub fn best_path(token_in: T::Hash, token_out: T::Hash, amount_in: T::Balance) -> Option<(Vec<T::Hash>, T::Balance)> {
+        let mut search = PathSearch::<T> {
+            adjacency: Self::adjacency(),
+            token_out,
+            quotes_left: T::MaxPathSearchQuotes::get(),
+            reached: BTreeMap::new(),
+            best: None,
+        };
+        Self::search_path(&mut search, &mut sp_std::vec![token_in], amount_in);
+        search.best
+    }
+}
@@ -0,0 +1,230 @@
+use super::*;
+
+use crate::{Error, mock::*};
+use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize}};
+
+type System = system::Module<Test>;
+type Balances = balances::Module<Test>;
+type TokenModule = token::Module<Test>;
+type TradeModule = trade::Module<Test>;
+type PoolModule = pool::Module<Test>;
+type RouterModule = super::Module<Test>;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	system::GenesisConfig::default()
+		.build_storage::<Test>()
+		.unwrap()
+		.into()
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		TradeModule::on_finalize(System::block_number());
+		Balances::on_finalize(System::block_number());
+		System::on_finalize(System::block_number());
+		System::set_block_number(System::block_number() + 1);
+		System::on_initialize(System::block_number());
+		Balances::on_initialize(System::block_number());
+		TradeModule::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn run_to_block_works() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(System::block_number(), 0);
+		run_to_block(10);
+		assert_eq!(System::block_number(), 10);
+	});
+}
+
+#[test]
+fn swap_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"88".to_vec(), 10000000));
+		let other = TokenModule::owned_token((alice, 1)).unwrap();
+
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, bob, 200000, None));
+
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, base, 3000, 0), Error::<Test>::SameToken);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 0, 0), Error::<Test>::ZeroAmount);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 3000, 0), Error::<Test>::NoRoute);
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		// bob sells 1000 quote at 1 and 1000 quote at 1.1
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 100_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 110_000_000, 1000));
+
+		// only the market orders, 500 of the level at 1.1 buys 454
+		let quote_result = RouterModule::quote_swap(base, quote, 1500).unwrap();
+		assert_eq!(quote_result.book_amount_in, 1500);
+		assert_eq!(quote_result.book_amount_out, 1454);
+		assert_eq!(quote_result.pool_amount_in, 0);
+		assert_eq!(quote_result.amount_out, 1454);
+		assert_eq!(quote_result.book.unwrap().levels.len(), 2);
+
+		// the market orders only buy with 2100
+		let quote_result = RouterModule::quote_swap(base, quote, 3000).unwrap();
+		assert_eq!(quote_result.amount_in, 2100);
+		assert_eq!(quote_result.remained_amount_in, 900);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 3000, 0), Error::<Test>::InsufficientLiquidity);
+
+		assert_ok!(PoolModule::create_pool(Origin::signed(bob), base, quote));
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(bob), base, quote, 100000, 100000, 0, 0));
+
+		// the level at 1 is better than the pool, the pool is better than the level at 1.1
+		let quote_result = RouterModule::quote_swap(base, quote, 3000).unwrap();
+		assert_eq!(quote_result.book_amount_in, 1000);
+		assert_eq!(quote_result.book_amount_out, 1000);
+		assert_eq!(quote_result.pool_amount_in, 2000);
+		assert_eq!(quote_result.pool_amount_out, 1955);
+		assert_eq!(quote_result.amount_out, 2955);
+		assert_eq!(quote_result.book.unwrap().levels.len(), 1);
+		// the pool only buys 2904
+		assert_eq!(PoolModule::get_amount_out(3000, 100000, 100000).unwrap(), 2904);
+
+		// the pool takes 4737 until its marginal rate falls to the level at 1.1, then the level, then the pool again
+		let quote_result = RouterModule::quote_swap(base, quote, 10000).unwrap();
+		assert_eq!(quote_result.book_amount_in, 2100);
+		assert_eq!(quote_result.book_amount_out, 2000);
+		assert_eq!(quote_result.pool_amount_in, 7900);
+		assert_eq!(quote_result.pool_amount_out, 7301);
+		assert_eq!(quote_result.amount_in, 10000);
+		assert_eq!(quote_result.amount_out, 9301);
+		assert_eq!(quote_result.remained_amount_in, 0);
+
+		// no buy orders, only the pool
+		let quote_result = RouterModule::quote_swap(quote, base, 1000).unwrap();
+		assert_eq!(quote_result.book, None);
+		assert_eq!(quote_result.pool_amount_in, 1000);
+		assert_eq!(quote_result.amount_out, 987);
+
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, other, 3000, 0), Error::<Test>::NoRoute);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 10000, 9302), Error::<Test>::SlippageExceeded);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 30000000, 0), token::Error::<Test>::BalanceNotEnough);
+
+		// the pool part is checked before the market orders are filled
+		assert_ok!(TokenModule::freeze_account(Origin::signed(bob), quote, PoolModule::account_id()));
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 10000, 9301), token::Error::<Test>::AccountFrozen);
+		assert_eq!(TradeModule::market_levels(tp_hash, trade::OrderType::Buy), vec![(100_000_000, 1000), (110_000_000, 1100)]);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 0);
+		assert_ok!(TokenModule::thaw_account(Origin::signed(bob), quote, PoolModule::account_id()));
+
+		assert_ok!(RouterModule::swap(Origin::signed(alice), base, quote, 10000, 9301));
+
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 200000 - 10000);
+		assert_eq!(TokenModule::free_balance_of((alice, base)), 21000000 - 200000 - 10000);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 9301);
+		assert_eq!(TradeModule::market_levels(tp_hash, trade::OrderType::Buy), vec![]);
+
+		let pool = PoolModule::pool_of(base, quote).unwrap();
+		assert_eq!(pool.reserves(base), (107900, 92699));
+
+		// the swap does not match the market orders of the sender
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 100_000_000, 1000));
+		assert_err!(RouterModule::swap(Origin::signed(bob), base, quote, 500, 0), Error::<Test>::SelfMatch);
+		assert_ok!(RouterModule::swap(Origin::signed(alice), base, quote, 500, 0));
+		assert_eq!(TradeModule::market_levels(tp_hash, trade::OrderType::Buy), vec![(100_000_000, 500)]);
+	});
+}
+
+#[test]
+fn route_levels_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, bob, 200000, None));
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+
+		// bob sells 100 quote at each of 12 levels from 1 to 1.11
+		for i in 0..12 {
+			assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, trade::OrderType::Sell, 100_000_000 + i * 1_000_000, 100));
+		}
+
+		// the swap only walks MaxRouteLevels levels, which buy with 100 + 101 + ... + 109
+		let quote_result = RouterModule::quote_swap(base, quote, 100000).unwrap();
+		assert_eq!(quote_result.book.unwrap().levels.len(), 10);
+		assert_eq!(quote_result.amount_in, 1045);
+		assert_eq!(quote_result.remained_amount_in, 100000 - 1045);
+		assert_err!(RouterModule::swap(Origin::signed(alice), base, quote, 100000, 0), Error::<Test>::InsufficientLiquidity);
+		assert_ok!(RouterModule::swap(Origin::signed(alice), base, quote, 1045, 0));
+		assert_eq!(TokenModule::balance_of((alice, quote)), 1000);
+	});
+}
+
+#[test]
+fn swap_path_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token_a = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token_b = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"88".to_vec(), 10000000));
+		let token_c = TokenModule::owned_token((alice, 1)).unwrap();
+
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_a, bob, 200000, None));
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_c, bob, 200000, None));
+
+		assert_ok!(PoolModule::create_pool(Origin::signed(bob), token_a, token_b));
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(bob), token_a, token_b, 100000, 100000, 0, 0));
+		assert_ok!(PoolModule::create_pool(Origin::signed(bob), token_b, token_c));
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(bob), token_b, token_c, 100000, 50000, 0, 0));
+		// a shallow direct pool
+		assert_ok!(PoolModule::create_pool(Origin::signed(alice), token_a, token_c));
+		assert_ok!(PoolModule::add_liquidity(Origin::signed(alice), token_a, token_c, 10000, 5000, 0, 0));
+
+		assert_err!(RouterModule::swap_path(Origin::signed(alice), vec![token_a], 1000, 0), Error::<Test>::InvalidPath);
+		assert_err!(RouterModule::swap_path(Origin::signed(alice), vec![token_a, token_b, token_a], 1000, 0), Error::<Test>::InvalidPath);
+		assert_err!(RouterModule::swap_path(Origin::signed(alice), vec![token_a, token_b, token_c, token_a, token_b], 1000, 0), Error::<Test>::PathTooLong);
+
+		let quotes = RouterModule::quote_swap_path(vec![token_a, token_b, token_c], 1000).unwrap();
+		assert_eq!(quotes.len(), 2);
+		assert_eq!((quotes[0].amount_in, quotes[0].amount_out), (1000, 987));
+		assert_eq!((quotes[1].amount_in, quotes[1].amount_out), (987, 487));
+
+		// the direct pool only buys 453
+		assert_eq!(RouterModule::quote_swap(token_a, token_c, 1000).unwrap().amount_out, 453);
+		assert_eq!(RouterModule::best_path(token_a, token_c, 1000), Some((vec![token_a, token_b, token_c], 487)));
+
+		assert_err!(RouterModule::swap_path(Origin::signed(alice), vec![token_a, token_b, token_c], 1000, 488), Error::<Test>::SlippageExceeded);
+		// the last hop is checked before the first one fills
+		assert_ok!(TokenModule::freeze_account(Origin::signed(alice), token_c, PoolModule::account_id()));
+		assert_err!(RouterModule::swap_path(Origin::signed(alice), vec![token_a, token_b, token_c], 1000, 487), token::Error::<Test>::AccountFrozen);
+		assert_eq!(TokenModule::balance_of((alice, token_a)), 21000000 - 200000 - 10000);
+		assert_eq!(PoolModule::pool_of(token_a, token_b).unwrap().reserves(token_a), (100000, 100000));
+		assert_ok!(TokenModule::thaw_account(Origin::signed(alice), token_c, PoolModule::account_id()));
+
+		assert_ok!(RouterModule::swap_path(Origin::signed(alice), vec![token_a, token_b, token_c], 1000, 487));
+
+		assert_eq!(TokenModule::balance_of((alice, token_a)), 21000000 - 200000 - 10000 - 1000);
+		assert_eq!(TokenModule::balance_of((alice, token_b)), 0);
+		assert_eq!(TokenModule::balance_of((alice, token_c)), 10000000 - 200000 - 5000 + 487);
+
+		assert_eq!(PoolModule::pool_of(token_a, token_b).unwrap().reserves(token_a), (101000, 99013));
+		assert_eq!(PoolModule::pool_of(token_b, token_c).unwrap().reserves(token_b), (100987, 49513));
+	});
+}
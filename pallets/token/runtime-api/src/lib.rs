@@ -0,0 +1,25 @@
+//! Runtime API definition for the token pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+
+pub use token::Token;
+
+sp_api::decl_runtime_apis! {
+	pub trait TokenApi<AccountId, Hash, Balance> where
+		AccountId: Codec,
+		Hash: Codec,
+		Balance: Codec,
+	{
+		/// The token with its metadata
+		fn token(token: Hash) -> Option<Token<Hash, Balance>>;
+
+		/// The token of the symbol, the symbol is case insensitive
+		fn token_by_symbol(symbol: Vec<u8>) -> Option<Hash>;
+
+		/// The amount the spender can transfer from the owner
+		fn allowance(owner: AccountId, spender: AccountId, token: Hash) -> Balance;
+	}
+}
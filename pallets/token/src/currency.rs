@@ -0,0 +1,449 @@
+use sp_std::{marker::PhantomData, prelude::Vec};
+use sp_runtime::traits::{AtLeast32Bit, CheckedAdd, CheckedSub, Member, Zero};
+use frame_support::{
+    ensure, dispatch, Parameter,
+    traits::{Get, Currency, ReservableCurrency, ExistenceRequirement, WithdrawReasons, BalanceStatus},
+    weights::Weight,
+};
+
+use crate::{Trait, Module, Error, RawEvent, ReserveId, ReservedBalanceOf, TokenRole};
+
+/// The balances of many currencies, keyed by the currency id
+pub trait MultiCurrency<AccountId> {
+    type CurrencyId: Parameter + Member + Copy;
+    type Balance: Parameter + Member + AtLeast32Bit + Default + Copy;
+
+    fn exists(currency_id: Self::CurrencyId) -> bool;
+    fn owner(currency_id: Self::CurrencyId) -> Option<AccountId>;
+    /// The account which lists the currency on the dex and manages its trade pairs, None if only root does
+    fn market_admin(currency_id: Self::CurrencyId) -> Option<AccountId>;
+    /// The amounts are in 10^-decimals of the displayed unit
+    fn decimals(currency_id: Self::CurrencyId) -> u8;
+    fn currency_by_symbol(symbol: Vec<u8>) -> Option<Self::CurrencyId>;
+
+    /// The free and the reserved balance
+    fn total_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance;
+    fn free_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance;
+    /// The account has `amount` of free balance to reserve or transfer
+    fn ensure_can_withdraw(currency_id: Self::CurrencyId, who: &AccountId, amount: Self::Balance) -> dispatch::DispatchResult;
+    /// The currency is not paused
+    fn ensure_active(currency_id: Self::CurrencyId) -> dispatch::DispatchResult;
+    /// The currency is not paused and the account is not frozen
+    fn ensure_transferable(currency_id: Self::CurrencyId, who: &AccountId) -> dispatch::DispatchResult;
+    /// The account could be paid any amount of the currency, an account of the native currency has to exist
+    fn ensure_can_deposit(currency_id: Self::CurrencyId, who: &AccountId) -> dispatch::DispatchResult;
+    fn transfer(currency_id: Self::CurrencyId, from: &AccountId, to: &AccountId, amount: Self::Balance) -> dispatch::DispatchResult;
+}
+
+/// The balances of many currencies held in named reserves
+pub trait MultiReservableCurrency<AccountId>: MultiCurrency<AccountId> {
+    type ReserveIdentifier: Parameter + Member + Copy;
+
+    /// The sum of the named reserves of the account
+    fn reserved_balance(currency_id: Self::CurrencyId, who: &AccountId) -> Self::Balance;
+    fn named_reserve(currency_id: Self::CurrencyId, id: Self::ReserveIdentifier, who: &AccountId) -> Self::Balance;
+    fn reserve(currency_id: Self::CurrencyId, id: Self::ReserveIdentifier, who: &AccountId,
+               amount: Self::Balance) -> dispatch::DispatchResult;
+    fn unreserve(currency_id: Self::CurrencyId, id: Self::ReserveIdentifier, who: &AccountId,
+                 amount: Self::Balance) -> dispatch::DispatchResult;
+    /// Pay `amount` of the reserve `id` of `from` to the free balance of `to`
+    fn repatriate_reserved(currency_id: Self::CurrencyId, id: Self::ReserveIdentifier, from: &AccountId, to: &AccountId,
+                           amount: Self::Balance) -> dispatch::DispatchResult;
+
+    /// Move the balances held before the named reserves to the legacy reserve, before their holders take them
+    fn migrate_legacy_reserves() -> Weight;
+}
+
+impl<T: Trait> MultiCurrency<T::AccountId> for Module<T> {
+    type CurrencyId = T::Hash;
+    type Balance = T::Balance;
+
+    fn exists(currency_id: T::Hash) -> bool {
+        Self::token(currency_id).is_some()
+    }
+
+    fn owner(currency_id: T::Hash) -> Option<T::AccountId> {
+        Self::owner(currency_id)
+    }
+
+    fn market_admin(currency_id: T::Hash) -> Option<T::AccountId> {
+        Self::role(currency_id, TokenRole::MarketAdmin)
+    }
+
+    fn decimals(currency_id: T::Hash) -> u8 {
+        Self::decimals(currency_id)
+    }
+
+    fn currency_by_symbol(symbol: Vec<u8>) -> Option<T::Hash> {
+        Self::token_by_symbol(symbol)
+    }
+
+    fn total_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        Self::balance_of((who.clone(), currency_id))
+    }
+
+    fn free_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        Self::free_balance_of((who.clone(), currency_id))
+    }
+
+    fn ensure_can_withdraw(currency_id: T::Hash, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_free_balance(who.clone(), currency_id, amount)
+    }
+
+    fn ensure_active(currency_id: T::Hash) -> dispatch::DispatchResult {
+        Self::ensure_not_paused(currency_id)
+    }
+
+    fn ensure_transferable(currency_id: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        Self::ensure_transferable(currency_id, who)
+    }
+
+    fn ensure_can_deposit(currency_id: T::Hash, _who: &T::AccountId) -> dispatch::DispatchResult {
+        ensure!(Self::token(currency_id).is_some(), Error::<T>::NoMatchingToken);
+
+        Ok(())
+    }
+
+    fn transfer(currency_id: T::Hash, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::do_transfer(from.clone(), currency_id, to.clone(), amount, None)
+    }
+}
+
+impl<T: Trait> MultiReservableCurrency<T::AccountId> for Module<T> {
+    type ReserveIdentifier = ReserveId<T::Hash>;
+
+    fn reserved_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        Self::reserved_balance_of((who.clone(), currency_id))
+    }
+
+    fn named_reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId) -> T::Balance {
+        Self::named_reserve((who.clone(), currency_id, id))
+    }
+
+    fn reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::reserve(who.clone(), currency_id, id, amount)
+    }
+
+    fn unreserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::unreserve(who.clone(), currency_id, id, amount)
+    }
+
+    fn repatriate_reserved(currency_id: T::Hash, id: ReserveId<T::Hash>, from: &T::AccountId, to: &T::AccountId,
+                           amount: T::Balance) -> dispatch::DispatchResult {
+        Self::repatriate_reserved(from.clone(), currency_id, id, to.clone(), amount)
+    }
+
+    fn migrate_legacy_reserves() -> Weight {
+        Self::migrate_reserves()
+    }
+}
+
+type NativeCurrency<T> = pallet_balances::Module<T>;
+
+/// The native currency of pallet_balances as the currency `NativeId`, the named reserves and their sum are
+/// kept by the token module on top of the reserved balance
+pub struct BalancesAdapter<T, NativeId, NativeDecimals>(PhantomData<(T, NativeId, NativeDecimals)>);
+
+impl<T: Trait, NativeId: Get<T::Hash>, NativeDecimals: Get<u8>> BalancesAdapter<T, NativeId, NativeDecimals> {
+    fn ensure_native(currency_id: T::Hash) -> dispatch::DispatchResult {
+        ensure!(currency_id == NativeId::get(), Error::<T>::NoMatchingToken);
+
+        Ok(())
+    }
+
+    // the sum of the named reserves, the reserved balance of pallet_balances has the reserves of other modules too
+    fn set_reserved(who: &T::AccountId, currency_id: T::Hash, amount: T::Balance) {
+        if amount.is_zero() {
+            ReservedBalanceOf::<T>::remove((who.clone(), currency_id));
+        } else {
+            ReservedBalanceOf::<T>::insert((who.clone(), currency_id), amount);
+        }
+    }
+}
+
+impl<T: Trait, NativeId: Get<T::Hash>, NativeDecimals: Get<u8>> MultiCurrency<T::AccountId>
+    for BalancesAdapter<T, NativeId, NativeDecimals> {
+    type CurrencyId = T::Hash;
+    type Balance = T::Balance;
+
+    fn exists(currency_id: T::Hash) -> bool {
+        currency_id == NativeId::get()
+    }
+
+    fn owner(_currency_id: T::Hash) -> Option<T::AccountId> {
+        None
+    }
+
+    fn market_admin(_currency_id: T::Hash) -> Option<T::AccountId> {
+        None
+    }
+
+    fn decimals(_currency_id: T::Hash) -> u8 {
+        NativeDecimals::get()
+    }
+
+    fn currency_by_symbol(_symbol: Vec<u8>) -> Option<T::Hash> {
+        None
+    }
+
+    fn total_balance(_currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        NativeCurrency::<T>::total_balance(who)
+    }
+
+    fn free_balance(_currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        NativeCurrency::<T>::free_balance(who)
+    }
+
+    fn ensure_can_withdraw(currency_id: T::Hash, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+
+        let new_balance = NativeCurrency::<T>::free_balance(who).checked_sub(&amount).ok_or(Error::<T>::BalanceNotEnough)?;
+        NativeCurrency::<T>::ensure_can_withdraw(who, amount, WithdrawReasons::all(), new_balance)
+    }
+
+    fn ensure_active(currency_id: T::Hash) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)
+    }
+
+    fn ensure_transferable(currency_id: T::Hash, _who: &T::AccountId) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)
+    }
+
+    // pallet_balances only repatriates to an existing account, and a new one needs the existential deposit
+    fn ensure_can_deposit(currency_id: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+        ensure!(!NativeCurrency::<T>::total_balance(who).is_zero(), Error::<T>::DeadAccount);
+
+        Ok(())
+    }
+
+    fn transfer(currency_id: T::Hash, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+
+        <NativeCurrency<T> as Currency<T::AccountId>>::transfer(from, to, amount, ExistenceRequirement::AllowDeath)
+    }
+}
+
+impl<T: Trait, NativeId: Get<T::Hash>, NativeDecimals: Get<u8>> MultiReservableCurrency<T::AccountId>
+    for BalancesAdapter<T, NativeId, NativeDecimals> {
+    type ReserveIdentifier = ReserveId<T::Hash>;
+
+    fn reserved_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        Module::<T>::reserved_balance_of((who.clone(), currency_id))
+    }
+
+    fn named_reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId) -> T::Balance {
+        Module::<T>::named_reserve((who.clone(), currency_id, id))
+    }
+
+    fn reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+
+        let new_named_amount = Self::named_reserve(currency_id, id, who).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        let new_reserved_amount = Self::reserved_balance(currency_id, who).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        <NativeCurrency<T> as ReservableCurrency<T::AccountId>>::reserve(who, amount)?;
+        Module::<T>::set_named_reserve(who.clone(), currency_id, id, new_named_amount);
+        Self::set_reserved(who, currency_id, new_reserved_amount);
+
+        Module::<T>::deposit_event(RawEvent::Reserved(who.clone(), currency_id, id, amount));
+
+        Ok(())
+    }
+
+    fn unreserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+
+        let new_named_amount = Self::named_reserve(currency_id, id, who).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_reserved_amount = Self::reserved_balance(currency_id, who).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        ensure!(NativeCurrency::<T>::reserved_balance(who) >= amount, Error::<T>::ReservedBalanceNotEnough);
+
+        <NativeCurrency<T> as ReservableCurrency<T::AccountId>>::unreserve(who, amount);
+        Module::<T>::set_named_reserve(who.clone(), currency_id, id, new_named_amount);
+        Self::set_reserved(who, currency_id, new_reserved_amount);
+
+        Module::<T>::deposit_event(RawEvent::Unreserved(who.clone(), currency_id, id, amount));
+
+        Ok(())
+    }
+
+    fn repatriate_reserved(currency_id: T::Hash, id: ReserveId<T::Hash>, from: &T::AccountId, to: &T::AccountId,
+                           amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_native(currency_id)?;
+
+        if from == to {
+            return Self::unreserve(currency_id, id, from, amount);
+        }
+
+        let new_named_amount = Self::named_reserve(currency_id, id, from).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_reserved_amount = Self::reserved_balance(currency_id, from).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        ensure!(NativeCurrency::<T>::reserved_balance(from) >= amount, Error::<T>::ReservedBalanceNotEnough);
+        Self::ensure_can_deposit(currency_id, to)?;
+
+        // the reserved balance covers the amount, so all of it is repatriated or none on error
+        let remained = NativeCurrency::<T>::repatriate_reserved(from, to, amount, BalanceStatus::Free)?;
+        ensure!(remained.is_zero(), Error::<T>::ReservedBalanceNotEnough);
+        Module::<T>::set_named_reserve(from.clone(), currency_id, id, new_named_amount);
+        Self::set_reserved(from, currency_id, new_reserved_amount);
+
+        Module::<T>::deposit_event(RawEvent::ReserveRepatriated(from.clone(), to.clone(), currency_id, id, amount));
+
+        Ok(())
+    }
+
+    // the native currency had no frozen balance
+    fn migrate_legacy_reserves() -> Weight {
+        0
+    }
+}
+
+/// The native currency as `NativeId` through `BalancesAdapter`, and the tokens of the token module
+pub struct Currencies<T, NativeId, NativeDecimals>(PhantomData<(T, NativeId, NativeDecimals)>);
+
+impl<T: Trait, NativeId: Get<T::Hash>, NativeDecimals: Get<u8>> MultiCurrency<T::AccountId>
+    for Currencies<T, NativeId, NativeDecimals> {
+    type CurrencyId = T::Hash;
+    type Balance = T::Balance;
+
+    fn exists(currency_id: T::Hash) -> bool {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::exists(currency_id)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::exists(currency_id)
+        }
+    }
+
+    fn owner(currency_id: T::Hash) -> Option<T::AccountId> {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::owner(currency_id)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::owner(currency_id)
+        }
+    }
+
+    fn market_admin(currency_id: T::Hash) -> Option<T::AccountId> {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::market_admin(currency_id)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::market_admin(currency_id)
+        }
+    }
+
+    fn decimals(currency_id: T::Hash) -> u8 {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::decimals(currency_id)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::decimals(currency_id)
+        }
+    }
+
+    fn currency_by_symbol(symbol: Vec<u8>) -> Option<T::Hash> {
+        <Module<T> as MultiCurrency<T::AccountId>>::currency_by_symbol(symbol)
+    }
+
+    fn total_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::total_balance(currency_id, who)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::total_balance(currency_id, who)
+        }
+    }
+
+    fn free_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::free_balance(currency_id, who)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::free_balance(currency_id, who)
+        }
+    }
+
+    fn ensure_can_withdraw(currency_id: T::Hash, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::ensure_can_withdraw(currency_id, who, amount)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::ensure_can_withdraw(currency_id, who, amount)
+        }
+    }
+
+    fn ensure_active(currency_id: T::Hash) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::ensure_active(currency_id)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::ensure_active(currency_id)
+        }
+    }
+
+    fn ensure_transferable(currency_id: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::ensure_transferable(currency_id, who)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::ensure_transferable(currency_id, who)
+        }
+    }
+
+    fn ensure_can_deposit(currency_id: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::ensure_can_deposit(currency_id, who)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::ensure_can_deposit(currency_id, who)
+        }
+    }
+
+    fn transfer(currency_id: T::Hash, from: &T::AccountId, to: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::transfer(currency_id, from, to, amount)
+        } else {
+            <Module<T> as MultiCurrency<T::AccountId>>::transfer(currency_id, from, to, amount)
+        }
+    }
+}
+
+impl<T: Trait, NativeId: Get<T::Hash>, NativeDecimals: Get<u8>> MultiReservableCurrency<T::AccountId>
+    for Currencies<T, NativeId, NativeDecimals> {
+    type ReserveIdentifier = ReserveId<T::Hash>;
+
+    fn reserved_balance(currency_id: T::Hash, who: &T::AccountId) -> T::Balance {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::reserved_balance(currency_id, who)
+        } else {
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::reserved_balance(currency_id, who)
+        }
+    }
+
+    fn named_reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId) -> T::Balance {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::named_reserve(currency_id, id, who)
+        } else {
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::named_reserve(currency_id, id, who)
+        }
+    }
+
+    fn reserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::reserve(currency_id, id, who, amount)
+        } else {
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::reserve(currency_id, id, who, amount)
+        }
+    }
+
+    fn unreserve(currency_id: T::Hash, id: ReserveId<T::Hash>, who: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::unreserve(currency_id, id, who, amount)
+        } else {
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::unreserve(currency_id, id, who, amount)
+        }
+    }
+
+    fn repatriate_reserved(currency_id: T::Hash, id: ReserveId<T::Hash>, from: &T::AccountId, to: &T::AccountId,
+                           amount: T::Balance) -> dispatch::DispatchResult {
+        if currency_id == NativeId::get() {
+            BalancesAdapter::<T, NativeId, NativeDecimals>::repatriate_reserved(currency_id, id, from, to, amount)
+        } else {
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::repatriate_reserved(currency_id, id, from, to, amount)
+        }
+    }
+
+    fn migrate_legacy_reserves() -> Weight {
+        BalancesAdapter::<T, NativeId, NativeDecimals>::migrate_legacy_reserves() +
+            <Module<T> as MultiReservableCurrency<T::AccountId>>::migrate_legacy_reserves()
+    }
+}
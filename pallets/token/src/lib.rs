@@ -2,12 +2,13 @@
 
 use codec::{Decode, Encode};
 use sp_std::prelude::Vec;
-use sp_runtime::{traits::{Bounded, Hash}};
+use sp_runtime::{traits::{Bounded, Hash, CheckedAdd, CheckedSub, Saturating, Zero}};
 use frame_support::{
-    decl_error, decl_event, decl_module, decl_storage, ensure, dispatch, StorageMap, StorageValue, traits::Randomness
+    decl_error, decl_event, decl_module, decl_storage, ensure, dispatch, StorageMap, StorageValue, IterableStorageMap,
+    traits::Randomness, weights::Weight, storage::unhashed
 };
 
-use frame_system::ensure_signed;
+use frame_system::{ensure_signed, ensure_root};
 
 use pallet_balances as balances;
 use frame_system as system;
@@ -19,14 +20,59 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+pub mod currency;
+pub use currency::{MultiCurrency, MultiReservableCurrency, BalancesAdapter, Currencies};
+
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub struct Token<Hash, Balance> {
     pub hash: Hash,
     pub symbol: Vec<u8>,
     pub total_supply: Balance,
+    pub name: Vec<u8>,
+    /// the amounts are in 10^-decimals of the displayed unit
+    pub decimals: u8,
+    pub icon_uri: Option<Vec<u8>>,
+    pub description: Option<Vec<u8>>,
+}
+
+/// The roles of a token, held by the owner unless they are given to other accounts
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRole {
+    Minter,
+    Freezer,
+    MetadataAdmin,
+    /// lists the token on the dex and manages its trade pairs
+    MarketAdmin,
+}
+
+/// What a named reserve holds the balance for
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReserveId<Hash> {
+    /// the remained sell amount of the order with the hash
+    Order(Hash),
+    /// the other locks, like staking, vesting or HTLCs, by a reason identifier
+    Named([u8; 8]),
 }
 
+/// The reason of the balances frozen before the named reserves, the trade module moves them to its orders
+pub const LEGACY_RESERVE: [u8; 8] = *b"legacy  ";
+
+/// The token layout before the metadata
+#[derive(Encode, Decode)]
+struct TokenV0<Hash, Balance> {
+    hash: Hash,
+    symbol: Vec<u8>,
+    total_supply: Balance,
+}
+
+/// Symbols are upper case ASCII letters and digits, '-' is only for the tokens created by the other modules
+pub const MAX_SYMBOL_LENGTH: usize = 32;
+pub const MAX_NAME_LENGTH: usize = 64;
+pub const MAX_DECIMALS: u8 = 18;
+pub const MAX_ICON_URI_LENGTH: usize = 256;
+pub const MAX_DESCRIPTION_LENGTH: usize = 512;
+
 pub trait Trait: balances::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
 }
@@ -44,6 +90,52 @@ decl_error! {
 		SenderHaveNoToken,
 		/// Memo length exceed limitation
 		MemoLengthExceedLimitation,
+		/// Sender is not the minter of the token
+		SenderNotMinter,
+		/// Sender is not the owner of the token
+		SenderNotOwner,
+		/// Sender is not the metadata admin of the token
+		SenderNotMetadataAdmin,
+		/// Sender is not the pending owner of the token
+		SenderNotPendingOwner,
+		/// Sender is not the freezer of the token
+		SenderNotFreezer,
+		/// The account is frozen by the freezer of the token
+		AccountFrozen,
+		/// The token is paused by the freezer of the token
+		TokenPaused,
+		/// The token has frozen accounts, which nobody could thaw without an owner
+		FrozenAccountsLeft,
+		/// Max supply is less than the total supply
+		MaxSupplyTooSmall,
+		/// Total supply would exceed the max supply
+		MaxSupplyExceeded,
+		/// Name is empty or exceeds the max length
+		InvalidName,
+		/// Decimals exceed the max decimals
+		DecimalsTooLarge,
+		/// Icon uri length exceed limitation
+		IconUriLengthExceedLimitation,
+		/// Description length exceed limitation
+		DescriptionLengthExceedLimitation,
+		/// Symbol is empty, too long or has characters not allowed
+		InvalidSymbol,
+		/// Symbol is used by another token
+		SymbolAlreadyExists,
+		/// Symbol is reserved for another account
+		SymbolReserved,
+		/// Symbol is not reserved
+		SymbolNotReserved,
+		/// Symbol is not used by any token
+		SymbolNotFound,
+		/// Symbol is revoked, only an account root reserves it for can issue it
+		SymbolRevoked,
+		/// The allowance is not enough
+		AllowanceNotEnough,
+		/// The named reserve is not enough
+		ReservedBalanceNotEnough,
+		/// The account doesn't exist to receive the native currency
+		DeadAccount,
 	}
 }
 
@@ -56,8 +148,32 @@ decl_event!(
     {
 		Issued(AccountId, Hash, Balance),
         Transferd(AccountId, AccountId, Hash, Balance),
-        Freezed(AccountId, Hash, Balance),
-        UnFreezed(AccountId, Hash, Balance),
+        /// account, token, reserve, amount
+        Reserved(AccountId, Hash, ReserveId<Hash>, Balance),
+        Unreserved(AccountId, Hash, ReserveId<Hash>, Balance),
+        /// from, to, token, the reserve of from, amount
+        ReserveRepatriated(AccountId, AccountId, Hash, ReserveId<Hash>, Balance),
+        Minted(AccountId, AccountId, Hash, Balance),
+        Burned(AccountId, Hash, Balance),
+        /// owner, token, the minter
+        MinterChanged(AccountId, Hash, AccountId),
+        /// token, role, the account given the role, None gives it back to the owner
+        RoleSet(Hash, TokenRole, Option<AccountId>),
+        /// owner, token, the pending owner
+        OwnershipTransferStarted(AccountId, Hash, AccountId),
+        /// previous owner, new owner, token
+        OwnershipTransferred(AccountId, AccountId, Hash),
+        OwnershipRenounced(AccountId, Hash),
+        AccountFrozen(Hash, AccountId),
+        AccountThawed(Hash, AccountId),
+        TokenPaused(Hash),
+        TokenUnpaused(Hash),
+        MetadataUpdated(AccountId, Hash),
+        SymbolReserved(Vec<u8>, AccountId),
+        SymbolUnreserved(Vec<u8>),
+        SymbolRevoked(Vec<u8>, Hash),
+        /// owner, spender, token, the new allowance
+        Approved(AccountId, AccountId, Hash, Balance),
 	}
 );
 
@@ -65,15 +181,95 @@ decl_storage! {
     trait Store for Module<T: Trait> as TokenModule {
         Tokens get(fn token): map hasher(blake2_128_concat) T::Hash => Option<Token<T::Hash, T::Balance>>;
         Owners get(fn owner): map hasher(blake2_128_concat) T::Hash => Option<T::AccountId>;
+        /// Normalized symbol => TokenHash
+        TokensBySymbol get(fn token_hash_by_symbol): map hasher(blake2_128_concat) Vec<u8> => Option<T::Hash>;
+        /// Normalized symbol => the account which is allowed to issue it
+        ReservedSymbols get(fn reserved_symbol): map hasher(blake2_128_concat) Vec<u8> => Option<T::AccountId>;
+        /// Normalized symbol => whether root revoked it, it is kept from the issuers until root reserves it again
+        RevokedSymbols get(fn is_revoked_symbol): map hasher(blake2_128_concat) Vec<u8> => bool;
+        /// Whether all the tokens stored in the old layout are brought to the current one
+        TokensMigrated: bool;
+        /// Whether all the tokens created before the symbol registry are registered
+        SymbolRegistryComplete: bool;
+        /// The account which accepts the ownership to become the owner
+        PendingOwners get(fn pending_owner): map hasher(blake2_128_concat) T::Hash => Option<T::AccountId>;
+        /// The minters set before they are kept in the roles, drained to the roles by the migration
+        Minters: map hasher(blake2_128_concat) T::Hash => Option<T::AccountId>;
+        /// (TokenHash, Role) => the account given the role, instead of the owner
+        Roles get(fn role_holder): map hasher(blake2_128_concat) (T::Hash, TokenRole) => Option<T::AccountId>;
+        MaxSupply get(fn max_supply): map hasher(blake2_128_concat) T::Hash => Option<T::Balance>;
         BalanceOf get(fn balance_of): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => T::Balance;
         FreeBalanceOf get(fn free_balance_of): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => T::Balance;
-        FreezedBalanceOf get(fn freezed_balance_of): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => T::Balance;
+        /// (AccountId, TokenHash) => the sum of the named reserves of the account
+        ReservedBalanceOf get(fn reserved_balance_of): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => T::Balance;
+        /// (AccountId, TokenHash, ReserveId) => the balance held for the reason or the order
+        NamedReserves get(fn named_reserve): map hasher(blake2_128_concat) (T::AccountId, T::Hash, ReserveId<T::Hash>) => T::Balance;
+        /// The frozen balances before the named reserves, drained to the legacy reserve by the migration
+        FreezedBalanceOf: map hasher(blake2_128_concat) (T::AccountId, T::Hash) => T::Balance;
+        /// (TokenHash, AccountId) => whether the freezer of the token froze the account, unlike the balance
+        /// frozen by the orders, the account can't move any of its balance
+        FrozenAccounts get(fn is_frozen_account): map hasher(blake2_128_concat) (T::Hash, T::AccountId) => bool;
+        /// TokenHash => whether the freezer of the token paused all its transfers
+        PausedTokens get(fn is_paused): map hasher(blake2_128_concat) T::Hash => bool;
+        /// TokenHash => the number of the accounts frozen by the freezer of the token
+        FrozenAccountsCount get(fn frozen_accounts_count): map hasher(blake2_128_concat) T::Hash => u32;
+        /// (Owner, Spender, TokenHash) => the amount the spender can transfer from the owner
+        Allowances get(fn allowance): map hasher(blake2_128_concat) (T::AccountId, T::AccountId, T::Hash) => T::Balance;
 
+        /// (AccountId, u64) => TokenHash, the tokens issued by the account
         OwnedTokens get(fn owned_token): map hasher(blake2_128_concat) (T::AccountId, u64) => Option<T::Hash>;
         OwnedTokensIndex get(fn owned_token_index): map hasher(blake2_128_concat) T::AccountId => u64;
 
         Nonce get(fn nonce): u64;
     }
+    add_extra_genesis {
+        /// (Symbol, Owner, Total_Supply, Fixed_Hash), the owner holds the supply unless `balances` distributes it
+        config(tokens): Vec<(Vec<u8>, T::AccountId, T::Balance, Option<T::Hash>)>;
+        /// (Symbol, Holder, Amount), the amounts of a token sum to its supply
+        config(balances): Vec<(Vec<u8>, T::AccountId, T::Balance)>;
+
+        build(|config: &GenesisConfig<T>| {
+            // there is no token of the old layout or created before the symbol registry on a new chain
+            TokensMigrated::put(true);
+            SymbolRegistryComplete::put(true);
+
+            for (symbol, owner, total_supply, hash) in config.tokens.iter() {
+                let hash = match hash {
+                    Some(hash) => {
+                        assert!(<Module<T>>::token(hash).is_none(), "duplicated token hash in the genesis");
+                        Module::<T>::create_with_hash(owner.clone(), *hash, symbol.clone(), *total_supply)
+                    },
+                    None => Module::<T>::do_create(owner.clone(), symbol.clone(), *total_supply),
+                }.expect("invalid token in the genesis");
+
+                let balances = config.balances.iter()
+                    .filter(|(s, _, _)| s.to_ascii_uppercase() == symbol.to_ascii_uppercase())
+                    .collect::<Vec<_>>();
+                if balances.is_empty() {
+                    continue;
+                }
+
+                let sum = balances.iter().fold(Some(T::Balance::zero()), |sum, (_, _, amount)| {
+                    sum.and_then(|sum| sum.checked_add(amount))
+                });
+                assert!(sum == Some(*total_supply), "the genesis balances of a token don't sum to its supply");
+
+                for (_, holder, amount) in balances {
+                    if holder != owner {
+                        Module::<T>::do_transfer(owner.clone(), hash, holder.clone(), *amount, None)
+                            .expect("the owner holds the supply; qed");
+                    }
+                }
+            }
+
+            for (symbol, _, _) in config.balances.iter() {
+                assert!(
+                    config.tokens.iter().any(|(s, _, _, _)| s.to_ascii_uppercase() == symbol.to_ascii_uppercase()),
+                    "genesis balances of an unknown token"
+                );
+            }
+        })
+    }
 }
 
 decl_module! {
@@ -87,6 +283,198 @@ decl_module! {
             Self::do_issue(origin, symbol, total_supply)
         }
 
+		#[weight = 200_000]
+        pub fn issue_with_metadata(
+            origin,
+            symbol: Vec<u8>,
+            total_supply: T::Balance,
+            name: Vec<u8>,
+            decimals: u8,
+            icon_uri: Option<Vec<u8>>,
+            description: Option<Vec<u8>>,
+        ) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_issuable_symbol(&symbol)?;
+            Self::ensure_metadata(&name, decimals, &icon_uri, &description)?;
+
+            let hash = Self::do_create(sender.clone(), symbol, total_supply)?;
+            Self::do_set_metadata(sender, hash, name, decimals, icon_uri, description)
+        }
+
+		#[weight = 200_000]
+        pub fn set_metadata(
+            origin,
+            token_hash: T::Hash,
+            name: Vec<u8>,
+            decimals: u8,
+            icon_uri: Option<Vec<u8>>,
+            description: Option<Vec<u8>>,
+        ) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+            ensure!(Self::has_role(token_hash, TokenRole::MetadataAdmin, &sender), Error::<T>::SenderNotMetadataAdmin);
+            Self::ensure_metadata(&name, decimals, &icon_uri, &description)?;
+
+            Self::do_set_metadata(sender, token_hash, name, decimals, icon_uri, description)
+        }
+
+		#[weight = 200_000]
+        pub fn issue_capped(origin, symbol: Vec<u8>, total_supply: T::Balance, max_supply: T::Balance)
+            -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(max_supply >= total_supply, Error::<T>::MaxSupplyTooSmall);
+            Self::ensure_issuable_symbol(&symbol)?;
+
+            let hash = Self::do_create(sender, symbol, total_supply)?;
+            MaxSupply::<T>::insert(hash, max_supply);
+
+            Ok(())
+        }
+
+        /// Give the role to the account, or back to the owner with None
+		#[weight = 200_000]
+        pub fn set_role(origin, token_hash: T::Hash, role: TokenRole, account: Option<T::AccountId>) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_owner(token_hash, &sender)?;
+
+            Self::do_set_role(token_hash, role, account);
+
+            Ok(())
+        }
+
+        /// Give the minter role to the account, the same as `set_role` with `TokenRole::Minter`
+		#[weight = 200_000]
+        pub fn set_minter(origin, token_hash: T::Hash, minter: T::AccountId) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_owner(token_hash, &sender)?;
+
+            Self::do_set_role(token_hash, TokenRole::Minter, Some(minter.clone()));
+            Self::deposit_event(RawEvent::MinterChanged(sender, token_hash, minter));
+
+            Ok(())
+        }
+
+        /// The new owner takes the ownership by `accept_ownership`, until then the owner can transfer it again
+		#[weight = 200_000]
+        pub fn transfer_ownership(origin, token_hash: T::Hash, new_owner: T::AccountId) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_owner(token_hash, &sender)?;
+
+            PendingOwners::<T>::insert(token_hash, new_owner.clone());
+            Self::deposit_event(RawEvent::OwnershipTransferStarted(sender, token_hash, new_owner));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn accept_ownership(origin, token_hash: T::Hash) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let pending_owner = Self::pending_owner(token_hash).ok_or(Error::<T>::SenderNotPendingOwner)?;
+            ensure!(pending_owner == sender, Error::<T>::SenderNotPendingOwner);
+            let owner = Self::owner(token_hash).ok_or(Error::<T>::NoMatchingToken)?;
+
+            PendingOwners::<T>::remove(token_hash);
+            Owners::<T>::insert(token_hash, sender.clone());
+            Self::deposit_event(RawEvent::OwnershipTransferred(owner, sender, token_hash));
+
+            Ok(())
+        }
+
+        /// Leave the token without an owner, the roles given to other accounts are taken back too,
+        /// so the token must not be paused nor have frozen accounts left
+		#[weight = 200_000]
+        pub fn renounce_ownership(origin, token_hash: T::Hash) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_owner(token_hash, &sender)?;
+            Self::ensure_not_paused(token_hash)?;
+            ensure!(Self::frozen_accounts_count(token_hash) == 0, Error::<T>::FrozenAccountsLeft);
+
+            Owners::<T>::remove(token_hash);
+            PendingOwners::<T>::remove(token_hash);
+            for role in [TokenRole::Minter, TokenRole::Freezer, TokenRole::MetadataAdmin, TokenRole::MarketAdmin].iter() {
+                Roles::<T>::remove((token_hash, *role));
+            }
+            Self::deposit_event(RawEvent::OwnershipRenounced(sender, token_hash));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn mint(origin, token_hash: T::Hash, to: T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            ensure!(Self::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+            ensure!(Self::has_role(token_hash, TokenRole::Minter, &sender), Error::<T>::SenderNotMinter);
+
+            Self::do_mint(to.clone(), token_hash, amount)?;
+            Self::deposit_event(RawEvent::Minted(sender, to, token_hash, amount));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn freeze_account(origin, token_hash: T::Hash, who: T::AccountId) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_freezer(token_hash, &sender)?;
+
+            if !Self::is_frozen_account((token_hash, who.clone())) {
+                FrozenAccounts::<T>::insert((token_hash, who.clone()), true);
+                FrozenAccountsCount::<T>::mutate(token_hash, |count| *count += 1);
+            }
+            Self::deposit_event(RawEvent::AccountFrozen(token_hash, who));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn thaw_account(origin, token_hash: T::Hash, who: T::AccountId) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_freezer(token_hash, &sender)?;
+
+            if Self::is_frozen_account((token_hash, who.clone())) {
+                FrozenAccounts::<T>::remove((token_hash, who.clone()));
+                FrozenAccountsCount::<T>::mutate(token_hash, |count| *count -= 1);
+            }
+            Self::deposit_event(RawEvent::AccountThawed(token_hash, who));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn pause_token(origin, token_hash: T::Hash) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_freezer(token_hash, &sender)?;
+
+            PausedTokens::<T>::insert(token_hash, true);
+            Self::deposit_event(RawEvent::TokenPaused(token_hash));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn unpause_token(origin, token_hash: T::Hash) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_freezer(token_hash, &sender)?;
+
+            PausedTokens::<T>::remove(token_hash);
+            Self::deposit_event(RawEvent::TokenUnpaused(token_hash));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn burn(origin, token_hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            Self::ensure_transferable(token_hash, &sender)?;
+
+            Self::do_burn(sender.clone(), token_hash, amount)?;
+            Self::deposit_event(RawEvent::Burned(sender, token_hash, amount));
+
+            Ok(())
+        }
+
 		#[weight = 200_000]
         pub fn transfer(origin, token_hash: T::Hash, to: T::AccountId, amount: T::Balance, memo: Option<Vec<u8>>)
             -> dispatch::DispatchResult {
@@ -95,6 +483,110 @@ decl_module! {
             Self::deposit_event(RawEvent::Transferd(sender, to, token_hash, amount));
 
             Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn approve(origin, token_hash: T::Hash, spender: T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+
+            Self::do_approve(sender, spender, token_hash, amount);
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn increase_allowance(origin, token_hash: T::Hash, spender: T::AccountId, amount: T::Balance)
+            -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+
+            let allowance = Self::allowance((sender.clone(), spender.clone(), token_hash))
+                .checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+            Self::do_approve(sender, spender, token_hash, allowance);
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn decrease_allowance(origin, token_hash: T::Hash, spender: T::AccountId, amount: T::Balance)
+            -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+            ensure!(Self::token(token_hash).is_some(), Error::<T>::NoMatchingToken);
+
+            let allowance = Self::allowance((sender.clone(), spender.clone(), token_hash))
+                .checked_sub(&amount).ok_or(Error::<T>::AllowanceNotEnough)?;
+            Self::do_approve(sender, spender, token_hash, allowance);
+
+            Ok(())
+        }
+
+        /// Transfer from `from` by the sender, within the allowance `from` approved to the sender
+		#[weight = 200_000]
+        pub fn transfer_from(
+            origin,
+            token_hash: T::Hash,
+            from: T::AccountId,
+            to: T::AccountId,
+            amount: T::Balance,
+            memo: Option<Vec<u8>>,
+        ) -> dispatch::DispatchResult {
+            let sender = ensure_signed(origin)?;
+
+            let allowance = Self::allowance((from.clone(), sender.clone(), token_hash))
+                .checked_sub(&amount).ok_or(Error::<T>::AllowanceNotEnough)?;
+
+            Self::do_transfer(from.clone(), token_hash, to.clone(), amount, memo)?;
+            Self::do_approve(from.clone(), sender, token_hash, allowance);
+            Self::deposit_event(RawEvent::Transferd(from, to, token_hash, amount));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn reserve_symbol(origin, symbol: Vec<u8>, issuer: T::AccountId) -> dispatch::DispatchResult {
+            ensure_root(origin)?;
+
+            let symbol = Self::normalize_symbol(&symbol)?;
+            ensure!(!TokensBySymbol::<T>::contains_key(&symbol), Error::<T>::SymbolAlreadyExists);
+
+            ReservedSymbols::<T>::insert(&symbol, issuer.clone());
+            Self::deposit_event(RawEvent::SymbolReserved(symbol, issuer));
+
+            Ok(())
+        }
+
+		#[weight = 200_000]
+        pub fn unreserve_symbol(origin, symbol: Vec<u8>) -> dispatch::DispatchResult {
+            ensure_root(origin)?;
+
+            let symbol = Self::normalize_symbol(&symbol)?;
+            ensure!(ReservedSymbols::<T>::contains_key(&symbol), Error::<T>::SymbolNotReserved);
+
+            ReservedSymbols::<T>::remove(&symbol);
+            Self::deposit_event(RawEvent::SymbolUnreserved(symbol));
+
+            Ok(())
+        }
+
+        /// Remove the symbol from the registry, the token keeps existing but can't be looked up by the symbol,
+        /// and no one can issue the symbol until root reserves it for an account
+		#[weight = 200_000]
+        pub fn revoke_symbol(origin, symbol: Vec<u8>) -> dispatch::DispatchResult {
+            ensure_root(origin)?;
+
+            let symbol = Self::normalize_symbol(&symbol)?;
+            let hash = Self::token_hash_by_symbol(&symbol).ok_or(Error::<T>::SymbolNotFound)?;
+
+            TokensBySymbol::<T>::remove(&symbol);
+            RevokedSymbols::insert(&symbol, true);
+            Self::deposit_event(RawEvent::SymbolRevoked(symbol, hash));
+
+            Ok(())
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            Self::migrate_tokens() + Self::register_symbols() + Self::migrate_reserves() + Self::migrate_minters()
         }
     }
 }
@@ -102,22 +594,87 @@ decl_module! {
 impl<T: Trait> Module<T> {
     pub fn do_issue(origin: T::Origin, symbol: Vec<u8>, total_supply: T::Balance) -> dispatch::DispatchResult {
         let sender = ensure_signed(origin)?;
+        Self::ensure_issuable_symbol(&symbol)?;
+
+        Self::do_create(sender, symbol, total_supply)?;
+
+        Ok(())
+    }
+
+    fn do_approve(owner: T::AccountId, spender: T::AccountId, hash: T::Hash, amount: T::Balance) {
+        if amount.is_zero() {
+            Allowances::<T>::remove((owner.clone(), spender.clone(), hash));
+        } else {
+            Allowances::<T>::insert((owner.clone(), spender.clone(), hash), amount);
+        }
+
+        Self::deposit_event(RawEvent::Approved(owner, spender, hash, amount));
+    }
+
+    /// Upper case the symbol and check its length and characters
+    pub fn normalize_symbol(symbol: &[u8]) -> Result<Vec<u8>, dispatch::DispatchError> {
+        ensure!(!symbol.is_empty() && symbol.len() <= MAX_SYMBOL_LENGTH, Error::<T>::InvalidSymbol);
+        ensure!(
+            symbol.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'-'),
+            Error::<T>::InvalidSymbol
+        );
+
+        Ok(symbol.to_ascii_uppercase())
+    }
+
+    // the symbols with '-' are left to the tokens created by the other modules, like the LP tokens
+    fn ensure_issuable_symbol(symbol: &[u8]) -> dispatch::DispatchResult {
+        ensure!(symbol.iter().all(|c| c.is_ascii_alphanumeric()), Error::<T>::InvalidSymbol);
+
+        Ok(())
+    }
 
+    /// The token of the symbol, the symbol is normalized first
+    pub fn token_by_symbol(symbol: Vec<u8>) -> Option<T::Hash> {
+        let symbol = Self::normalize_symbol(&symbol).ok()?;
+        Self::token_hash_by_symbol(symbol)
+    }
+
+    /// Create a token, `sender` owns it and holds all the supply
+    pub fn do_create(sender: T::AccountId, symbol: Vec<u8>, total_supply: T::Balance) -> Result<T::Hash, dispatch::DispatchError> {
         let nonce = Nonce::get();
 
         let random_seed = <randomness_collective_flip::Module<T>>::random_seed();
         let hash = (random_seed, sender.clone(), nonce)
             .using_encoded(<T as system::Trait>::Hashing::hash);
 
+        Self::create_with_hash(sender, hash, symbol, total_supply)
+    }
+
+    fn create_with_hash(
+        sender: T::AccountId,
+        hash: T::Hash,
+        symbol: Vec<u8>,
+        total_supply: T::Balance,
+    ) -> Result<T::Hash, dispatch::DispatchError> {
+        let symbol = Self::normalize_symbol(&symbol)?;
+        ensure!(!TokensBySymbol::<T>::contains_key(&symbol), Error::<T>::SymbolAlreadyExists);
+        match Self::reserved_symbol(&symbol) {
+            Some(issuer) => ensure!(issuer == sender, Error::<T>::SymbolReserved),
+            None => ensure!(!Self::is_revoked_symbol(&symbol), Error::<T>::SymbolRevoked),
+        }
+
         let token = Token::<T::Hash, T::Balance> {
             hash: hash.clone(),
             total_supply,
             symbol: symbol.clone(),
+            name: symbol.clone(),
+            decimals: 0,
+            icon_uri: None,
+            description: None,
         };
 
         Nonce::mutate(|n| *n += 1);
         Tokens::<T>::insert(hash.clone(), token);
         Owners::<T>::insert(hash.clone(), sender.clone());
+        TokensBySymbol::<T>::insert(&symbol, hash.clone());
+        ReservedSymbols::<T>::remove(&symbol);
+        RevokedSymbols::remove(&symbol);
         BalanceOf::<T>::insert((sender.clone(), hash.clone()), total_supply);
         FreeBalanceOf::<T>::insert((sender.clone(), hash.clone()), total_supply);
 
@@ -127,6 +684,257 @@ impl<T: Trait> Module<T> {
 
         Self::deposit_event(RawEvent::Issued(sender, hash.clone(), total_supply));
 
+        Ok(hash)
+    }
+
+    pub fn ensure_metadata(
+        name: &[u8],
+        decimals: u8,
+        icon_uri: &Option<Vec<u8>>,
+        description: &Option<Vec<u8>>,
+    ) -> dispatch::DispatchResult {
+        ensure!(!name.is_empty() && name.len() <= MAX_NAME_LENGTH, Error::<T>::InvalidName);
+        ensure!(decimals <= MAX_DECIMALS, Error::<T>::DecimalsTooLarge);
+
+        if let Some(icon_uri) = icon_uri {
+            ensure!(icon_uri.len() <= MAX_ICON_URI_LENGTH, Error::<T>::IconUriLengthExceedLimitation);
+        }
+
+        if let Some(description) = description {
+            ensure!(description.len() <= MAX_DESCRIPTION_LENGTH, Error::<T>::DescriptionLengthExceedLimitation);
+        }
+
+        Ok(())
+    }
+
+    /// Set the metadata of the token, it should be checked by `ensure_metadata` first
+    fn do_set_metadata(
+        sender: T::AccountId,
+        hash: T::Hash,
+        name: Vec<u8>,
+        decimals: u8,
+        icon_uri: Option<Vec<u8>>,
+        description: Option<Vec<u8>>,
+    ) -> dispatch::DispatchResult {
+        let mut token = Self::token(hash).ok_or(Error::<T>::NoMatchingToken)?;
+
+        token.name = name;
+        token.decimals = decimals;
+        token.icon_uri = icon_uri;
+        token.description = description;
+        Tokens::<T>::insert(hash, token);
+
+        Self::deposit_event(RawEvent::MetadataUpdated(sender, hash));
+
+        Ok(())
+    }
+
+    /// The decimals of the token, 0 if there is no such token
+    pub fn decimals(hash: T::Hash) -> u8 {
+        Self::token(hash).map(|token| token.decimals).unwrap_or(0)
+    }
+
+    // bring the tokens stored in an old layout to the current one, once, the tokens of the old layout all have an owner
+    fn migrate_tokens() -> Weight {
+        if TokensMigrated::get() {
+            return 0;
+        }
+
+        let mut read: Weight = 0;
+        let mut migrated: Weight = 0;
+        for (hash, _) in Owners::<T>::iter() {
+            read += 1;
+            let raw = match unhashed::get_raw(&<Tokens<T>>::hashed_key_for(hash)) {
+                Some(raw) => raw,
+                None => continue,
+            };
+
+            let input = &mut &raw[..];
+            if Token::<T::Hash, T::Balance>::decode(input).is_ok() && input.is_empty() {
+                continue;
+            }
+
+            if let Ok(old) = TokenV0::<T::Hash, T::Balance>::decode(&mut &raw[..]) {
+                let token = Token {
+                    hash: old.hash,
+                    name: old.symbol.clone(),
+                    symbol: old.symbol,
+                    total_supply: old.total_supply,
+                    decimals: 0,
+                    icon_uri: None,
+                    description: None,
+                };
+                Tokens::<T>::insert(hash, token);
+                migrated += 1;
+            }
+        }
+        TokensMigrated::put(true);
+
+        (read + migrated) * 100_000
+    }
+
+    // register the symbols of the tokens created before the symbol registry, once,
+    // one token of a duplicated symbol gets it and root can revoke it later
+    fn register_symbols() -> Weight {
+        if SymbolRegistryComplete::get() {
+            return 0;
+        }
+
+        let mut registered: Weight = 0;
+        for (hash, _) in Owners::<T>::iter() {
+            let symbol = match Self::token(hash).map(|token| Self::normalize_symbol(&token.symbol)) {
+                Some(Ok(symbol)) => symbol,
+                _ => continue,
+            };
+
+            if !TokensBySymbol::<T>::contains_key(&symbol) {
+                TokensBySymbol::<T>::insert(&symbol, hash);
+                registered += 1;
+            }
+        }
+        SymbolRegistryComplete::put(true);
+
+        registered * 100_000
+    }
+
+    /// Move the balances frozen before the named reserves to the legacy reserve of the account
+    pub fn migrate_reserves() -> Weight {
+        let mut migrated: Weight = 0;
+
+        for ((who, hash), amount) in FreezedBalanceOf::<T>::drain() {
+            migrated += 1;
+            if amount.is_zero() {
+                continue;
+            }
+
+            ReservedBalanceOf::<T>::mutate((who.clone(), hash), |reserved| *reserved = reserved.saturating_add(amount));
+            NamedReserves::<T>::mutate((who, hash, ReserveId::Named(LEGACY_RESERVE)), |reserved| *reserved = reserved.saturating_add(amount));
+        }
+
+        migrated * 100_000
+    }
+
+    /// Move the minters set before the roles to the minter role, unless the role is already given
+    pub fn migrate_minters() -> Weight {
+        let mut migrated: Weight = 0;
+
+        for (hash, minter) in Minters::<T>::drain() {
+            migrated += 1;
+            if !Roles::<T>::contains_key((hash, TokenRole::Minter)) {
+                Roles::<T>::insert((hash, TokenRole::Minter), minter);
+            }
+        }
+
+        migrated * 100_000
+    }
+
+    fn do_set_role(hash: T::Hash, role: TokenRole, account: Option<T::AccountId>) {
+        match account.clone() {
+            Some(account) => Roles::<T>::insert((hash, role), account),
+            None => Roles::<T>::remove((hash, role)),
+        }
+        Self::deposit_event(RawEvent::RoleSet(hash, role, account));
+    }
+
+    /// The account holding the role of the token, the owner unless the role is given to another account
+    pub fn role(hash: T::Hash, role: TokenRole) -> Option<T::AccountId> {
+        Self::role_holder((hash, role)).or_else(|| Self::owner(hash))
+    }
+
+    /// The account allowed to mint the token, the owner unless a minter is set
+    pub fn minter(hash: T::Hash) -> Option<T::AccountId> {
+        Self::role(hash, TokenRole::Minter)
+    }
+
+    pub fn has_role(hash: T::Hash, role: TokenRole, who: &T::AccountId) -> bool {
+        Self::role(hash, role).as_ref() == Some(who)
+    }
+
+    fn ensure_freezer(hash: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        ensure!(Self::token(hash).is_some(), Error::<T>::NoMatchingToken);
+        ensure!(Self::has_role(hash, TokenRole::Freezer, who), Error::<T>::SenderNotFreezer);
+
+        Ok(())
+    }
+
+    pub fn ensure_not_paused(hash: T::Hash) -> dispatch::DispatchResult {
+        ensure!(!Self::is_paused(hash), Error::<T>::TokenPaused);
+
+        Ok(())
+    }
+
+    /// The token is not paused and the account is not frozen, so the account could send and receive the token
+    pub fn ensure_transferable(hash: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        Self::ensure_not_paused(hash)?;
+        ensure!(!Self::is_frozen_account((hash, who.clone())), Error::<T>::AccountFrozen);
+
+        Ok(())
+    }
+
+    pub fn ensure_owner(hash: T::Hash, who: &T::AccountId) -> dispatch::DispatchResult {
+        ensure!(Self::token(hash).is_some(), Error::<T>::NoMatchingToken);
+        ensure!(Self::owner(hash).as_ref() == Some(who), Error::<T>::SenderNotOwner);
+
+        Ok(())
+    }
+
+    /// All the checks of `do_transfer`, so that a caller could validate several transfers before the first write
+    pub fn ensure_can_transfer(sender: &T::AccountId, hash: T::Hash, to: &T::AccountId, amount: T::Balance) -> dispatch::DispatchResult {
+        Self::ensure_free_balance(sender.clone(), hash, amount)?;
+        Self::ensure_transferable(hash, sender)?;
+        Self::ensure_transferable(hash, to)?;
+
+        if sender != to {
+            Self::balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        }
+
+        Ok(())
+    }
+
+    /// All the checks of `do_mint`
+    pub fn ensure_can_mint(to: &T::AccountId, hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
+        let token = Self::token(hash).ok_or(Error::<T>::NoMatchingToken)?;
+        Self::ensure_transferable(hash, to)?;
+
+        let total_supply = token.total_supply.checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        if let Some(max_supply) = Self::max_supply(hash) {
+            ensure!(total_supply <= max_supply, Error::<T>::MaxSupplyExceeded);
+        }
+        Self::balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+
+        Ok(())
+    }
+
+    /// Mint to the account, a paused token or a frozen account is not credited like by a transfer
+    pub fn do_mint(to: T::AccountId, hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
+        let mut token = Self::token(hash).ok_or(Error::<T>::NoMatchingToken)?;
+        Self::ensure_transferable(hash, &to)?;
+
+        token.total_supply = token.total_supply.checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        if let Some(max_supply) = Self::max_supply(hash) {
+            ensure!(token.total_supply <= max_supply, Error::<T>::MaxSupplyExceeded);
+        }
+        let new_amount = Self::balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        let new_free_amount = Self::free_balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+
+        Tokens::<T>::insert(hash, token);
+        BalanceOf::<T>::insert((to.clone(), hash), new_amount);
+        FreeBalanceOf::<T>::insert((to.clone(), hash), new_free_amount);
+
+        Ok(())
+    }
+
+    pub fn do_burn(from: T::AccountId, hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
+        let mut token = Self::token(hash).ok_or(Error::<T>::NoMatchingToken)?;
+
+        let new_amount = Self::balance_of((from.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::BalanceNotEnough)?;
+        let new_free_amount = Self::free_balance_of((from.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::BalanceNotEnough)?;
+        token.total_supply = token.total_supply.checked_sub(&amount).ok_or(Error::<T>::AmountOverflow)?;
+
+        Tokens::<T>::insert(hash, token);
+        BalanceOf::<T>::insert((from.clone(), hash), new_amount);
+        FreeBalanceOf::<T>::insert((from.clone(), hash), new_free_amount);
+
         Ok(())
     }
 
@@ -149,6 +957,9 @@ impl<T: Trait> Module<T> {
             Error::<T>::SenderHaveNoToken 
         );
 
+        Self::ensure_transferable(hash, &sender)?;
+        Self::ensure_transferable(hash, &to)?;
+
         let from_amount = Self::balance_of((sender.clone(), hash.clone()));
         ensure!(from_amount >= amount, Error::<T>::BalanceNotEnough);
         let new_from_amount = from_amount - amount;
@@ -182,60 +993,79 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    pub fn do_freeze(sender: T::AccountId, hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
-        let token = Self::token(hash);
-        ensure!(token.is_some(), Error::<T>::NoMatchingToken);
+    fn set_named_reserve(who: T::AccountId, hash: T::Hash, id: ReserveId<T::Hash>, amount: T::Balance) {
+        if amount.is_zero() {
+            NamedReserves::<T>::remove((who, hash, id));
+        } else {
+            NamedReserves::<T>::insert((who, hash, id), amount);
+        }
+    }
+
+    /// Hold `amount` of the free balance in the reserve `id` of the account
+    pub fn reserve(who: T::AccountId, hash: T::Hash, id: ReserveId<T::Hash>, amount: T::Balance) -> dispatch::DispatchResult {
+        ensure!(Self::token(hash).is_some(), Error::<T>::NoMatchingToken);
 
         ensure!(
-            FreeBalanceOf::<T>::contains_key((sender.clone(), hash)),
+            FreeBalanceOf::<T>::contains_key((who.clone(), hash)),
             Error::<T>::SenderHaveNoToken
         );
 
-        let old_free_amount = Self::free_balance_of((sender.clone(), hash.clone()));
-        ensure!(
-            old_free_amount >= amount,
-            Error::<T>::BalanceNotEnough
-        );
+        let new_free_amount = Self::free_balance_of((who.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::BalanceNotEnough)?;
+        let new_reserved_amount = Self::reserved_balance_of((who.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        let new_named_amount = Self::named_reserve((who.clone(), hash, id)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
 
-        let old_freezed_amount = Self::freezed_balance_of((sender.clone(), hash.clone()));
-        ensure!(
-            old_freezed_amount + amount <= T::Balance::max_value(),
-            Error::<T>::AmountOverflow
-        );
+        FreeBalanceOf::<T>::insert((who.clone(), hash), new_free_amount);
+        ReservedBalanceOf::<T>::insert((who.clone(), hash), new_reserved_amount);
+        Self::set_named_reserve(who.clone(), hash, id, new_named_amount);
 
-        FreeBalanceOf::<T>::insert((sender.clone(), hash.clone()), old_free_amount - amount);
-        FreezedBalanceOf::<T>::insert((sender.clone(), hash.clone()), old_freezed_amount + amount);
+        Self::deposit_event(RawEvent::Reserved(who, hash, id, amount));
 
-        Self::deposit_event(RawEvent::Freezed(sender, hash, amount));
+        Ok(())
+    }
+
+    /// Give `amount` of the reserve `id` back to the free balance of the account
+    pub fn unreserve(who: T::AccountId, hash: T::Hash, id: ReserveId<T::Hash>, amount: T::Balance) -> dispatch::DispatchResult {
+        ensure!(Self::token(hash).is_some(), Error::<T>::NoMatchingToken);
+
+        let new_named_amount = Self::named_reserve((who.clone(), hash, id)).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_reserved_amount = Self::reserved_balance_of((who.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_free_amount = Self::free_balance_of((who.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+
+        FreeBalanceOf::<T>::insert((who.clone(), hash), new_free_amount);
+        ReservedBalanceOf::<T>::insert((who.clone(), hash), new_reserved_amount);
+        Self::set_named_reserve(who.clone(), hash, id, new_named_amount);
+
+        Self::deposit_event(RawEvent::Unreserved(who, hash, id, amount));
 
         Ok(())
     }
 
-    pub fn do_unfreeze(sender: T::AccountId, hash: T::Hash, amount: T::Balance) -> dispatch::DispatchResult {
-        let token = Self::token(hash);
-        ensure!(token.is_some(), Error::<T>::NoMatchingToken);
+    /// Pay `amount` of the reserve `id` of `from` to the free balance of `to`
+    pub fn repatriate_reserved(from: T::AccountId, hash: T::Hash, id: ReserveId<T::Hash>, to: T::AccountId,
+                               amount: T::Balance) -> dispatch::DispatchResult {
+        ensure!(Self::token(hash).is_some(), Error::<T>::NoMatchingToken);
 
-        ensure!(
-            FreeBalanceOf::<T>::contains_key((sender.clone(), hash)),
-            Error::<T>::SenderHaveNoToken
-        );
+        Self::ensure_transferable(hash, &from)?;
+        Self::ensure_transferable(hash, &to)?;
 
-        let old_freezed_amount = Self::freezed_balance_of((sender.clone(), hash.clone()));
-        ensure!(
-            old_freezed_amount >= amount,
-            Error::<T>::BalanceNotEnough
-        );
+        if from == to {
+            return Self::unreserve(from, hash, id, amount);
+        }
 
-        let old_free_amount = Self::free_balance_of((sender.clone(), hash.clone()));
-        ensure!(
-            old_free_amount + amount <= T::Balance::max_value(),
-            Error::<T>::AmountOverflow
-        );
+        let new_named_amount = Self::named_reserve((from.clone(), hash, id)).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_reserved_amount = Self::reserved_balance_of((from.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::ReservedBalanceNotEnough)?;
+        let new_from_amount = Self::balance_of((from.clone(), hash)).checked_sub(&amount).ok_or(Error::<T>::BalanceNotEnough)?;
+
+        let new_to_amount = Self::balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
+        let new_to_free_amount = Self::free_balance_of((to.clone(), hash)).checked_add(&amount).ok_or(Error::<T>::AmountOverflow)?;
 
-        FreeBalanceOf::<T>::insert((sender.clone(), hash.clone()), old_free_amount + amount);
-        FreezedBalanceOf::<T>::insert((sender.clone(), hash.clone()), old_freezed_amount - amount);
+        Self::set_named_reserve(from.clone(), hash, id, new_named_amount);
+        ReservedBalanceOf::<T>::insert((from.clone(), hash), new_reserved_amount);
+        BalanceOf::<T>::insert((from.clone(), hash), new_from_amount);
+        BalanceOf::<T>::insert((to.clone(), hash), new_to_amount);
+        FreeBalanceOf::<T>::insert((to.clone(), hash), new_to_free_amount);
 
-        Self::deposit_event(RawEvent::UnFreezed(sender, hash, amount));
+        Self::deposit_event(RawEvent::ReserveRepatriated(from, to, hash, id, amount));
 
         Ok(())
     }
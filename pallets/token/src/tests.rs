@@ -1,6 +1,7 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize}};
+use crate::{Error, TokenRole, ReserveId, LEGACY_RESERVE, FreeBalanceOf, FreezedBalanceOf, mock::*};
+use frame_support::{assert_ok, assert_err, StorageMap, traits::{OnFinalize, OnInitialize}};
 use sp_core::H256;
+use sp_runtime::traits::BadOrigin;
 
 fn run_to_block(n: u64) {
 	while System::block_number() < n {
@@ -46,7 +47,7 @@ fn token_tests() {
 
 		assert_eq!(TokenModule::balance_of((alice, token.hash)), 21000000);
 		assert_eq!(TokenModule::free_balance_of((alice, token.hash)), 21000000);
-		assert_eq!(TokenModule::freezed_balance_of((alice, token.hash)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((alice, token.hash)), 0);
 
 		assert_ok!(TokenModule::transfer(
 			Origin::signed(alice),
@@ -57,10 +58,10 @@ fn token_tests() {
 		));
 		assert_eq!(TokenModule::balance_of((alice, token.hash)), 20999900);
 		assert_eq!(TokenModule::free_balance_of((alice, token.hash)), 20999900);
-		assert_eq!(TokenModule::freezed_balance_of((alice, token.hash)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((alice, token.hash)), 0);
 		assert_eq!(TokenModule::balance_of((bob, token.hash)), 100);
 		assert_eq!(TokenModule::free_balance_of((bob, token.hash)), 100);
-		assert_eq!(TokenModule::freezed_balance_of((bob, token.hash)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((bob, token.hash)), 0);
 
 		assert_err!(
 			TokenModule::transfer(Origin::signed(bob), H256::from_low_u64_be(0), charlie, 101, None),
@@ -76,3 +77,531 @@ fn token_tests() {
 		);
 	});
 }
+
+#[test]
+fn mint_and_burn_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+		let charlie = 30u64;
+
+		assert_ok!(TokenModule::issue_capped(Origin::signed(alice), b"66".to_vec(), 1000, 1500));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_eq!(TokenModule::max_supply(token_hash), Some(1500));
+		assert_eq!(TokenModule::role(token_hash, TokenRole::Minter), Some(alice));
+
+		assert_err!(
+			TokenModule::issue_capped(Origin::signed(alice), b"77".to_vec(), 1000, 999),
+			Error::<Test>::MaxSupplyTooSmall
+		);
+
+		assert_ok!(TokenModule::mint(Origin::signed(alice), token_hash, bob, 300));
+		assert_eq!(TokenModule::token(token_hash).unwrap().total_supply, 1300);
+		assert_eq!(TokenModule::balance_of((bob, token_hash)), 300);
+		assert_eq!(TokenModule::free_balance_of((bob, token_hash)), 300);
+
+		assert_err!(
+			TokenModule::mint(Origin::signed(bob), token_hash, bob, 100),
+			Error::<Test>::SenderNotMinter
+		);
+		assert_err!(
+			TokenModule::set_role(Origin::signed(bob), token_hash, TokenRole::Minter, Some(bob)),
+			Error::<Test>::SenderNotOwner
+		);
+
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::Minter, Some(charlie)));
+		assert_eq!(TokenModule::role(token_hash, TokenRole::Minter), Some(charlie));
+		assert_err!(
+			TokenModule::mint(Origin::signed(alice), token_hash, alice, 100),
+			Error::<Test>::SenderNotMinter
+		);
+		assert_err!(
+			TokenModule::mint(Origin::signed(charlie), token_hash, charlie, 201),
+			Error::<Test>::MaxSupplyExceeded
+		);
+		assert_ok!(TokenModule::mint(Origin::signed(charlie), token_hash, charlie, 200));
+		assert_eq!(TokenModule::token(token_hash).unwrap().total_supply, 1500);
+
+		// set_minter gives the same role as set_role
+		assert_err!(
+			TokenModule::set_minter(Origin::signed(charlie), token_hash, charlie),
+			Error::<Test>::SenderNotOwner
+		);
+		assert_ok!(TokenModule::set_minter(Origin::signed(alice), token_hash, bob));
+		assert_eq!(TokenModule::minter(token_hash), Some(bob));
+		assert_eq!(TokenModule::role(token_hash, TokenRole::Minter), Some(bob));
+		assert_eq!(TokenModule::role_holder((token_hash, TokenRole::Minter)), Some(bob));
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::Minter, None));
+		assert_eq!(TokenModule::role_holder((token_hash, TokenRole::Minter)), None);
+		assert_eq!(TokenModule::minter(token_hash), Some(alice));
+
+		// the minters set before the roles become the minter role, a role given since is kept
+		<Minters<Test>>::insert(token_hash, charlie);
+		TokenModule::migrate_minters();
+		assert_eq!(TokenModule::minter(token_hash), Some(charlie));
+		assert_ok!(TokenModule::set_minter(Origin::signed(alice), token_hash, bob));
+		<Minters<Test>>::insert(token_hash, charlie);
+		TokenModule::migrate_minters();
+		assert_eq!(TokenModule::minter(token_hash), Some(bob));
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::Minter, None));
+
+		assert_err!(
+			TokenModule::burn(Origin::signed(bob), token_hash, 301),
+			Error::<Test>::BalanceNotEnough
+		);
+		assert_ok!(TokenModule::burn(Origin::signed(bob), token_hash, 100));
+		assert_eq!(TokenModule::token(token_hash).unwrap().total_supply, 1400);
+		assert_eq!(TokenModule::balance_of((bob, token_hash)), 200);
+		assert_eq!(TokenModule::free_balance_of((bob, token_hash)), 200);
+	});
+}
+
+#[test]
+fn metadata_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token = TokenModule::token(token_hash).unwrap();
+		assert_eq!(token.name, b"66".to_vec());
+		assert_eq!(token.decimals, 0);
+		assert_eq!(token.icon_uri, None);
+		assert_eq!(token.description, None);
+
+		assert_ok!(TokenModule::issue_with_metadata(
+			Origin::signed(bob),
+			b"77".to_vec(),
+			1000,
+			b"Token 77".to_vec(),
+			12,
+			Some(b"https://example.com/77.png".to_vec()),
+			None
+		));
+		let token_hash2 = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token_hash2).unwrap();
+		assert_eq!(token2.symbol, b"77".to_vec());
+		assert_eq!(token2.name, b"Token 77".to_vec());
+		assert_eq!(token2.decimals, 12);
+		assert_eq!(token2.icon_uri, Some(b"https://example.com/77.png".to_vec()));
+		assert_eq!(TokenModule::decimals(token_hash2), 12);
+
+		assert_err!(
+			TokenModule::issue_with_metadata(Origin::signed(bob), b"88".to_vec(), 1000, vec![], 12, None, None),
+			Error::<Test>::InvalidName
+		);
+		assert_eq!(TokenModule::owned_token_index(bob), 1);
+
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(bob), token_hash, b"Token 66".to_vec(), 8, None, None),
+			Error::<Test>::SenderNotMetadataAdmin
+		);
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(alice), token_hash, b"Token 66".to_vec(), 19, None, None),
+			Error::<Test>::DecimalsTooLarge
+		);
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(alice), token_hash, vec![b'6'; 65], 8, None, None),
+			Error::<Test>::InvalidName
+		);
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(alice), token_hash, b"Token 66".to_vec(), 8, Some(vec![b'a'; 257]), None),
+			Error::<Test>::IconUriLengthExceedLimitation
+		);
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(alice), token_hash, b"Token 66".to_vec(), 8, None, Some(vec![b'a'; 513])),
+			Error::<Test>::DescriptionLengthExceedLimitation
+		);
+
+		assert_ok!(TokenModule::set_metadata(
+			Origin::signed(alice),
+			token_hash,
+			b"Token 66".to_vec(),
+			8,
+			None,
+			Some(b"the token 66".to_vec())
+		));
+		let token = TokenModule::token(token_hash).unwrap();
+		assert_eq!(token.symbol, b"66".to_vec());
+		assert_eq!(token.name, b"Token 66".to_vec());
+		assert_eq!(token.decimals, 8);
+		assert_eq!(token.description, Some(b"the token 66".to_vec()));
+		assert_eq!(token.total_supply, 1000);
+	});
+}
+
+#[test]
+fn symbol_registry_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"usdx".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_eq!(TokenModule::token(token_hash).unwrap().symbol, b"USDX".to_vec());
+		assert_eq!(TokenModule::token_by_symbol(b"UsDx".to_vec()), Some(token_hash));
+		assert_eq!(TokenModule::token_by_symbol(b"ABC".to_vec()), None);
+
+		assert_err!(
+			TokenModule::issue(Origin::signed(bob), b"USDx".to_vec(), 1000),
+			Error::<Test>::SymbolAlreadyExists
+		);
+		assert_err!(TokenModule::issue(Origin::signed(bob), vec![], 1000), Error::<Test>::InvalidSymbol);
+		assert_err!(TokenModule::issue(Origin::signed(bob), b"US DX".to_vec(), 1000), Error::<Test>::InvalidSymbol);
+		assert_err!(TokenModule::issue(Origin::signed(bob), b"LP-0".to_vec(), 1000), Error::<Test>::InvalidSymbol);
+		assert_err!(TokenModule::issue(Origin::signed(bob), vec![b'A'; 33], 1000), Error::<Test>::InvalidSymbol);
+
+		assert_err!(
+			TokenModule::reserve_symbol(Origin::signed(alice), b"DOT".to_vec(), alice),
+			BadOrigin
+		);
+		assert_err!(
+			TokenModule::reserve_symbol(frame_system::RawOrigin::Root.into(), b"usdx".to_vec(), bob),
+			Error::<Test>::SymbolAlreadyExists
+		);
+		assert_ok!(TokenModule::reserve_symbol(frame_system::RawOrigin::Root.into(), b"dot".to_vec(), alice));
+		assert_eq!(TokenModule::reserved_symbol(b"DOT".to_vec()), Some(alice));
+		assert_err!(
+			TokenModule::issue(Origin::signed(bob), b"DOT".to_vec(), 1000),
+			Error::<Test>::SymbolReserved
+		);
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"DOT".to_vec(), 1000));
+		assert_eq!(TokenModule::reserved_symbol(b"DOT".to_vec()), None);
+		assert_eq!(TokenModule::token_by_symbol(b"DOT".to_vec()), TokenModule::owned_token((alice, 1)));
+
+		assert_ok!(TokenModule::reserve_symbol(frame_system::RawOrigin::Root.into(), b"KSM".to_vec(), alice));
+		assert_ok!(TokenModule::unreserve_symbol(frame_system::RawOrigin::Root.into(), b"KSM".to_vec()));
+		assert_err!(
+			TokenModule::unreserve_symbol(frame_system::RawOrigin::Root.into(), b"KSM".to_vec()),
+			Error::<Test>::SymbolNotReserved
+		);
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"KSM".to_vec(), 1000));
+
+		assert_err!(
+			TokenModule::revoke_symbol(frame_system::RawOrigin::Root.into(), b"ABC".to_vec()),
+			Error::<Test>::SymbolNotFound
+		);
+		assert_ok!(TokenModule::revoke_symbol(frame_system::RawOrigin::Root.into(), b"usdx".to_vec()));
+		assert_eq!(TokenModule::token_by_symbol(b"USDX".to_vec()), None);
+		assert!(TokenModule::token(token_hash).is_some());
+		assert!(TokenModule::is_revoked_symbol(b"USDX".to_vec()));
+		assert_err!(
+			TokenModule::issue(Origin::signed(bob), b"USDX".to_vec(), 1000),
+			Error::<Test>::SymbolRevoked
+		);
+
+		// root gives the revoked symbol to an issuer
+		assert_ok!(TokenModule::reserve_symbol(frame_system::RawOrigin::Root.into(), b"usdx".to_vec(), bob));
+		assert_ok!(TokenModule::unreserve_symbol(frame_system::RawOrigin::Root.into(), b"usdx".to_vec()));
+		assert_err!(
+			TokenModule::issue(Origin::signed(bob), b"USDX".to_vec(), 1000),
+			Error::<Test>::SymbolRevoked
+		);
+		assert_ok!(TokenModule::reserve_symbol(frame_system::RawOrigin::Root.into(), b"usdx".to_vec(), bob));
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"USDX".to_vec(), 1000));
+		assert_eq!(TokenModule::token_by_symbol(b"USDX".to_vec()), TokenModule::owned_token((bob, 1)));
+		assert!(!TokenModule::is_revoked_symbol(b"USDX".to_vec()));
+	});
+}
+
+#[test]
+fn allowance_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+		let charlie = 30u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+
+		assert_err!(
+			TokenModule::approve(Origin::signed(alice), H256::from_low_u64_be(0), bob, 100),
+			Error::<Test>::NoMatchingToken
+		);
+		assert_ok!(TokenModule::approve(Origin::signed(alice), token_hash, bob, 100));
+		assert_eq!(TokenModule::allowance((alice, bob, token_hash)), 100);
+		assert_ok!(TokenModule::increase_allowance(Origin::signed(alice), token_hash, bob, 50));
+		assert_eq!(TokenModule::allowance((alice, bob, token_hash)), 150);
+		assert_err!(
+			TokenModule::decrease_allowance(Origin::signed(alice), token_hash, bob, 151),
+			Error::<Test>::AllowanceNotEnough
+		);
+		assert_ok!(TokenModule::decrease_allowance(Origin::signed(alice), token_hash, bob, 30));
+		assert_eq!(TokenModule::allowance((alice, bob, token_hash)), 120);
+
+		assert_err!(
+			TokenModule::transfer_from(Origin::signed(bob), token_hash, alice, charlie, 121, None),
+			Error::<Test>::AllowanceNotEnough
+		);
+		assert_err!(
+			TokenModule::transfer_from(Origin::signed(charlie), token_hash, alice, charlie, 1, None),
+			Error::<Test>::AllowanceNotEnough
+		);
+		assert_ok!(TokenModule::transfer_from(Origin::signed(bob), token_hash, alice, charlie, 100, None));
+		assert_eq!(TokenModule::allowance((alice, bob, token_hash)), 20);
+		assert_eq!(TokenModule::balance_of((alice, token_hash)), 900);
+		assert_eq!(TokenModule::free_balance_of((charlie, token_hash)), 100);
+
+		// the allowance is kept when the transfer fails
+		assert_ok!(TokenModule::approve(Origin::signed(charlie), token_hash, bob, 200));
+		assert_err!(
+			TokenModule::transfer_from(Origin::signed(bob), token_hash, charlie, bob, 101, None),
+			Error::<Test>::BalanceNotEnough
+		);
+		assert_eq!(TokenModule::allowance((charlie, bob, token_hash)), 200);
+		assert_eq!(TokenModule::balance_of((charlie, token_hash)), 100);
+
+		assert_ok!(TokenModule::transfer_from(Origin::signed(bob), token_hash, alice, bob, 20, None));
+		assert_eq!(TokenModule::allowance((alice, bob, token_hash)), 0);
+		assert_eq!(TokenModule::balance_of((bob, token_hash)), 20);
+	});
+}
+
+#[test]
+fn genesis_config_tests() {
+	let alice = 10u64;
+	let bob = 20u64;
+	let charlie = 30u64;
+	let fixed_hash = H256::from_low_u64_be(1);
+
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	crate::GenesisConfig::<Test> {
+		tokens: vec![
+			(b"66".to_vec(), alice, 1000, Some(fixed_hash)),
+			(b"usdx".to_vec(), bob, 500, None),
+		],
+		balances: vec![
+			(b"66".to_vec(), alice, 700),
+			(b"66".to_vec(), bob, 200),
+			(b"66".to_vec(), charlie, 100),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		let token = TokenModule::token(fixed_hash).unwrap();
+		assert_eq!(token.symbol, b"66".to_vec());
+		assert_eq!(token.total_supply, 1000);
+		assert_eq!(TokenModule::owner(fixed_hash), Some(alice));
+		assert_eq!(TokenModule::token_by_symbol(b"66".to_vec()), Some(fixed_hash));
+		assert_eq!(TokenModule::balance_of((alice, fixed_hash)), 700);
+		assert_eq!(TokenModule::free_balance_of((bob, fixed_hash)), 200);
+		assert_eq!(TokenModule::free_balance_of((charlie, fixed_hash)), 100);
+
+		let usdx_hash = TokenModule::token_by_symbol(b"USDX".to_vec()).unwrap();
+		assert_eq!(TokenModule::owned_token((bob, 0)), Some(usdx_hash));
+		assert_eq!(TokenModule::balance_of((bob, usdx_hash)), 500);
+
+		assert_err!(
+			TokenModule::issue(Origin::signed(charlie), b"66".to_vec(), 1000),
+			Error::<Test>::SymbolAlreadyExists
+		);
+	});
+}
+
+#[test]
+#[should_panic(expected = "the genesis balances of a token don't sum to its supply")]
+fn genesis_config_balances_sum_to_supply() {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	crate::GenesisConfig::<Test> {
+		tokens: vec![(b"66".to_vec(), 10, 1000, None)],
+		balances: vec![(b"66".to_vec(), 10, 700), (b"66".to_vec(), 20, 200)],
+	}.assimilate_storage(&mut t).unwrap();
+}
+
+#[test]
+fn ownership_and_roles_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+		let charlie = 30u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+
+		// the metadata admin role
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::MetadataAdmin, Some(bob)));
+		assert_err!(
+			TokenModule::set_metadata(Origin::signed(alice), token_hash, b"Token 66".to_vec(), 8, None, None),
+			Error::<Test>::SenderNotMetadataAdmin
+		);
+		assert_ok!(TokenModule::set_metadata(Origin::signed(bob), token_hash, b"Token 66".to_vec(), 8, None, None));
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::MetadataAdmin, None));
+		assert_eq!(TokenModule::role(token_hash, TokenRole::MetadataAdmin), Some(alice));
+
+		// two step ownership transfer
+		assert_err!(
+			TokenModule::transfer_ownership(Origin::signed(bob), token_hash, bob),
+			Error::<Test>::SenderNotOwner
+		);
+		assert_ok!(TokenModule::transfer_ownership(Origin::signed(alice), token_hash, bob));
+		assert_eq!(TokenModule::pending_owner(token_hash), Some(bob));
+		assert_eq!(TokenModule::owner(token_hash), Some(alice));
+		assert_err!(
+			TokenModule::accept_ownership(Origin::signed(charlie), token_hash),
+			Error::<Test>::SenderNotPendingOwner
+		);
+		assert_ok!(TokenModule::accept_ownership(Origin::signed(bob), token_hash));
+		assert_eq!(TokenModule::owner(token_hash), Some(bob));
+		assert_eq!(TokenModule::pending_owner(token_hash), None);
+		assert_eq!(TokenModule::role(token_hash, TokenRole::Minter), Some(bob));
+		assert_err!(
+			TokenModule::mint(Origin::signed(alice), token_hash, alice, 100),
+			Error::<Test>::SenderNotMinter
+		);
+		assert_ok!(TokenModule::mint(Origin::signed(bob), token_hash, bob, 100));
+
+		// renounce, nobody could unpause the token or thaw an account after it
+		assert_ok!(TokenModule::set_role(Origin::signed(bob), token_hash, TokenRole::Minter, Some(charlie)));
+		assert_ok!(TokenModule::pause_token(Origin::signed(bob), token_hash));
+		assert_err!(TokenModule::renounce_ownership(Origin::signed(bob), token_hash), Error::<Test>::TokenPaused);
+		assert_ok!(TokenModule::unpause_token(Origin::signed(bob), token_hash));
+		assert_ok!(TokenModule::freeze_account(Origin::signed(bob), token_hash, alice));
+		assert_ok!(TokenModule::freeze_account(Origin::signed(bob), token_hash, alice));
+		assert_eq!(TokenModule::frozen_accounts_count(token_hash), 1);
+		assert_err!(TokenModule::renounce_ownership(Origin::signed(bob), token_hash), Error::<Test>::FrozenAccountsLeft);
+		assert_ok!(TokenModule::thaw_account(Origin::signed(bob), token_hash, alice));
+		assert_ok!(TokenModule::thaw_account(Origin::signed(bob), token_hash, alice));
+		assert_eq!(TokenModule::frozen_accounts_count(token_hash), 0);
+		assert_ok!(TokenModule::renounce_ownership(Origin::signed(bob), token_hash));
+		assert_eq!(TokenModule::owner(token_hash), None);
+		assert_eq!(TokenModule::role(token_hash, TokenRole::Minter), None);
+		assert_err!(
+			TokenModule::mint(Origin::signed(charlie), token_hash, charlie, 100),
+			Error::<Test>::SenderNotMinter
+		);
+		assert_err!(
+			TokenModule::transfer_ownership(Origin::signed(bob), token_hash, bob),
+			Error::<Test>::SenderNotOwner
+		);
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), token_hash, charlie, 100, None));
+	});
+}
+
+#[test]
+fn freeze_and_pause_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+		let charlie = 30u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_hash, bob, 100, None));
+
+		assert_ok!(TokenModule::set_role(Origin::signed(alice), token_hash, TokenRole::Freezer, Some(charlie)));
+		assert_err!(
+			TokenModule::freeze_account(Origin::signed(alice), token_hash, bob),
+			Error::<Test>::SenderNotFreezer
+		);
+		assert_err!(
+			TokenModule::freeze_account(Origin::signed(charlie), H256::from_low_u64_be(2), bob),
+			Error::<Test>::NoMatchingToken
+		);
+
+		// a frozen account can neither send nor receive the token, nor burn it
+		assert_ok!(TokenModule::freeze_account(Origin::signed(charlie), token_hash, bob));
+		assert!(TokenModule::is_frozen_account((token_hash, bob)));
+		assert_err!(
+			TokenModule::transfer(Origin::signed(bob), token_hash, alice, 10, None),
+			Error::<Test>::AccountFrozen
+		);
+		assert_err!(
+			TokenModule::transfer(Origin::signed(alice), token_hash, bob, 10, None),
+			Error::<Test>::AccountFrozen
+		);
+		assert_err!(TokenModule::burn(Origin::signed(bob), token_hash, 10), Error::<Test>::AccountFrozen);
+		assert_err!(TokenModule::mint(Origin::signed(alice), token_hash, bob, 10), Error::<Test>::AccountFrozen);
+		assert_eq!(TokenModule::balance_of((bob, token_hash)), 100);
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_hash, charlie, 10, None));
+
+		assert_ok!(TokenModule::thaw_account(Origin::signed(charlie), token_hash, bob));
+		assert!(!TokenModule::is_frozen_account((token_hash, bob)));
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), token_hash, alice, 10, None));
+
+		// a paused token can't be moved by anyone
+		assert_err!(TokenModule::pause_token(Origin::signed(bob), token_hash), Error::<Test>::SenderNotFreezer);
+		assert_ok!(TokenModule::pause_token(Origin::signed(charlie), token_hash));
+		assert!(TokenModule::is_paused(token_hash));
+		assert_err!(
+			TokenModule::transfer(Origin::signed(alice), token_hash, charlie, 10, None),
+			Error::<Test>::TokenPaused
+		);
+		assert_err!(TokenModule::burn(Origin::signed(alice), token_hash, 10), Error::<Test>::TokenPaused);
+		assert_err!(TokenModule::mint(Origin::signed(alice), token_hash, alice, 10), Error::<Test>::TokenPaused);
+		assert_eq!(TokenModule::token(token_hash).unwrap().total_supply, 1000);
+
+		assert_ok!(TokenModule::unpause_token(Origin::signed(charlie), token_hash));
+		assert!(!TokenModule::is_paused(token_hash));
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_hash, charlie, 10, None));
+	});
+}
+
+#[test]
+fn named_reserve_tests() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10u64;
+		let bob = 20u64;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 1000));
+		let token_hash = TokenModule::owned_token((alice, 0)).unwrap();
+
+		let order = ReserveId::Order(H256::from_low_u64_be(1));
+		let staking = ReserveId::Named(*b"staking ");
+
+		assert_ok!(TokenModule::reserve(alice, token_hash, order, 300));
+		assert_ok!(TokenModule::reserve(alice, token_hash, staking, 200));
+		assert_err!(TokenModule::reserve(alice, token_hash, staking, 501), Error::<Test>::BalanceNotEnough);
+		assert_eq!(TokenModule::named_reserve((alice, token_hash, order)), 300);
+		assert_eq!(TokenModule::named_reserve((alice, token_hash, staking)), 200);
+		assert_eq!(TokenModule::reserved_balance_of((alice, token_hash)), 500);
+		assert_eq!(TokenModule::free_balance_of((alice, token_hash)), 500);
+		assert_err!(
+			TokenModule::transfer(Origin::signed(alice), token_hash, bob, 501, None),
+			Error::<Test>::BalanceNotEnough
+		);
+
+		// each reserve only gives back what it holds
+		assert_err!(TokenModule::unreserve(alice, token_hash, staking, 201), Error::<Test>::ReservedBalanceNotEnough);
+		assert_ok!(TokenModule::unreserve(alice, token_hash, staking, 200));
+		assert_eq!(TokenModule::named_reserve((alice, token_hash, staking)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((alice, token_hash)), 300);
+		assert_eq!(TokenModule::free_balance_of((alice, token_hash)), 700);
+
+		// the reserve pays the free balance of another account
+		assert_err!(
+			TokenModule::repatriate_reserved(alice, token_hash, order, bob, 301),
+			Error::<Test>::ReservedBalanceNotEnough
+		);
+		assert_ok!(TokenModule::repatriate_reserved(alice, token_hash, order, bob, 100));
+		assert_eq!(TokenModule::named_reserve((alice, token_hash, order)), 200);
+		assert_eq!(TokenModule::balance_of((alice, token_hash)), 900);
+		assert_eq!(TokenModule::free_balance_of((alice, token_hash)), 700);
+		assert_eq!(TokenModule::balance_of((bob, token_hash)), 100);
+		assert_eq!(TokenModule::free_balance_of((bob, token_hash)), 100);
+
+		// the frozen balances before the named reserves go to the legacy reserve
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), token_hash, bob, 150, None));
+		<FreeBalanceOf<Test>>::insert((bob, token_hash), 100);
+		<FreezedBalanceOf<Test>>::insert((bob, token_hash), 150);
+		TokenModule::migrate_reserves();
+		assert_eq!(TokenModule::named_reserve((bob, token_hash, ReserveId::Named(LEGACY_RESERVE))), 150);
+		assert_eq!(TokenModule::reserved_balance_of((bob, token_hash)), 150);
+		assert_ok!(TokenModule::unreserve(bob, token_hash, ReserveId::Named(LEGACY_RESERVE), 150));
+		assert_eq!(TokenModule::free_balance_of((bob, token_hash)), 250);
+	});
+}
@@ -0,0 +1,43 @@
+//! Runtime API definition for the trade pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_std::prelude::*;
+use sp_runtime::DispatchError;
+
+pub use trade::{Ticker, OrderType, OrderQuote, OrderQuoteLevel};
+
+sp_api::decl_runtime_apis! {
+	pub trait TradeApi<AccountId, Hash, Price, Balance, BlockNumber> where
+		AccountId: Codec,
+		Hash: Codec,
+		Price: Codec,
+		Balance: Codec,
+		BlockNumber: Codec,
+	{
+		/// The 24h ticker of the trade pair
+		fn ticker(trade_pair: Hash) -> Option<Ticker<Hash, Price, Balance>>;
+
+		/// The 24h tickers of all the trade pairs
+		fn tickers() -> Vec<Ticker<Hash, Price, Balance>>;
+
+		/// The time weighted average price of the trade pair over the last `window` blocks
+		fn twap(trade_pair: Hash, window: BlockNumber) -> Result<Price, DispatchError>;
+
+		/// Simulate the order against the current market orders without creating orders or trades
+		fn quote_order(trade_pair: Hash, otype: OrderType, price: Price, sell_amount: Balance) -> Result<OrderQuote<Price, Balance>, DispatchError>;
+
+		/// The price and the quote volume which the crossed orders would clear at now, the opening price in the pre-open phase
+		fn indicative_price(trade_pair: Hash) -> Option<(Price, Balance)>;
+
+		/// Page through all the opened orders of the account, `count` orders starting from `start`
+		fn owned_opened_orders(account: AccountId, start: u64, count: u64) -> Vec<Hash>;
+
+		/// Page through all the opened orders of the account in the trade pair, `count` orders starting from `start`
+		fn owned_trade_pair_opened_orders(account: AccountId, trade_pair: Hash, start: u64, count: u64) -> Vec<Hash>;
+
+		/// Page through the trades of the order, `count` trades starting from `start`
+		fn order_trades(order: Hash, start: u64, count: u64) -> Vec<Hash>;
+	}
+}
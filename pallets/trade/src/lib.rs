@@ -4,31 +4,61 @@ use core::convert::{TryInto, TryFrom};
 
 use sp_core::U256;
 use sp_std::{prelude::*, if_std, fmt::Debug, result, ops::Not};
-use sp_runtime::{traits::{Bounded, Member, Zero, CheckedSub, Hash, AtLeast32Bit}};
+use sp_runtime::{traits::{Bounded, Member, Zero, One, CheckedSub, Hash, AtLeast32Bit}};
 
-use frame_support::{decl_module, decl_storage, decl_event, decl_error, StorageValue, StorageMap,
+use frame_support::{decl_module, decl_storage, decl_event, decl_error, StorageValue, StorageMap, IterableStorageMap,
                     ensure, Parameter, dispatch, traits::{Get, Randomness},
-                    weights::{Weight}};
+                    weights::{Weight}, storage::unhashed};
 
 use system::ensure_signed;
 use codec::{Encode, Decode};
+use token::{ReserveId, LEGACY_RESERVE, MultiCurrency, MultiReservableCurrency};
 use byteorder::{ByteOrder, LittleEndian};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
 
 #[cfg(test)]
 mod mock;
 
+#[cfg(test)]
+mod mock_ledger;
+
 #[cfg(test)]
 mod tests;
 
 mod types;
 
-pub trait Trait: token::Trait + system::Trait {
+pub trait Trait: balances::Trait + system::Trait {
     type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+    /// The ledger of the traded currencies
+    type Currency: MultiReservableCurrency<Self::AccountId, CurrencyId = Self::Hash, Balance = Self::Balance,
+        ReserveIdentifier = ReserveId<Self::Hash>>;
     type Price: Parameter + Default + Member + Bounded + AtLeast32Bit + Copy + From<u128> + Into<u128>;
-    type PriceFactor: Get<u128>;
+    /// Price decimals of the trade pairs created without specifying it
+    type DefaultPriceDecimals: Get<u8>;
+    type MaxPriceDecimals: Get<u8>;
+    /// Max number of blocks of the pre-open phase of a new trade pair
+    type MaxPreOpenBlocks: Get<u32>;
+    /// Max number of the pending orders of a trade pair, and of the market orders of each side a batch auction reads
+    type MaxPendingOrders: Get<u32>;
     type BlocksPerDay: Get<u32>;
     type OpenedOrdersArrayCap: Get<u8>;
     type ClosedOrdersArrayCap: Get<u8>;
+    type ArchiveRetentionBlocks: Get<u32>;
+    type ArchivePruneBatchSize: Get<u32>;
+    /// Max number of price observations kept per trade pair for the TWAP
+    type OracleObservationsCap: Get<u32>;
+    /// Blocks without a fill after which the oracle price is considered stale
+    type OracleMaxAge: Get<u32>;
+}
+
+/// Prices of the trade pairs for other pallets
+pub trait PriceOracle<Hash, Price, BlockNumber> {
+    /// The latest matched price of the trade pair
+    fn latest_price(tp_hash: Hash) -> Option<Price>;
+
+    /// The time weighted average price of the trade pair over the last `window` blocks
+    fn twap(tp_hash: Hash, window: BlockNumber) -> Result<Price, dispatch::DispatchError>;
 }
 
 #[derive(Encode, Decode, Clone, PartialEq, Eq)]
@@ -43,9 +73,131 @@ pub struct TradePair<T> where T: Trait {
     one_day_trade_volume: T::Balance, // sum of quote qty
     one_day_highest_price: Option<T::Price>,
     one_day_lowest_price: Option<T::Price>,
+
+    price_decimals: u8, // price = base / quote * 10^price_decimals
+
+    // top of book, the amounts are in quote
+    buy_one_price: Option<T::Price>,
+    buy_one_amount: T::Balance,
+    sell_one_price: Option<T::Price>,
+    sell_one_amount: T::Balance,
+
+    one_day_base_volume: T::Balance, // sum of base qty
+    one_day_trade_count: u64,
+    one_day_open_price: Option<T::Price>,
+    prev_close_price: Option<T::Price>, // latest matched price before the last day
+
+    // sum of latest_matched_price * blocks, updated on the first fill of a block
+    price_cumulative: U256,
+    price_cumulative_block: T::BlockNumber,
+
+    matching_mode: MatchingMode,
+
+    // the orders accumulate without matching up to this block, then the book uncrosses at its end
+    pre_open_until: Option<T::BlockNumber>,
+
+    allocation_policy: AllocationPolicy,
+}
+
+impl<T> TradePair<T> where T: Trait {
+    pub fn price_factor(&self) -> u128 {
+        10u128.pow(self.price_decimals as u32)
+    }
+
+    pub fn base(&self) -> T::Hash {
+        self.base
+    }
+
+    pub fn quote(&self) -> T::Hash {
+        self.quote
+    }
+
+    pub fn matching_mode(&self) -> MatchingMode {
+        self.matching_mode
+    }
+
+    pub fn is_pre_open(&self) -> bool {
+        self.pre_open_until.is_some()
+    }
+
+    pub fn allocation_policy(&self) -> AllocationPolicy {
+        self.allocation_policy
+    }
+}
+
+/// 24h statistics of a trade pair, the volumes are in quote unless noted
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Ticker<Hash, Price, Balance> {
+    pub trade_pair: Hash,
+    pub base: Hash,
+    pub quote: Hash,
+    pub price_decimals: u8,
+    /// decimals of the base and the quote tokens, to format the amounts
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+
+    pub last_price: Option<Price>,
+    pub open_price: Option<Price>,
+    pub prev_close_price: Option<Price>,
+    pub high_price: Option<Price>,
+    pub low_price: Option<Price>,
+    /// (last_price - open_price) / open_price in basis points
+    pub price_change_bps: Option<i64>,
+    /// volume weighted average price
+    pub weighted_avg_price: Option<Price>,
+
+    pub base_volume: Balance,
+    pub quote_volume: Balance,
+    pub trade_count: u64,
+
+    pub buy_one_price: Option<Price>,
+    pub sell_one_price: Option<Price>,
+}
+
+/// Fills of a simulated order at one price level
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OrderQuoteLevel<Price, Balance> {
+    pub price: Price,
+    pub base_amount: Balance,
+    pub quote_amount: Balance,
+    /// number of maker orders hit at the level
+    pub orders: u32,
+}
+
+/// The result of simulating an order against the current market orders
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct OrderQuote<Price, Balance> {
+    pub levels: Vec<OrderQuoteLevel<Price, Balance>>,
+    pub base_amount: Balance,
+    pub quote_amount: Balance,
+    /// base_amount / quote_amount in the trade pair's price decimals
+    pub average_price: Option<Price>,
+    /// amounts left on the market if the order is not filled, the unused sell amount is unreserved otherwise
+    pub remained_sell_amount: Balance,
+    pub remained_buy_amount: Balance,
+    pub filled: bool,
+}
+
+// the fills of the maker orders of a price level for a taker order
+struct LevelMatch<Balance> {
+    // (index of the maker order, base amount, quote amount) in time priority
+    fills: Vec<(usize, Balance, Balance)>,
+    // the level is shared pro rata instead of in time priority
+    pro_rata: bool,
+    // the remained amount of the taker order is too small to fill anything more at the level
+    dust: bool,
 }
 
+/// The layout version of the trade pairs, each layout change appends its fields to the previous layout:
+/// 1 the price decimals, 2 the top of book, 3 the ticker, 4 the price cumulative, 5 the matching mode,
+/// 6 the pre-open phase, 7 the allocation policy
+const STORAGE_VERSION: u32 = 7;
+
 #[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub enum OrderType {
     Buy,
     Sell,
@@ -62,6 +214,47 @@ impl Not for OrderType {
     }
 }
 
+/// A decimal price, parsed with integer arithmetic only
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum DecimalPrice {
+    /// ASCII decimal string, e.g. b"3.1415"
+    Text(Vec<u8>),
+    /// (Mantissa, Exponent), the price is mantissa * 10^exponent
+    Scientific(u128, i8),
+}
+
+/// How to handle the digits beyond the price precision
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    /// Reject the price if any digit is beyond the precision
+    Exact,
+    Down,
+    Up,
+    /// Round to the nearest, half up
+    HalfUp,
+}
+
+/// How the orders of a trade pair are matched
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchingMode {
+    /// Match each order against the market orders as soon as it is created
+    Continuous,
+    /// Collect the orders of the block and clear them together in `on_finalize` at a uniform price
+    BatchAuction,
+}
+
+/// How a price level shares a taker order which does not fill all of it
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationPolicy {
+    /// The earlier maker order fills first
+    Fifo,
+    /// Every maker order fills in proportion to its remained amount
+    ProRata,
+    /// The earliest maker order fills first, the others pro rata
+    FifoTopProRata,
+}
+
 #[derive(Encode, Decode, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "std", derive(Debug))]
 pub enum OrderStatus {
@@ -160,6 +353,16 @@ impl<T> Trade<T> where T: Trait {
     }
 }
 
+/// A finished order or a trade waiting to be pruned from the state
+#[derive(Encode, Decode, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ArchiveItem<T> where T: Trait {
+    /// OrderHash
+    Order(T::Hash),
+    /// (TradeHash, Index in TradePairOwnedTrades, Vec<(AccountId, Index in OwnedTrades, Index in OwnedTPTrades)>)
+    Trade(T::Hash, u64, Vec<(T::AccountId, u64, u64)>),
+}
+
 type OrderLinkedItem<T> = types::LinkedItem<<T as system::Trait>::Hash, <T as Trait>::Price, <T as balances::Trait>::Balance>;
 type OrderLinkedItemList<T> = types::LinkedList<T, LinkedItemList<T>, <T as system::Trait>::Hash, <T as Trait>::Price, <T as balances::Trait>::Balance>;
 
@@ -178,9 +381,9 @@ decl_error! {
         NoMatchingTradePair,
         /// Base equals to quote
         BaseEqualQuote,
-        /// Token owner not found
+        /// Base or quote token not found
         TokenOwnerNotFound,
-        /// Sender not equal to base or quote owner
+        /// Sender not equal to base or quote market admin
         SenderNotEqualToBaseOrQuoteOwner,
         /// Same trade pair with the given base and quote was already exist
         TradePairExisted,
@@ -200,6 +403,28 @@ decl_error! {
         CanOnlyCancelOwnOrder,
         /// can only cancel not finished order
         CanOnlyCancelNotFinishedOrder,
+        /// Decimal price is not a valid decimal number
+        DecimalPriceMalformed,
+        /// Decimal price has more digits than the price precision
+        DecimalPricePrecisionExceeded,
+        /// Decimal price overflow
+        DecimalPriceOverflow,
+        /// Price decimals exceed MaxPriceDecimals
+        PriceDecimalsTooLarge,
+        /// No trade in the trade pair yet
+        OraclePriceUnavailable,
+        /// No trade in the trade pair within OracleMaxAge blocks
+        OraclePriceStale,
+        /// Not enough price observations for the window
+        OracleInsufficientHistory,
+        /// The trade pair clears its orders in batch auctions
+        NotContinuousMatching,
+        /// The trade pair is in its pre-open phase
+        TradePairNotOpened,
+        /// Pre-open blocks exceed MaxPreOpenBlocks
+        PreOpenBlocksTooLarge,
+        /// The pending orders of the trade pair reach MaxPendingOrders
+        TooManyPendingOrders,
 	}
 }
 
@@ -216,6 +441,8 @@ decl_storage! {
 
 		/// OrderHash => Order
 		Orders get(fn order): map hasher(blake2_128_concat) T::Hash => Option<LimitOrder<T>>;
+		/// OrderHash => (Index in OwnedOrders, Index in TradePairOwnedOrders)
+		OrderIndexes get(fn order_indexes): map hasher(blake2_128_concat) T::Hash => Option<(u64, u64)>;
 		/// (AccoundId, Index) => OrderHash
 		OwnedOrders get(fn owned_order): map hasher(blake2_128_concat) (T::AccountId, u64) => Option<T::Hash>;
 		///	AccountId => Index
@@ -233,6 +460,11 @@ decl_storage! {
 		/// (TradePairHash, Price) => LinkedItem
 		LinkedItemList get(fn linked_item): map hasher(blake2_128_concat) (T::Hash, Option<T::Price>) => Option<OrderLinkedItem<T>>;
 
+		/// The trade pairs in the pre-open phase or in the batch auction mode, which are cleared in on_finalize
+		ClearingTradePairs get(fn clearing_trade_pairs): Vec<T::Hash>;
+		/// TradePairHash => Vec<OrderHash>, the orders waiting for the batch auction, not in the market yet
+		TPPendingOrders get(fn trade_pair_pending_orders): map hasher(blake2_128_concat) T::Hash => Vec<T::Hash>;
+
 		/// TradeHash => Trade
 		Trades get(fn trade): map hasher(blake2_128_concat) T::Hash => Option<Trade<T>>;
 
@@ -246,9 +478,20 @@ decl_storage! {
 		/// (AccountId, TradePairHash) => u64
 		OwnedTPTradesIndex get(fn owned_tp_trades_index): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => u64;
 
-        /// (AccountId, TradePairHash) => Vec<OrderHash>
+        /// (AccountId, TradePairHash) => Vec<OrderHash>, the most recent opened orders, capped by OpenedOrdersArrayCap
         OwnedTPOpenedOrders get(fn owned_tp_opened_orders): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => Option<Vec<T::Hash>>;
 
+		/// (AccountId, u64) => OrderHash, all the opened orders of the account
+		OwnedOpenedOrders get(fn owned_opened_order): map hasher(blake2_128_concat) (T::AccountId, u64) => Option<T::Hash>;
+		/// AccountId => u64, count of the opened orders of the account
+		OwnedOpenedOrdersIndex get(fn owned_opened_orders_index): map hasher(blake2_128_concat) T::AccountId => u64;
+		/// (AccountId, TradePairHash, u64) => OrderHash, all the opened orders of the account in the trade pair
+		OwnedTPAllOpenedOrders get(fn owned_tp_all_opened_order): map hasher(blake2_128_concat) (T::AccountId, T::Hash, u64) => Option<T::Hash>;
+		/// (AccountId, TradePairHash) => u64, count of the opened orders of the account in the trade pair
+		OwnedTPAllOpenedOrdersIndex get(fn owned_tp_all_opened_orders_index): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => u64;
+		/// OrderHash => (Index in OwnedOpenedOrders, Index in OwnedTPAllOpenedOrders)
+		OpenedOrderPositions get(fn opened_order_position): map hasher(blake2_128_concat) T::Hash => Option<(u64, u64)>;
+
         /// (AccountId, TradePairHash) => Vec<OrderHash>
         OwnedTPClosedOrders get(fn owned_tp_closed_orders): map hasher(blake2_128_concat) (T::AccountId, T::Hash) => Option<Vec<T::Hash>>;
 
@@ -262,9 +505,72 @@ decl_storage! {
 		/// store the trade pair's H/L price within last day
 		/// TradePairHash => (Vec<Highest_Price>, Vec<Lowest_Price>)
 		TPTradePriceBucket get(fn trade_pair_trade_price_bucket): map hasher(blake2_128_concat) T::Hash => (Vec<Option<T::Price>>, Vec<Option<T::Price>>);
+		/// (TradePairHash, BlockNumber) => (Sum_of_Base_Volume, Trade_Count, Open_Price, Close_Price)
+		TPTradeTickerDataBucket get(fn trade_pair_trade_ticker_data_bucket): map hasher(blake2_128_concat) (T::Hash, T::BlockNumber) => (T::Balance, u64, Option<T::Price>, Option<T::Price>);
+		/// a ring of the open price of the blocks with trades within last day, each linked to the next block with trades
+		/// (TradePairHash, BlockNumber % BlocksPerDay) => (BlockNumber, Open_Price, Next_BlockNumber)
+		TPTradeOpenPriceRing get(fn trade_pair_trade_open_price_ring): map hasher(blake2_128_concat) (T::Hash, T::BlockNumber) => Option<(T::BlockNumber, T::Price, Option<T::BlockNumber>)>;
+		/// TradePairHash => (Oldest_BlockNumber, Latest_BlockNumber) of the blocks with trades within last day
+		TPTradeOpenPriceBlocks get(fn trade_pair_trade_open_price_blocks): map hasher(blake2_128_concat) T::Hash => Option<(T::BlockNumber, T::BlockNumber)>;
+		/// TradePairHash => Vec<(BlockNumber, Price_Cumulative)>, capped by OracleObservationsCap
+		TPPriceObservations get(fn trade_pair_price_observations): map hasher(blake2_128_concat) T::Hash => Vec<(T::BlockNumber, U256)>;
+
+		/// (BlockNumber, u64) => ArchiveItem, the orders finished and the trades created in the block
+		ArchiveItems get(fn archive_item): map hasher(blake2_128_concat) (T::BlockNumber, u64) => Option<ArchiveItem<T>>;
+		/// BlockNumber => u64
+		ArchiveItemsIndex get(fn archive_items_index): map hasher(blake2_128_concat) T::BlockNumber => u64;
+		/// BlockNumber => Vec<Hash>, the pending subtree roots of the block's incremental merkle tree
+		ArchiveMerkleFrontier get(fn archive_merkle_frontier): map hasher(blake2_128_concat) T::BlockNumber => Vec<T::Hash>;
+		/// BlockNumber => (MerkleRoot, Leaf_Count), kept after the archived items of the block are pruned
+		ArchiveRoots get(fn archive_root): map hasher(blake2_128_concat) T::BlockNumber => Option<(T::Hash, u64)>;
+		/// (BlockNumber, Index in ArchiveItems) of the next archive item to prune
+		ArchivePruneCursor get(fn archive_prune_cursor): (T::BlockNumber, u64);
+
+		/// Whether the orders opened before the index of all the opened orders are listed in it
+		OpenedOrdersComplete: bool;
+		/// Whether the balances frozen for the open orders before the named reserves are moved to the orders
+		OrderReservesComplete: bool;
+		/// The layout version of the trade pairs, 0 on the chains started before it
+		StorageVersion get(fn storage_version): u32;
 
 		Nonce: u64;
 	}
+	add_extra_genesis {
+		/// (Base_Symbol, Quote_Symbol, Price_Decimals), created by the market admin of the base token
+		config(trade_pairs): Vec<(Vec<u8>, Vec<u8>, u8)>;
+		/// (Owner, Base_Symbol, Quote_Symbol, OrderType, Price, Sell_Amount), the resting orders of the trade pairs
+		config(orders): Vec<(T::AccountId, Vec<u8>, Vec<u8>, OrderType, T::Price, T::Balance)>;
+
+		build(|config: &GenesisConfig<T>| {
+			StorageVersion::put(STORAGE_VERSION);
+			OpenedOrdersComplete::put(true);
+
+			let token_of = |symbol: &Vec<u8>| T::Currency::currency_by_symbol(symbol.clone())
+				.expect("unknown token symbol in the genesis");
+
+			for (base, quote, price_decimals) in config.trade_pairs.iter() {
+				let (base, quote) = (token_of(base), token_of(quote));
+				let admin = T::Currency::market_admin(base).expect("every genesis token has an owner; qed");
+
+				<Module<T>>::do_create_trade_pair(admin, base, quote, *price_decimals, 0)
+					.expect("invalid trade pair in the genesis");
+			}
+
+			for (owner, base, quote, otype, price, sell_amount) in config.orders.iter() {
+				let (base, quote) = (token_of(base), token_of(quote));
+				let tp_hash = <Module<T>>::trade_pair_hash_by_base_quote((base, quote))
+					.expect("genesis order of an unknown trade pair");
+				let trades = <Module<T>>::trade_pair_owned_trades_index(tp_hash);
+
+				<Module<T>>::do_create_limit_order(owner.clone(), base, quote, *otype, *price, *sell_amount)
+					.expect("invalid order in the genesis");
+				assert!(
+					<Module<T>>::trade_pair_owned_trades_index(tp_hash) == trades,
+					"the genesis orders of a trade pair cross each other"
+				);
+			}
+		})
+	}
 }
 
 decl_event!(
@@ -272,6 +578,8 @@ decl_event!(
 	where
 		<T as system::Trait>::AccountId,
 		<T as system::Trait>::Hash,
+		<T as Trait>::Price,
+		<T as balances::Trait>::Balance,
 		TradePair = TradePair<T>,
 		LimitOrder = LimitOrder<T>,
 		Trade = Trade<T>,
@@ -286,6 +594,24 @@ decl_event!(
 
 		// (accountId, orderHash)
 		OrderCanceled(AccountId, Hash),
+
+		// (tradePairHash, buyOnePrice, buyOneAmount, sellOnePrice, sellOneAmount)
+		TopOfBookChanged(Hash, Option<Price>, Balance, Option<Price>, Balance),
+
+		// (accountId, tradePairHash, MatchingMode)
+		MatchingModeChanged(AccountId, Hash, MatchingMode),
+
+		// (tradePairHash, clearingPrice, quoteVolume)
+		BatchCleared(Hash, Price, Balance),
+
+		// (tradePairHash, openingPrice)
+		TradePairOpened(Hash, Option<Price>),
+
+		// (tradePairHash, error), the pending orders are cleared again in the next block
+		ClearingFailed(Hash, dispatch::DispatchError),
+
+		// (accountId, tradePairHash, AllocationPolicy)
+		AllocationPolicyChanged(AccountId, Hash, AllocationPolicy),
 	}
 );
 
@@ -298,26 +624,29 @@ impl<T: Trait> OrderOwnedTrades<T> {
 }
 
 impl<T: Trait> OwnedTrades<T> {
-    fn add_trade(account_id: T::AccountId, trade_hash: T::Hash) {
+    fn add_trade(account_id: T::AccountId, trade_hash: T::Hash) -> u64 {
         let index = OwnedTradesIndex::<T>::get(&account_id);
         Self::insert((account_id.clone(), index), trade_hash);
         OwnedTradesIndex::<T>::insert(account_id, index + 1);
+        index
     }
 }
 
 impl<T: Trait> TradePairOwnedTrades<T> {
-    fn add_trade(tp_hash: T::Hash, trade_hash: T::Hash) {
+    fn add_trade(tp_hash: T::Hash, trade_hash: T::Hash) -> u64 {
         let index = TradePairOwnedTradesIndex::<T>::get(&tp_hash);
         Self::insert((tp_hash.clone(), index), trade_hash);
         TradePairOwnedTradesIndex::<T>::insert(tp_hash, index + 1);
+        index
     }
 }
 
 impl<T: Trait> OwnedTPTrades<T> {
-    fn add_trade(account_id: T::AccountId, tp_hash: T::Hash, trade_hash: T::Hash) {
+    fn add_trade(account_id: T::AccountId, tp_hash: T::Hash, trade_hash: T::Hash) -> u64 {
         let index = OwnedTPTradesIndex::<T>::get((account_id.clone(), tp_hash));
         Self::insert((account_id.clone(), tp_hash, index), trade_hash);
         OwnedTPTradesIndex::<T>::insert((account_id.clone(), tp_hash), index + 1);
+        index
     }
 }
 
@@ -358,6 +687,60 @@ impl<T: Trait> OwnedTPOpenedOrders<T> {
     }
 }
 
+impl<T: Trait> OwnedOpenedOrders<T> {
+    fn add_order(account_id: T::AccountId, tp_hash: T::Hash, order_hash: T::Hash) {
+        if <OpenedOrderPositions<T>>::contains_key(order_hash) {
+            return;
+        }
+
+        let index = <OwnedOpenedOrdersIndex<T>>::get(&account_id);
+        Self::insert((account_id.clone(), index), order_hash);
+        <OwnedOpenedOrdersIndex<T>>::insert(account_id.clone(), index + 1);
+
+        let tp_index = <OwnedTPAllOpenedOrdersIndex<T>>::get((account_id.clone(), tp_hash));
+        <OwnedTPAllOpenedOrders<T>>::insert((account_id.clone(), tp_hash, tp_index), order_hash);
+        <OwnedTPAllOpenedOrdersIndex<T>>::insert((account_id, tp_hash), tp_index + 1);
+
+        <OpenedOrderPositions<T>>::insert(order_hash, (index, tp_index));
+    }
+
+    // swap the last order into the removed slot, so both indexes stay dense
+    fn remove_order(account_id: T::AccountId, tp_hash: T::Hash, order_hash: T::Hash) {
+        let (index, tp_index) = match <OpenedOrderPositions<T>>::take(order_hash) {
+            Some(position) => position,
+            None => return,
+        };
+
+        let last_index = <OwnedOpenedOrdersIndex<T>>::get(&account_id) - 1;
+        let last_hash = Self::take((account_id.clone(), last_index));
+        if last_index != index {
+            if let Some(last_hash) = last_hash {
+                Self::insert((account_id.clone(), index), last_hash);
+                <OpenedOrderPositions<T>>::mutate(last_hash, |position| {
+                    if let Some(position) = position {
+                        position.0 = index;
+                    }
+                });
+            }
+        }
+        <OwnedOpenedOrdersIndex<T>>::insert(account_id.clone(), last_index);
+
+        let last_tp_index = <OwnedTPAllOpenedOrdersIndex<T>>::get((account_id.clone(), tp_hash)) - 1;
+        let last_tp_hash = <OwnedTPAllOpenedOrders<T>>::take((account_id.clone(), tp_hash, last_tp_index));
+        if last_tp_index != tp_index {
+            if let Some(last_tp_hash) = last_tp_hash {
+                <OwnedTPAllOpenedOrders<T>>::insert((account_id.clone(), tp_hash, tp_index), last_tp_hash);
+                <OpenedOrderPositions<T>>::mutate(last_tp_hash, |position| {
+                    if let Some(position) = position {
+                        position.1 = tp_index;
+                    }
+                });
+            }
+        }
+        <OwnedTPAllOpenedOrdersIndex<T>>::insert((account_id, tp_hash), last_tp_index);
+    }
+}
+
 impl<T: Trait> OwnedTPClosedOrders<T> {
     fn add_order(account_id: T::AccountId, tp_hash: T::Hash, order_hash: T::Hash) {
 
@@ -380,6 +763,17 @@ impl<T: Trait> OwnedTPClosedOrders<T> {
             }
         }
     }
+
+    fn remove_order(account_id: T::AccountId, tp_hash: T::Hash, order_hash: T::Hash) {
+        if let Some(mut orders) = Self::get((account_id.clone(), tp_hash)) {
+            orders.retain(|&x| x != order_hash);
+            if orders.is_empty() {
+                <OwnedTPClosedOrders<T>>::remove((account_id, tp_hash));
+            } else {
+                <OwnedTPClosedOrders<T>>::insert((account_id, tp_hash), orders);
+            }
+        }
+    }
 }
 
 decl_module! {
@@ -392,7 +786,23 @@ decl_module! {
 		pub fn create_trade_pair(origin, base: T::Hash, quote: T::Hash) -> Result<(), dispatch::DispatchError> {
 			let sender = ensure_signed(origin)?;
 
-			Self::do_create_trade_pair(sender, base, quote)
+			Self::do_create_trade_pair(sender, base, quote, T::DefaultPriceDecimals::get(), 0)
+		}
+
+		#[weight = 1_000_000]
+		pub fn create_trade_pair_with_price_decimals(origin, base: T::Hash, quote: T::Hash, price_decimals: u8) -> Result<(), dispatch::DispatchError> {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_create_trade_pair(sender, base, quote, price_decimals, 0)
+		}
+
+		/// The orders of the first `pre_open_blocks` blocks accumulate without matching, and are matched together
+		/// at the equilibrium price at the end of them
+		#[weight = 1_000_000]
+		pub fn create_trade_pair_with_opening_auction(origin, base: T::Hash, quote: T::Hash, price_decimals: u8, pre_open_blocks: u32) -> Result<(), dispatch::DispatchError> {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_create_trade_pair(sender, base, quote, price_decimals, pre_open_blocks)
 		}
 
 		#[weight = 1_000_000]
@@ -402,11 +812,22 @@ decl_module! {
 			Self::do_create_limit_order(sender, base, quote, otype, price, sell_amount)
 		}
 
+		/// Deprecated, the price is decoded as a float, use `create_limit_order_with_decimal_price` instead
 		#[weight = 1_000_000]
 		pub fn create_limit_order_with_le_float(origin, base: T::Hash, quote: T::Hash, otype: OrderType, price: Vec<u8>, sell_amount: T::Balance) -> Result<(), dispatch::DispatchError> {
 			let sender = ensure_signed(origin)?;
 
-			let price = Self::price_as_vec_u8_to_x_by_100m(price)?;
+			let price_factor = Self::price_factor_of(base, quote)?;
+			let price = Self::price_as_vec_u8_to_x_by_100m(price, price_factor)?;
+			Self::do_create_limit_order(sender, base, quote, otype, price, sell_amount)
+		}
+
+		#[weight = 1_000_000]
+		pub fn create_limit_order_with_decimal_price(origin, base: T::Hash, quote: T::Hash, otype: OrderType, price: DecimalPrice, rounding: Rounding, sell_amount: T::Balance) -> Result<(), dispatch::DispatchError> {
+			let sender = ensure_signed(origin)?;
+
+			let price_factor = Self::price_factor_of(base, quote)?;
+			let price = Self::decimal_price_to_price(price, rounding, price_factor)?;
 			Self::do_create_limit_order(sender, base, quote, otype, price, sell_amount)
 		}
 
@@ -417,11 +838,34 @@ decl_module! {
 			Self::do_cancel_limit_order(sender, order_hash)
 		}
 
+		/// Only the market admin of the base or the quote token could change the matching mode
+		#[weight = 1_000_000]
+		pub fn set_matching_mode(origin, base: T::Hash, quote: T::Hash, mode: MatchingMode) -> Result<(), dispatch::DispatchError> {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_set_matching_mode(sender, base, quote, mode)
+		}
+
+		/// Only the market admin of the base or the quote token could change the allocation policy
+		#[weight = 1_000_000]
+		pub fn set_allocation_policy(origin, base: T::Hash, quote: T::Hash, policy: AllocationPolicy) -> Result<(), dispatch::DispatchError> {
+			let sender = ensure_signed(origin)?;
+
+			Self::do_set_allocation_policy(sender, base, quote, policy)
+		}
+
+		fn on_runtime_upgrade() -> Weight {
+			// the order reserves are migrated through the index of the opened orders
+			Self::migrate_trade_pairs() + Self::migrate_opened_orders() + Self::migrate_order_reserves()
+		}
+
 		fn on_initialize(block_number: T::BlockNumber) -> Weight {
+			let prune_weight = Self::prune_archive(block_number) + Self::clearing_weight(block_number);
+
 			let days: T::BlockNumber = <<T as system::Trait>::BlockNumber as From<_>>::from(T::BlocksPerDay::get());
 
 			if block_number <= days {
-				return 1000
+				return 1000 + prune_weight
 			}
 
 			for index in 0 .. TradePairsIndex::get() {
@@ -429,6 +873,32 @@ decl_module! {
 				let mut tp = TradePairs::<T>::get(tp_hash).unwrap();
 				let (amount, _, _) = TPTradeDataBucket::<T>::get((tp_hash, block_number - days));
 				tp.one_day_trade_volume = tp.one_day_trade_volume - amount;
+
+				let (base_amount, count, _, close_price) = TPTradeTickerDataBucket::<T>::get((tp_hash, block_number - days));
+				tp.one_day_base_volume = tp.one_day_base_volume - base_amount;
+				tp.one_day_trade_count = tp.one_day_trade_count - count;
+				if close_price.is_some() {
+					tp.prev_close_price = close_price;
+				}
+
+				// the oldest block with trades leaves the window, the next one opens the day
+				let expired = block_number - days;
+				if let Some((oldest, latest)) = TPTradeOpenPriceBlocks::<T>::get(tp_hash) {
+					if oldest == expired {
+						let next = TPTradeOpenPriceRing::<T>::take((tp_hash, expired % days)).and_then(|(_, _, next)| next);
+						match next {
+							Some(next) => {
+								TPTradeOpenPriceBlocks::<T>::insert(tp_hash, (next, latest));
+								tp.one_day_open_price = TPTradeOpenPriceRing::<T>::get((tp_hash, next % days)).map(|(_, price, _)| price);
+							},
+							None => {
+								TPTradeOpenPriceBlocks::<T>::remove(tp_hash);
+								tp.one_day_open_price = None;
+							},
+						}
+					}
+				}
+
 				TradePairs::<T>::insert(tp_hash, tp);
 
 				let mut bucket = TPTradePriceBucket::<T>::get(tp_hash);
@@ -441,12 +911,22 @@ decl_module! {
 				TPTradePriceBucket::<T>::insert(tp_hash, bucket);
 			}
 
-			500_000
+			500_000 + prune_weight
 		}
 
 		fn on_finalize(block_number: T::BlockNumber) {
+			let days: T::BlockNumber = <<T as system::Trait>::BlockNumber as From<_>>::from(T::BlocksPerDay::get());
+
+			for tp_hash in Self::clearing_trade_pairs() {
+				// nothing is written when the clearing fails, the orders wait for the next block
+				if let Err(e) = Self::clear_orders(tp_hash, block_number) {
+					Self::deposit_event(RawEvent::ClearingFailed(tp_hash, e));
+				}
+			}
+
 			for index in 0 .. TradePairsIndex::get() {
 				let tp_hash = TradePairsHashByIndex::<T>::get(index).unwrap();
+
 				let mut tp = TradePairs::<T>::get(tp_hash).unwrap();
 
 				let data_bucket = TPTradeDataBucket::<T>::get((tp_hash, block_number));
@@ -475,6 +955,27 @@ decl_module! {
 				}
 
 				tp.one_day_trade_volume = tp.one_day_trade_volume + data_bucket.0;
+
+				let (base_amount, count, open_price, _) = TPTradeTickerDataBucket::<T>::get((tp_hash, block_number));
+				tp.one_day_base_volume = tp.one_day_base_volume + base_amount;
+				tp.one_day_trade_count = tp.one_day_trade_count + count;
+				if let Some(open_price) = open_price {
+					TPTradeOpenPriceRing::<T>::insert((tp_hash, block_number % days), (block_number, open_price, None::<T::BlockNumber>));
+					match TPTradeOpenPriceBlocks::<T>::get(tp_hash) {
+						Some((oldest, latest)) => {
+							TPTradeOpenPriceRing::<T>::mutate((tp_hash, latest % days), |item| {
+								if let Some(item) = item {
+									item.2 = Some(block_number);
+								}
+							});
+							TPTradeOpenPriceBlocks::<T>::insert(tp_hash, (oldest, block_number));
+						},
+						None => {
+							TPTradeOpenPriceBlocks::<T>::insert(tp_hash, (block_number, block_number));
+							tp.one_day_open_price = Some(open_price);
+						},
+					}
+				}
 				
 				if h_price != T::Price::min_value() {
 					tp.one_day_highest_price = Some(h_price);
@@ -494,6 +995,16 @@ decl_module! {
 	}
 }
 
+impl<T: Trait> PriceOracle<T::Hash, T::Price, T::BlockNumber> for Module<T> {
+    fn latest_price(tp_hash: T::Hash) -> Option<T::Price> {
+        Self::trade_pair(tp_hash).and_then(|tp| tp.latest_matched_price)
+    }
+
+    fn twap(tp_hash: T::Hash, window: T::BlockNumber) -> Result<T::Price, dispatch::DispatchError> {
+        Self::do_twap(tp_hash, window)
+    }
+}
+
 impl<T: Trait> Module<T> {
     fn ensure_bounds(price: T::Price, sell_amount: T::Balance) -> dispatch::DispatchResult {
         ensure!(price > Zero::zero() && price <= T::Price::max_value(), Error::<T>::BoundsCheckFailed);
@@ -501,27 +1012,118 @@ impl<T: Trait> Module<T> {
         Ok(())
     }
 
-    fn price_as_vec_u8_to_x_by_100m(price: Vec<u8>) -> Result<T::Price, dispatch::DispatchError> {
+    fn price_as_vec_u8_to_x_by_100m(price: Vec<u8>, price_factor: u128) -> Result<T::Price, dispatch::DispatchError> {
 
         ensure!(price.len() >= 8, Error::<T>::PriceLengthCheckFailed);
 
         let price = LittleEndian::read_f64(price.as_slice());
 
-        let price_v2 = (T::PriceFactor::get() as f64 * price) as u128;
-        let price_v3 = price_v2 as f64 / T::PriceFactor::get() as f64;
+        let price_v2 = (price_factor as f64 * price) as u128;
+        let price_v3 = price_v2 as f64 / price_factor as f64;
 
         ensure!(price == price_v3, Error::<T>::PriceLengthCheckFailed);
 
         TryFrom::try_from(price_v2).map_err(|_| Error::<T>::NumberCastError.into())
     }
 
-    fn ensure_counterparty_amount_bounds(otype: OrderType, price:T::Price, amount: T::Balance)
+    fn parse_decimal_text(text: &[u8]) -> Result<(u128, i32), dispatch::DispatchError> {
+        ensure!(text.len() <= 80, Error::<T>::DecimalPriceMalformed);
+
+        // the zeros at the end of the fraction part do not change the value
+        let mut text = text;
+        if text.contains(&b'.') {
+            while text.last() == Some(&b'0') {
+                text = &text[..text.len() - 1];
+            }
+        }
+
+        let mut mantissa: u128 = 0;
+        let mut exponent: i32 = 0;
+        let mut digits = 0;
+        let mut dot = false;
+
+        for &c in text {
+            match c {
+                b'.' => {
+                    ensure!(!dot, Error::<T>::DecimalPriceMalformed);
+                    dot = true;
+                },
+                b'0'..=b'9' => {
+                    mantissa = mantissa.checked_mul(10)
+                        .and_then(|m| m.checked_add((c - b'0') as u128))
+                        .ok_or(Error::<T>::DecimalPriceOverflow)?;
+                    if dot {
+                        exponent -= 1;
+                    }
+                    digits += 1;
+                },
+                _ => return Err(Error::<T>::DecimalPriceMalformed.into()),
+            }
+        }
+
+        ensure!(digits > 0, Error::<T>::DecimalPriceMalformed);
+
+        Ok((mantissa, exponent))
+    }
+
+    fn pow10(exponent: u32) -> Option<U256> {
+        let mut result = U256::one();
+        for _ in 0..exponent {
+            result = result.checked_mul(U256::from(10))?;
+        }
+        Some(result)
+    }
+
+    fn decimal_price_to_price(price: DecimalPrice, rounding: Rounding, price_factor: u128) -> Result<T::Price, dispatch::DispatchError> {
+        let (mantissa, exponent) = match price {
+            DecimalPrice::Text(text) => Self::parse_decimal_text(&text)?,
+            DecimalPrice::Scientific(mantissa, exponent) => (mantissa, exponent as i32),
+        };
+
+        let value = U256::from(mantissa).checked_mul(U256::from(price_factor))
+            .ok_or(Error::<T>::DecimalPriceOverflow)?;
+
+        let price = if exponent >= 0 {
+            Self::pow10(exponent as u32)
+                .and_then(|p| value.checked_mul(p))
+                .ok_or(Error::<T>::DecimalPriceOverflow)?
+        } else {
+            let (quotient, remainder) = match Self::pow10((-exponent) as u32) {
+                Some(divisor) => (value / divisor, value % divisor),
+                // the divisor is beyond U256, so is the remainder
+                None => (U256::zero(), value),
+            };
+
+            if remainder.is_zero() {
+                quotient
+            } else {
+                match rounding {
+                    Rounding::Exact => return Err(Error::<T>::DecimalPricePrecisionExceeded.into()),
+                    Rounding::Down => quotient,
+                    Rounding::Up => quotient + U256::one(),
+                    Rounding::HalfUp => {
+                        // remainder >= divisor / 2, without computing the divisor which may be beyond U256
+                        let half_up = match Self::pow10((-exponent) as u32) {
+                            Some(divisor) => remainder >= divisor - remainder,
+                            None => false,
+                        };
+                        if half_up { quotient + U256::one() } else { quotient }
+                    },
+                }
+            }
+        };
+
+        let price: u128 = price.try_into().map_err(|_| Error::<T>::DecimalPriceOverflow)?;
+        Self::from_128(price)
+    }
+
+    fn ensure_counterparty_amount_bounds(otype: OrderType, price:T::Price, amount: T::Balance, price_factor: u128)
                                          -> result::Result<T::Balance, dispatch::DispatchError> {
 
         let price_u256 = U256::from(Self::into_128(price)?);
         let amount_u256 = U256::from(Self::into_128(amount)?);
         let max_balance_u256 = U256::from(Self::into_128(T::Balance::max_value())?);
-        let price_factor_u256 = U256::from(T::PriceFactor::get());
+        let price_factor_u256 = U256::from(price_factor);
 
         let amount_v2: U256;
         let counterparty_amount: U256;
@@ -546,6 +1148,32 @@ impl<T: Trait> Module<T> {
         Self::from_128(result)
     }
 
+    // the counterparty amount of the market order, rounded down
+    fn counterparty_amount_floor(otype: OrderType, price: T::Price, amount: T::Balance, price_factor: u128)
+                                 -> result::Result<T::Balance, dispatch::DispatchError> {
+
+        let price_u256 = U256::from(Self::into_128(price)?);
+        let amount_u256 = U256::from(Self::into_128(amount)?);
+        let max_balance_u256 = U256::from(Self::into_128(T::Balance::max_value())?);
+        let price_factor_u256 = U256::from(price_factor);
+
+        let counterparty_amount = match otype {
+            OrderType::Buy => amount_u256 * price_factor_u256 / price_u256,
+            OrderType::Sell => amount_u256 * price_u256 / price_factor_u256,
+        };
+
+        ensure!(counterparty_amount != 0.into() && counterparty_amount <= max_balance_u256, Error::<T>::BoundsCheckFailed);
+
+        Self::from_128(counterparty_amount.low_u128())
+    }
+
+    fn ensure_continuous_matching(tp: &TradePair<T>) -> dispatch::DispatchResult {
+        ensure!(!tp.is_pre_open(), Error::<T>::TradePairNotOpened);
+        ensure!(tp.matching_mode == MatchingMode::Continuous, Error::<T>::NotContinuousMatching);
+
+        Ok(())
+    }
+
     fn ensure_trade_pair(base: T::Hash, quote: T::Hash) -> result::Result<T::Hash, dispatch::DispatchError> {
         let bq = Self::trade_pair_hash_by_base_quote((base, quote));
         ensure!(bq.is_some(), Error::<T>::NoMatchingTradePair);
@@ -556,19 +1184,24 @@ impl<T: Trait> Module<T> {
         }
     }
 
-    fn do_create_trade_pair(sender: T::AccountId, base: T::Hash, quote: T::Hash) -> dispatch::DispatchResult {
-
-        ensure!(base != quote, Error::<T>::BaseEqualQuote);
+    fn price_factor_of(base: T::Hash, quote: T::Hash) -> result::Result<u128, dispatch::DispatchError> {
+        let tp_hash = Self::ensure_trade_pair(base, quote)?;
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
 
-        let base_owner = <token::Module<T>>::owner(base);
-        let quote_owner = <token::Module<T>>::owner(quote);
+        Ok(tp.price_factor())
+    }
 
-        ensure!(base_owner.is_some() && quote_owner.is_some(), Error::<T>::TokenOwnerNotFound);
+    fn do_create_trade_pair(sender: T::AccountId, base: T::Hash, quote: T::Hash, price_decimals: u8,
+                            pre_open_blocks: u32) -> dispatch::DispatchResult {
 
-        let base_owner = base_owner.unwrap();
-        let quote_owner = quote_owner.unwrap();
+        ensure!(base != quote, Error::<T>::BaseEqualQuote);
+        ensure!(price_decimals <= T::MaxPriceDecimals::get(), Error::<T>::PriceDecimalsTooLarge);
+        ensure!(pre_open_blocks <= T::MaxPreOpenBlocks::get(), Error::<T>::PreOpenBlocksTooLarge);
 
-        ensure!(sender == base_owner || sender == quote_owner, Error::<T>::SenderNotEqualToBaseOrQuoteOwner);
+        // a token without an owner can still be listed by the market admin of the other token
+        ensure!(T::Currency::exists(base) && T::Currency::exists(quote), Error::<T>::TokenOwnerNotFound);
+        let sender_owned = |hash| T::Currency::market_admin(hash).as_ref() == Some(&sender);
+        ensure!(sender_owned(base) || sender_owned(quote), Error::<T>::SenderNotEqualToBaseOrQuoteOwner);
 
         let bq = Self::trade_pair_hash_by_base_quote((base, quote));
         let qb = Self::trade_pair_hash_by_base_quote((quote, base));
@@ -581,12 +1214,32 @@ impl<T: Trait> Module<T> {
         let hash = (random_seed, <system::Module<T>>::block_number(), sender.clone(), base, quote, nonce)
             .using_encoded(<T as system::Trait>::Hashing::hash);
 
+        // the block the pair is created at is the first block of the pre-open phase
+        let pre_open_until = match pre_open_blocks {
+            0 => None,
+            blocks => Some(<system::Module<T>>::block_number() + <<T as system::Trait>::BlockNumber as From<_>>::from(blocks - 1)),
+        };
+
         let tp = TradePair {
             hash, base, quote,
             latest_matched_price: None,
             one_day_trade_volume: Default::default(),
             one_day_highest_price: None,
             one_day_lowest_price: None,
+            price_decimals,
+            buy_one_price: None,
+            buy_one_amount: Default::default(),
+            sell_one_price: None,
+            sell_one_amount: Default::default(),
+            one_day_base_volume: Default::default(),
+            one_day_trade_count: 0,
+            one_day_open_price: None,
+            prev_close_price: None,
+            price_cumulative: U256::zero(),
+            price_cumulative_block: Zero::zero(),
+            matching_mode: MatchingMode::Continuous,
+            pre_open_until,
+            allocation_policy: AllocationPolicy::Fifo,
         };
 
         Nonce::mutate(|n| *n += 1);
@@ -596,72 +1249,254 @@ impl<T: Trait> Module<T> {
         let index = Self::trade_pair_index();
         TradePairsHashByIndex::<T>::insert(index, hash);
         TradePairsIndex::mutate(|n| *n += 1);
+        Self::update_clearing(&tp);
 
         Self::deposit_event(RawEvent::TradePairCreated(sender, hash, tp));
 
         Ok(())
     }
 
-    fn do_create_limit_order(sender: T::AccountId, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price,
-                             sell_amount: T::Balance) -> dispatch::DispatchResult {
+    fn do_set_matching_mode(sender: T::AccountId, base: T::Hash, quote: T::Hash, mode: MatchingMode) -> dispatch::DispatchResult {
+        let tp_hash = Self::ensure_trade_pair(base, quote)?;
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        Self::ensure_trade_pair_owner(&sender, &tp)?;
 
-        Self::ensure_bounds(price, sell_amount)?;
-        let buy_amount = Self::ensure_counterparty_amount_bounds(otype, price, sell_amount)?;
+        // the orders collected so far are cleared before the continuous matching resumes
+        if tp.matching_mode == MatchingMode::BatchAuction && !tp.is_pre_open() {
+            Self::clear_batch(tp_hash)?;
+        }
+
+        let mut tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        tp.matching_mode = mode;
+        Self::update_clearing(&tp);
+        <TradePairs<T>>::insert(tp_hash, tp);
+
+        Self::deposit_event(RawEvent::MatchingModeChanged(sender, tp_hash, mode));
+
+        Ok(())
+    }
 
+    fn do_set_allocation_policy(sender: T::AccountId, base: T::Hash, quote: T::Hash, policy: AllocationPolicy) -> dispatch::DispatchResult {
         let tp_hash = Self::ensure_trade_pair(base, quote)?;
+        let mut tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        Self::ensure_trade_pair_owner(&sender, &tp)?;
 
-        let op_token_hash;
-        match otype {
-            OrderType::Buy => op_token_hash = base,
-            OrderType::Sell => op_token_hash = quote,
+        tp.allocation_policy = policy;
+        <TradePairs<T>>::insert(tp_hash, tp);
+
+        Self::deposit_event(RawEvent::AllocationPolicyChanged(sender, tp_hash, policy));
+
+        Ok(())
+    }
+
+    fn ensure_trade_pair_owner(sender: &T::AccountId, tp: &TradePair<T>) -> dispatch::DispatchResult {
+        let sender_owned = |hash| T::Currency::market_admin(hash).as_ref() == Some(sender);
+        ensure!(sender_owned(tp.base) || sender_owned(tp.quote), Error::<T>::SenderNotEqualToBaseOrQuoteOwner);
+
+        Ok(())
+    }
+
+    /// The owner of the order could move the base and the quote, and could be paid the token the order buys,
+    /// the matching cancels the resting orders which are not
+    pub fn is_fillable(tp: &TradePair<T>, order: &LimitOrder<T>) -> bool {
+        let buy_token_hash = match order.otype {
+            OrderType::Buy => tp.quote,
+            OrderType::Sell => tp.base,
         };
 
+        T::Currency::ensure_transferable(tp.base, &order.owner).is_ok() && T::Currency::ensure_transferable(tp.quote, &order.owner).is_ok()
+            && T::Currency::ensure_can_deposit(buy_token_hash, &order.owner).is_ok()
+    }
+
+    fn do_create_limit_order(sender: T::AccountId, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price,
+                             sell_amount: T::Balance) -> dispatch::DispatchResult {
+
+        let tp_hash = Self::ensure_trade_pair(base, quote)?;
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+
+        Self::ensure_bounds(price, sell_amount)?;
+        let buy_amount = Self::ensure_counterparty_amount_bounds(otype, price, sell_amount, tp.price_factor())?;
+
         let mut order = LimitOrder::new(base, quote, sender.clone(), price, sell_amount, buy_amount, otype);
         let hash  = order.hash;
 
-        <token::Module<T>>::ensure_free_balance(sender.clone(), op_token_hash, sell_amount)?;
-        <token::Module<T>>::do_freeze(sender.clone(), op_token_hash, sell_amount)?;
-        Orders::insert(hash, order.clone());
-        Nonce::mutate(|n| *n += 1);
-        Self::deposit_event(RawEvent::OrderCreated(sender.clone(), base, quote, hash, order.clone()));
-        <OwnedTPOpenedOrders<T>>::add_order(sender.clone(), tp_hash, order.hash);
+        if Self::ensure_continuous_matching(&tp).is_err() {
+            ensure!(Self::trade_pair_pending_orders(tp_hash).len() < T::MaxPendingOrders::get() as usize, Error::<T>::TooManyPendingOrders);
+        }
 
-        let owned_index = Self::owned_orders_index(sender.clone());
-        OwnedOrders::<T>::insert((sender.clone(), owned_index), hash);
-        OwnedOrdersIndex::<T>::insert(sender.clone(), owned_index + 1);
+        Self::do_place_order(tp_hash, &order)?;
 
-        let tp_owned_index = Self::trade_pair_owned_order_index(tp_hash);
-        TradePairOwnedOrders::<T>::insert((tp_hash, tp_owned_index), hash);
-        TradePairOwnedOrdersIndex::<T>::insert(tp_hash, tp_owned_index + 1);
+        // in a batch auction or in the pre-open phase, the order is cleared with the others in on_finalize
+        if Self::ensure_continuous_matching(&tp).is_err() {
+            <TPPendingOrders<T>>::mutate(tp_hash, |orders| orders.push(hash));
+            return Ok(());
+        }
 
         // order match
-        let filled = Self::order_match(tp_hash, &mut order)?;
+        let (filled, _) = Self::order_match(tp_hash, &mut order, false)?;
 
         // add order to the market order list
         if !filled {
             <OrderLinkedItemList<T>>::append(tp_hash, price, hash, order.remained_sell_amount, order.remained_buy_amount, otype);
         } else {
             <OwnedTPOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order.hash);
+            <OwnedOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order.hash);
             <OwnedTPClosedOrders<T>>::add_order(sender.clone(), tp_hash, order.hash);
         }
 
+        Self::update_top_of_book(tp_hash)?;
+
         Ok(())
     }
 
-    fn order_match(tp_hash: T::Hash, order: &mut LimitOrder<T>) -> result::Result<bool, dispatch::DispatchError> {
-        let mut head = <OrderLinkedItemList<T>>::read_head(tp_hash);
+    /// Sell all of `sell_amount` to the market orders whose price is not worse than `price`, the part which
+    /// could not be matched is unreserved instead of being added to the market (immediate or cancel).
+    /// Returns the sold and the bought amounts of the fills.
+    pub fn do_create_market_order(sender: T::AccountId, base: T::Hash, quote: T::Hash, otype: OrderType, price: T::Price,
+                                  sell_amount: T::Balance) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
 
-        let end_item_price;
-        let otype = order.otype;
-        let oprice = order.price;
+        let tp_hash = Self::ensure_trade_pair(base, quote)?;
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        Self::ensure_continuous_matching(&tp)?;
 
-        if otype == OrderType::Buy {
-            end_item_price = Some(T::Price::min_value());
-        } else {
-            end_item_price = Some(T::Price::max_value());
-        }
+        Self::ensure_bounds(price, sell_amount)?;
+        let buy_amount = Self::counterparty_amount_floor(otype, price, sell_amount, tp.price_factor())?;
 
-        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        let mut order = LimitOrder::new(base, quote, sender.clone(), price, sell_amount, buy_amount, otype);
+
+        Self::do_place_order(tp_hash, &order)?;
+
+        let (filled, bought) = Self::order_match(tp_hash, &mut order, true)?;
+
+        let sold = sell_amount - order.remained_sell_amount;
+
+        if !filled {
+            let give_token_hash = match otype {
+                OrderType::Buy => base,
+                OrderType::Sell => quote,
+            };
+            T::Currency::unreserve(give_token_hash, ReserveId::Order(order.hash), &sender, order.remained_sell_amount)?;
+
+            order.status = OrderStatus::Canceled;
+            <Orders<T>>::insert(order.hash, order.clone());
+            Self::archive_order(&order);
+
+            <OwnedTPOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order.hash);
+            <OwnedOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order.hash);
+            <OwnedTPClosedOrders<T>>::add_order(sender.clone(), tp_hash, order.hash);
+
+            Self::deposit_event(RawEvent::OrderCanceled(sender.clone(), order.hash));
+        }
+
+        Self::update_top_of_book(tp_hash)?;
+
+        Ok((sold, bought))
+    }
+
+    // reserve the sell amount of the new order under its hash, save it and its indexes
+    fn do_place_order(tp_hash: T::Hash, order: &LimitOrder<T>) -> dispatch::DispatchResult {
+        let sender = order.owner.clone();
+        let hash = order.hash;
+
+        T::Currency::ensure_transferable(order.base, &sender)?;
+        T::Currency::ensure_transferable(order.quote, &sender)?;
+
+        let op_token_hash;
+        let buy_token_hash;
+        match order.otype {
+            OrderType::Buy => {
+                op_token_hash = order.base;
+                buy_token_hash = order.quote;
+            },
+            OrderType::Sell => {
+                op_token_hash = order.quote;
+                buy_token_hash = order.base;
+            },
+        };
+
+        // the fills are paid out of the reserves, which pallet_balances only does to an existing account
+        T::Currency::ensure_can_deposit(buy_token_hash, &sender)?;
+
+        T::Currency::ensure_can_withdraw(op_token_hash, &sender, order.sell_amount)?;
+        T::Currency::reserve(op_token_hash, ReserveId::Order(hash), &sender, order.sell_amount)?;
+        Orders::insert(hash, order.clone());
+        Nonce::mutate(|n| *n += 1);
+        Self::deposit_event(RawEvent::OrderCreated(sender.clone(), order.base, order.quote, hash, order.clone()));
+        <OwnedTPOpenedOrders<T>>::add_order(sender.clone(), tp_hash, order.hash);
+        <OwnedOpenedOrders<T>>::add_order(sender.clone(), tp_hash, order.hash);
+
+        let owned_index = Self::owned_orders_index(sender.clone());
+        OwnedOrders::<T>::insert((sender.clone(), owned_index), hash);
+        OwnedOrdersIndex::<T>::insert(sender.clone(), owned_index + 1);
+
+        let tp_owned_index = Self::trade_pair_owned_order_index(tp_hash);
+        TradePairOwnedOrders::<T>::insert((tp_hash, tp_owned_index), hash);
+        TradePairOwnedOrdersIndex::<T>::insert(tp_hash, tp_owned_index + 1);
+
+        OrderIndexes::<T>::insert(hash, (owned_index, tp_owned_index));
+
+        Ok(())
+    }
+
+    fn top_of_book(tp_hash: T::Hash) -> (Option<T::Price>, T::Balance, Option<T::Price>, T::Balance) {
+        let head = <OrderLinkedItemList<T>>::read_head(tp_hash);
+
+        let mut buy_one = (None, Default::default());
+        if let Some(price) = head.prev {
+            if price != T::Price::min_value() {
+                let item = <OrderLinkedItemList<T>>::read(tp_hash, Some(price));
+                buy_one = (Some(price), item.buy_amount);
+            }
+        }
+
+        let mut sell_one = (None, Default::default());
+        if let Some(price) = head.next {
+            if price != T::Price::max_value() {
+                let item = <OrderLinkedItemList<T>>::read(tp_hash, Some(price));
+                sell_one = (Some(price), item.sell_amount);
+            }
+        }
+
+        (buy_one.0, buy_one.1, sell_one.0, sell_one.1)
+    }
+
+    fn update_top_of_book(tp_hash: T::Hash) -> dispatch::DispatchResult {
+        let mut tp = <TradePairs<T>>::get(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+
+        let (buy_one_price, buy_one_amount, sell_one_price, sell_one_amount) = Self::top_of_book(tp_hash);
+
+        if tp.buy_one_price == buy_one_price && tp.buy_one_amount == buy_one_amount
+            && tp.sell_one_price == sell_one_price && tp.sell_one_amount == sell_one_amount {
+            return Ok(());
+        }
+
+        tp.buy_one_price = buy_one_price;
+        tp.buy_one_amount = buy_one_amount;
+        tp.sell_one_price = sell_one_price;
+        tp.sell_one_amount = sell_one_amount;
+        <TradePairs<T>>::insert(tp_hash, tp);
+
+        Self::deposit_event(RawEvent::TopOfBookChanged(tp_hash, buy_one_price, buy_one_amount, sell_one_price, sell_one_amount));
+
+        Ok(())
+    }
+
+    // a market order sells all of its sell amount instead of stopping once its buy amount is reached.
+    // Returns whether the order is filled and the amount it bought
+    fn order_match(tp_hash: T::Hash, order: &mut LimitOrder<T>, market: bool) -> result::Result<(bool, T::Balance), dispatch::DispatchError> {
+        let mut head = <OrderLinkedItemList<T>>::read_head(tp_hash);
+
+        let end_item_price;
+        let otype = order.otype;
+        let oprice = order.price;
+
+        if otype == OrderType::Buy {
+            end_item_price = Some(T::Price::min_value());
+        } else {
+            end_item_price = Some(T::Price::max_value());
+        }
+
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
         let give: T::Hash;
         let have: T::Hash;
 
@@ -676,6 +1511,7 @@ impl<T: Trait> Module<T> {
             },
         };
 
+        let mut bought: T::Balance = Zero::zero();
         loop {
             if order.status == OrderStatus::Filled {
                 break;
@@ -694,24 +1530,24 @@ impl<T: Trait> Module<T> {
             }
 
             let item = <LinkedItemList<T>>::get((tp_hash, Some(item_price))).ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
+            let mut makers = Vec::with_capacity(item.orders.len());
             for o in item.orders.iter() {
+                let o = Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
 
-                let mut o = Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+                // the balances of the frozen accounts can't move, and the accounts of the native currency which
+                // don't exist any more can't be paid, their orders are canceled once they are reached
+                if Self::is_fillable(&tp, &o) {
+                    makers.push(o);
+                } else {
+                    Self::cancel_order(tp_hash, o)?;
+                }
+            }
 
-                let (base_qty, quote_qty) = Self::calculate_ex_amount(&o, &order)?;
+            let level = Self::match_level(&tp, &makers, &order, market)?;
 
-                let give_qty: T::Balance;
-                let have_qty: T::Balance;
-                match otype {
-                    OrderType::Buy => {
-                        give_qty = base_qty;
-                        have_qty = quote_qty;
-                    },
-                    OrderType::Sell => {
-                        give_qty = quote_qty;
-                        have_qty = base_qty;
-                    },
-                };
+            for (index, base_qty, quote_qty) in level.fills {
+                let mut o = makers[index].clone();
+                let (give_qty, have_qty) = Self::taker_fill_amounts(otype, base_qty, quote_qty);
 
                 if order.remained_sell_amount == order.sell_amount {
                     order.status = OrderStatus::PartialFilled;
@@ -721,26 +1557,26 @@ impl<T: Trait> Module<T> {
                     o.status = OrderStatus::PartialFilled;
                 }
 
-                <token::Module<T>>::do_unfreeze(order.owner.clone(), give, give_qty)?;
-                <token::Module<T>>::do_unfreeze(o.owner.clone(), have, have_qty)?;
+                T::Currency::repatriate_reserved(give, ReserveId::Order(order.hash), &order.owner, &o.owner, give_qty)?;
+                T::Currency::repatriate_reserved(have, ReserveId::Order(o.hash), &o.owner, &order.owner, have_qty)?;
 
-                <token::Module<T>>::do_transfer(order.owner.clone(), give, o.owner.clone(), give_qty, None)?;
-                <token::Module<T>>::do_transfer(o.owner.clone(), have, order.owner.clone(), have_qty, None)?;
-
-                order.remained_sell_amount = order.remained_sell_amount.checked_sub(&give_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
-                order.remained_buy_amount = order.remained_buy_amount.checked_sub(&have_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+                Self::fill_taker(order, give_qty, have_qty, market)?;
+                bought = bought + have_qty;
 
                 o.remained_sell_amount = o.remained_sell_amount.checked_sub(&have_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
                 o.remained_buy_amount = o.remained_buy_amount.checked_sub(&give_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
 
-                if order.remained_buy_amount == Zero::zero() {
+                if Self::order_completed(&order, market) {
                     order.status = OrderStatus::Filled;
+                    // a market order may buy more than its buy amount
+                    order.remained_buy_amount = Zero::zero();
                     if order.remained_sell_amount != Zero::zero() {
-                        <token::Module<T>>::do_unfreeze(order.owner.clone(), give, order.remained_sell_amount)?;
+                        T::Currency::unreserve(give, ReserveId::Order(order.hash), &order.owner, order.remained_sell_amount)?;
                         order.remained_sell_amount = Zero::zero();
                     }
 
                     <OwnedTPOpenedOrders<T>>::remove_order(order.owner.clone(), tp_hash, order.hash);
+                    <OwnedOpenedOrders<T>>::remove_order(order.owner.clone(), tp_hash, order.hash);
                     <OwnedTPClosedOrders<T>>::add_order(order.owner.clone(), tp_hash, order.hash);
 
                     ensure!(order.is_finished(), Error::<T>::OrderMatchOrderIsNotFinished);
@@ -749,11 +1585,12 @@ impl<T: Trait> Module<T> {
                 if o.remained_buy_amount == Zero::zero() {
                     o.status = OrderStatus::Filled;
                     if o.remained_sell_amount != Zero::zero() {
-                        <token::Module<T>>::do_unfreeze(o.owner.clone(), have, o.remained_sell_amount)?;
+                        T::Currency::unreserve(have, ReserveId::Order(o.hash), &o.owner, o.remained_sell_amount)?;
                         o.remained_sell_amount = Zero::zero();
                     }
 
                     <OwnedTPOpenedOrders<T>>::remove_order(o.owner.clone(), tp_hash, o.hash);
+                    <OwnedOpenedOrders<T>>::remove_order(o.owner.clone(), tp_hash, o.hash);
                     <OwnedTPClosedOrders<T>>::add_order(o.owner.clone(), tp_hash, o.hash);
 
                     ensure!(o.is_finished(), Error::<T>::OrderMatchOrderIsNotFinished);
@@ -762,46 +1599,521 @@ impl<T: Trait> Module<T> {
                 Orders::insert(order.hash.clone(), order.clone());
                 Orders::insert(o.hash.clone(), o.clone());
 
+                if order.is_finished() {
+                    Self::archive_order(&order);
+                }
+                if o.is_finished() {
+                    Self::archive_order(&o);
+                }
+
                 // save the trade pair market data
-                Self::set_tp_market_data(tp_hash, o.price, quote_qty)?;
+                Self::set_tp_market_data(tp_hash, o.price, base_qty, quote_qty)?;
 
                 // update maker order's amount in market
                 <OrderLinkedItemList<T>>::update_amount(tp_hash, o.price, have_qty, give_qty);
 
+                // a filled maker order could be behind the ones not filled when the level is shared
+                if level.pro_rata && o.is_finished() {
+                    <OrderLinkedItemList<T>>::remove_order(tp_hash, o.price, o.hash, Zero::zero(), Zero::zero())?;
+                }
+
                 // remove the matched order
                 <OrderLinkedItemList<T>>::remove_all(tp_hash, !otype);
 
                 // save the trade data
                 let trade = Trade::new(tp.base, tp.quote, &o, &order, base_qty, quote_qty);
-                Trades::insert(trade.hash, trade.clone());
+                Self::save_trade(tp_hash, trade, &o, &order);
 
-                Self::deposit_event(RawEvent::TradeCreated(order.owner.clone(), tp.base, tp.quote, trade.hash, trade.clone()));
+                if order.status == OrderStatus::Filled {
+                    break
+                }
+            }
 
-                // save trade reference data to store
-                <OrderOwnedTrades<T>>::add_trade(order.hash, trade.hash);
-                <OrderOwnedTrades<T>>::add_trade(o.hash, trade.hash);
+            if level.dust {
+                break;
+            }
 
-                <OwnedTrades<T>>::add_trade(order.owner.clone(), trade.hash);
-                <OwnedTrades<T>>::add_trade(o.owner.clone(), trade.hash);
+            head = <OrderLinkedItemList<T>>::read_head(tp_hash);
+        }
 
-                <OwnedTPTrades<T>>::add_trade(order.owner.clone(), tp_hash, trade.hash);
-                <OwnedTPTrades<T>>::add_trade(o.owner.clone(), tp_hash, trade.hash);
+        Ok((order.status == OrderStatus::Filled, bought))
+    }
 
-                <TradePairOwnedTrades<T>>::add_trade(tp_hash, trade.hash);
+    // save the trade and its references, the taker's owner is notified
+    fn save_trade(tp_hash: T::Hash, trade: Trade<T>, maker_order: &LimitOrder<T>, taker_order: &LimitOrder<T>) {
+        Trades::insert(trade.hash, trade.clone());
 
-                if order.status == OrderStatus::Filled {
-                    break
+        Self::deposit_event(RawEvent::TradeCreated(taker_order.owner.clone(), trade.base, trade.quote, trade.hash, trade.clone()));
+
+        <OrderOwnedTrades<T>>::add_trade(taker_order.hash, trade.hash);
+        <OrderOwnedTrades<T>>::add_trade(maker_order.hash, trade.hash);
+
+        let taker_index = <OwnedTrades<T>>::add_trade(taker_order.owner.clone(), trade.hash);
+        let maker_index = <OwnedTrades<T>>::add_trade(maker_order.owner.clone(), trade.hash);
+
+        let taker_tp_index = <OwnedTPTrades<T>>::add_trade(taker_order.owner.clone(), tp_hash, trade.hash);
+        let maker_tp_index = <OwnedTPTrades<T>>::add_trade(maker_order.owner.clone(), tp_hash, trade.hash);
+
+        let tp_index = <TradePairOwnedTrades<T>>::add_trade(tp_hash, trade.hash);
+
+        let leaf = trade.using_encoded(<T as system::Trait>::Hashing::hash);
+        Self::archive(ArchiveItem::Trade(trade.hash, tp_index, sp_std::vec![
+            (taker_order.owner.clone(), taker_index, taker_tp_index),
+            (maker_order.owner.clone(), maker_index, maker_tp_index),
+        ]), leaf);
+    }
+
+    // keep the trade pair in ClearingTradePairs while it is pre-open or in the batch auction mode
+    fn update_clearing(tp: &TradePair<T>) {
+        let clears = tp.is_pre_open() || tp.matching_mode == MatchingMode::BatchAuction;
+        <ClearingTradePairs<T>>::mutate(|pairs| {
+            match (clears, pairs.iter().position(|&tp_hash| tp_hash == tp.hash)) {
+                (true, None) => pairs.push(tp.hash),
+                (false, Some(index)) => {
+                    pairs.remove(index);
+                },
+                _ => {},
+            }
+        });
+    }
+
+    // the weight of clearing the orders in on_finalize of the block, a batch auction reads the pending orders
+    // and at most MaxPendingOrders market orders of each side
+    fn clearing_weight(block_number: T::BlockNumber) -> Weight {
+        let mut weight = 0;
+        for tp_hash in Self::clearing_trade_pairs() {
+            let tp = match Self::trade_pair(tp_hash) {
+                Some(tp) => tp,
+                None => continue,
+            };
+
+            let clears = match tp.pre_open_until {
+                Some(pre_open_until) => block_number >= pre_open_until,
+                None => tp.matching_mode == MatchingMode::BatchAuction,
+            };
+            if clears {
+                let orders = Self::trade_pair_pending_orders(tp.hash).len() as Weight + 2 * T::MaxPendingOrders::get() as Weight;
+                weight += orders * 1_000_000;
+            }
+        }
+
+        weight
+    }
+
+    // open the trade pair at the end of its pre-open phase, or clear the batch auction of the block
+    fn clear_orders(tp_hash: T::Hash, block_number: T::BlockNumber) -> dispatch::DispatchResult {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+
+        match tp.pre_open_until {
+            Some(pre_open_until) if block_number < pre_open_until => Ok(()),
+            Some(_) => Self::open_trade_pair(tp_hash),
+            None if tp.matching_mode == MatchingMode::BatchAuction => Self::clear_batch(tp_hash).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    // uncross the orders collected in the pre-open phase at the equilibrium price, then start the matching.
+    // the pair stays pre-open when the clearing fails, clear_batch writes nothing then
+    fn open_trade_pair(tp_hash: T::Hash) -> dispatch::DispatchResult {
+        let price = Self::clear_batch(tp_hash)?;
+
+        let mut tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        tp.pre_open_until = None;
+        Self::update_clearing(&tp);
+        <TradePairs<T>>::insert(tp_hash, tp);
+
+        Self::deposit_event(RawEvent::TradePairOpened(tp_hash, price));
+
+        Ok(())
+    }
+
+    /// The price which the pending and the market orders would clear at now with the matched quote volume,
+    /// it is the opening price of a trade pair in its pre-open phase
+    pub fn indicative_price(tp_hash: T::Hash) -> Option<(T::Price, T::Balance)> {
+        Self::trade_pair(tp_hash)?;
+
+        match Self::batch_auction(tp_hash) {
+            Ok((Some((price, volume, _, _)), _)) => Some((price, volume)),
+            _ => None,
+        }
+    }
+
+    /// Clear the pending orders and the crossed market orders of the trade pair together at the uniform price
+    /// which maximizes the matched volume. The larger side fills in price priority, the orders at its last
+    /// matched price pro rata. The pending orders left join the market orders.
+    /// Returns the clearing price, None if nothing is crossed.
+    fn clear_batch(tp_hash: T::Hash) -> result::Result<Option<T::Price>, dispatch::DispatchError> {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        T::Currency::ensure_active(tp.base)?;
+        T::Currency::ensure_active(tp.quote)?;
+
+        // the storage is not rolled back in on_finalize, so all the fills are checked before the first write
+        let (auction, unfillable) = Self::batch_auction(tp_hash)?;
+        let auction = match auction {
+            Some((price, volume, buys, sells)) => {
+                let fills = Self::batch_fills(&tp, price, volume, &buys, &sells)?;
+                Self::price_cumulative_at(&tp, <system::Module<T>>::block_number())?;
+                Some((price, volume, buys, sells, fills))
+            },
+            None => None,
+        };
+
+        // the orders which can't be filled, like the ones of the frozen accounts, are left out of the auction
+        for o in unfillable {
+            Self::cancel_order(tp_hash, o)?;
+        }
+
+        let price = match auction {
+            Some((price, volume, buys, sells, fills)) => {
+                Self::fill_batch(tp_hash, &tp, price, volume, buys, sells, fills)?;
+                Some(price)
+            },
+            None => None,
+        };
+
+        // nothing is crossed any more, so the orders could join the market orders at their price
+        for hash in <TPPendingOrders<T>>::take(tp_hash) {
+            let o = Self::order(hash).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+            if !o.is_finished() {
+                <OrderLinkedItemList<T>>::append(tp_hash, o.price, o.hash, o.remained_sell_amount, o.remained_buy_amount, o.otype);
+            }
+        }
+
+        Self::update_top_of_book(tp_hash)?;
+
+        Ok(price)
+    }
+
+    fn fill_batch(tp_hash: T::Hash, tp: &TradePair<T>, price: T::Price, volume: T::Balance, mut buys: Vec<LimitOrder<T>>,
+                  mut sells: Vec<LimitOrder<T>>, fills: Vec<(usize, usize, T::Balance, T::Balance)>) -> dispatch::DispatchResult {
+        let pending_orders = Self::trade_pair_pending_orders(tp_hash);
+
+        let buys_before = buys.clone();
+        let sells_before = sells.clone();
+
+        for (b, s, base_qty, quote_qty) in fills {
+            Self::batch_fill(tp_hash, tp, price, &mut buys[b], &mut sells[s], base_qty, quote_qty)?;
+        }
+
+        for (before, order) in buys_before.iter().zip(buys.iter_mut()).chain(sells_before.iter().zip(sells.iter_mut())) {
+            let in_market = !pending_orders.contains(&order.hash);
+            Self::settle_batch_order(tp_hash, tp, before, order, in_market)?;
+        }
+
+        Self::deposit_event(RawEvent::BatchCleared(tp_hash, price, volume));
+
+        Ok(())
+    }
+
+    // both sides fill the same volume, pair them up into trades in priority order. the trades are
+    // (buy index, sell index, base amount, quote amount), checked against copies of the orders
+    fn batch_fills(tp: &TradePair<T>, price: T::Price, volume: T::Balance, buys: &[LimitOrder<T>], sells: &[LimitOrder<T>])
+        -> result::Result<Vec<(usize, usize, T::Balance, T::Balance)>, dispatch::DispatchError> {
+        let mut buy_fills = Self::allocate(buys, volume)?;
+        let mut sell_fills = Self::allocate(sells, volume)?;
+
+        let mut buys = buys.to_vec();
+        let mut sells = sells.to_vec();
+
+        let mut fills = Vec::new();
+        let (mut b, mut s) = (0, 0);
+        while b < buys.len() && s < sells.len() {
+            if buy_fills[b].is_zero() {
+                b += 1;
+                continue;
+            }
+            if sell_fills[s].is_zero() {
+                s += 1;
+                continue;
+            }
+
+            let quote_qty = buy_fills[b].min(sell_fills[s]);
+            let base_qty = Self::batch_base_amount(tp, price, &buys[b], quote_qty)?;
+            Self::fill_batch_orders(&mut buys[b], &mut sells[s], base_qty, quote_qty)?;
+            fills.push((b, s, base_qty, quote_qty));
+
+            buy_fills[b] = buy_fills[b] - quote_qty;
+            sell_fills[s] = sell_fills[s] - quote_qty;
+        }
+
+        Ok(fills)
+    }
+
+    // the crossed pending and market orders in price then time priority with the clearing price and the quote
+    // volume, and the orders read which can't be filled. at most MaxPendingOrders market orders of each side are read
+    fn batch_auction(tp_hash: T::Hash)
+        -> result::Result<(Option<(T::Price, T::Balance, Vec<LimitOrder<T>>, Vec<LimitOrder<T>>)>, Vec<LimitOrder<T>>), dispatch::DispatchError> {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        let fillable = |o: &LimitOrder<T>| Self::is_fillable(&tp, o);
+
+        let mut unfillable = Vec::new();
+        let mut pending_buys = Vec::new();
+        let mut pending_sells = Vec::new();
+        for o in Self::trade_pair_pending_orders(tp_hash).iter() {
+            let o = Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+            match o.otype {
+                _ if o.is_finished() => {},
+                _ if !fillable(&o) => unfillable.push(o),
+                OrderType::Buy => pending_buys.push(o),
+                OrderType::Sell => pending_sells.push(o),
+            }
+        }
+
+        let (market_buy_one_price, _, market_sell_one_price, _) = Self::top_of_book(tp_hash);
+        let buy_one_price = pending_buys.iter().map(|o| o.price).chain(market_buy_one_price).max();
+        let sell_one_price = pending_sells.iter().map(|o| o.price).chain(market_sell_one_price).min();
+
+        let (buy_one_price, sell_one_price) = match (buy_one_price, sell_one_price) {
+            (Some(buy_one_price), Some(sell_one_price)) if buy_one_price >= sell_one_price => (buy_one_price, sell_one_price),
+            _ => return Ok((None, unfillable)),
+        };
+
+        let max_orders = T::MaxPendingOrders::get() as usize;
+
+        // the market orders come before the pending ones at the same price, the sorts are stable
+        let (mut buys, unfillable_buys): (Vec<_>, Vec<_>) = Self::market_orders_to(tp_hash, OrderType::Buy, sell_one_price, max_orders)?
+            .into_iter().partition(fillable);
+        buys.extend(pending_buys.into_iter().filter(|o| o.price >= sell_one_price));
+        buys.sort_by(|a, b| b.price.cmp(&a.price));
+
+        let (mut sells, unfillable_sells): (Vec<_>, Vec<_>) = Self::market_orders_to(tp_hash, OrderType::Sell, buy_one_price, max_orders)?
+            .into_iter().partition(fillable);
+        sells.extend(pending_sells.into_iter().filter(|o| o.price <= buy_one_price));
+        sells.sort_by(|a, b| a.price.cmp(&b.price));
+
+        unfillable.extend(unfillable_buys);
+        unfillable.extend(unfillable_sells);
+
+        let (price, volume) = match Self::auction_price(&buys, &sells) {
+            Some(auction) => auction,
+            None => return Ok((None, unfillable)),
+        };
+
+        buys.retain(|o| o.price >= price);
+        sells.retain(|o| o.price <= price);
+
+        Ok((Some((price, volume, buys, sells)), unfillable))
+    }
+
+    // the clearing price maximizes the volume, then minimizes the unmatched amount, then is the lowest one.
+    // the buys are in descending and the sells in ascending price order, so a sweep over the prices keeps the
+    // demand and the supply as running sums
+    fn auction_price(buys: &[LimitOrder<T>], sells: &[LimitOrder<T>]) -> Option<(T::Price, T::Balance)> {
+        let mut prices: Vec<T::Price> = buys.iter().chain(sells.iter()).map(|o| o.price).collect();
+        prices.sort();
+        prices.dedup();
+
+        // the amounts are in quote, the demand is of the buys not below the price, the supply of the sells not above it
+        let mut demand = buys.iter().fold(T::Balance::zero(), |total, o| total.saturating_add(o.remained_buy_amount));
+        let mut supply = T::Balance::zero();
+        let mut next_buy = buys.len();
+        let mut next_sell = 0;
+
+        // (price, volume, imbalance)
+        let mut best: Option<(T::Price, T::Balance, T::Balance)> = None;
+        for &price in prices.iter() {
+            while next_buy > 0 && buys[next_buy - 1].price < price {
+                next_buy -= 1;
+                demand = demand.saturating_sub(buys[next_buy].remained_buy_amount);
+            }
+            while next_sell < sells.len() && sells[next_sell].price <= price {
+                supply = supply.saturating_add(sells[next_sell].remained_sell_amount);
+                next_sell += 1;
+            }
+
+            let volume = demand.min(supply);
+            let imbalance = demand.max(supply) - volume;
+
+            let better = match best {
+                Some((_, best_volume, best_imbalance)) => volume > best_volume || (volume == best_volume && imbalance < best_imbalance),
+                None => true,
+            };
+            if better {
+                best = Some((price, volume, imbalance));
+            }
+        }
+
+        match best {
+            Some((price, volume, _)) if !volume.is_zero() => Some((price, volume)),
+            _ => None,
+        }
+    }
+
+    // the market orders of `otype` from the best price to `limit_price`, in price then time priority, at most `max_orders`
+    fn market_orders_to(tp_hash: T::Hash, otype: OrderType, limit_price: T::Price, max_orders: usize)
+        -> result::Result<Vec<LimitOrder<T>>, dispatch::DispatchError> {
+        let end_item_price = match otype {
+            OrderType::Buy => T::Price::min_value(),
+            OrderType::Sell => T::Price::max_value(),
+        };
+
+        let mut orders = Vec::new();
+        let mut item = <OrderLinkedItemList<T>>::read_head(tp_hash);
+        loop {
+            let price = Self::next_match_price(&item, otype).ok_or(Error::<T>::OrderMatchGetPriceError)?;
+            if price == end_item_price || !Self::price_matched(limit_price, !otype, price) {
+                break;
+            }
+
+            item = <LinkedItemList<T>>::get((tp_hash, Some(price))).ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
+            for o in item.orders.iter() {
+                if orders.len() >= max_orders {
+                    return Ok(orders);
+                }
+
+                let o = Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError)?;
+                if !o.is_finished() {
+                    orders.push(o);
                 }
             }
+        }
 
-            head = <OrderLinkedItemList<T>>::read_head(tp_hash);
+        Ok(orders)
+    }
+
+    // fill the orders price level by price level, the level which the volume runs out at shares it pro rata.
+    // filling the better levels first leaves no crossed orders behind
+    fn allocate(orders: &[LimitOrder<T>], volume: T::Balance) -> result::Result<Vec<T::Balance>, dispatch::DispatchError> {
+        let mut fills = Vec::with_capacity(orders.len());
+        let mut remained = volume;
+
+        let mut start = 0;
+        while start < orders.len() {
+            let end = orders[start..].iter().position(|o| o.price != orders[start].price)
+                .map_or(orders.len(), |len| start + len);
+
+            // the amounts are in quote
+            let sizes: Vec<T::Balance> = orders[start..end].iter().map(|o| match o.otype {
+                OrderType::Buy => o.remained_buy_amount,
+                OrderType::Sell => o.remained_sell_amount,
+            }).collect();
+
+            let level_fills = Self::pro_rata(&sizes, remained)?;
+            remained = level_fills.iter().fold(remained, |remained, fill| remained - *fill);
+            fills.extend(level_fills);
+
+            start = end;
         }
 
-        if order.status == OrderStatus::Filled {
-            Ok(true)
-        } else {
-            Ok(false)
+        Ok(fills)
+    }
+
+    // share the volume in proportion to the sizes, the rounding remainder goes one by one in priority order
+    fn pro_rata(sizes: &[T::Balance], volume: T::Balance) -> result::Result<Vec<T::Balance>, dispatch::DispatchError> {
+        let total = sizes.iter().fold(T::Balance::zero(), |total, size| total.saturating_add(*size));
+        if total <= volume {
+            return Ok(sizes.to_vec());
+        }
+
+        let mut fills = Vec::with_capacity(sizes.len());
+        let mut allocated = T::Balance::zero();
+        for size in sizes.iter() {
+            let fill = U256::from(Self::into_128(volume)?) * U256::from(Self::into_128(*size)?) / U256::from(Self::into_128(total)?);
+            let fill: T::Balance = Self::from_128(fill.low_u128())?;
+            allocated = allocated + fill;
+            fills.push(fill);
+        }
+
+        let mut remainder = volume - allocated;
+        for (fill, size) in fills.iter_mut().zip(sizes.iter()) {
+            if remainder.is_zero() {
+                break;
+            }
+            if *fill < *size {
+                *fill = *fill + One::one();
+                remainder = remainder - One::one();
+            }
         }
+
+        Ok(fills)
+    }
+
+    // the base amount of the quote amount at the clearing price, rounded down and not more than the buy order sells
+    fn batch_base_amount(tp: &TradePair<T>, price: T::Price, buy_order: &LimitOrder<T>, quote_qty: T::Balance)
+        -> result::Result<T::Balance, dispatch::DispatchError> {
+        let base_qty = U256::from(Self::into_128(quote_qty)?) * U256::from(Self::into_128(price)?) / U256::from(tp.price_factor());
+        ensure!(base_qty <= U256::from(u128::max_value()), Error::<T>::NumberCastError);
+        let base_qty: T::Balance = Self::from_128(base_qty.low_u128())?;
+
+        Ok(base_qty.min(buy_order.remained_sell_amount))
+    }
+
+    fn fill_batch_orders(buy_order: &mut LimitOrder<T>, sell_order: &mut LimitOrder<T>, base_qty: T::Balance,
+                         quote_qty: T::Balance) -> dispatch::DispatchResult {
+        if buy_order.status == OrderStatus::Created {
+            buy_order.status = OrderStatus::PartialFilled;
+        }
+        if sell_order.status == OrderStatus::Created {
+            sell_order.status = OrderStatus::PartialFilled;
+        }
+
+        buy_order.remained_sell_amount = buy_order.remained_sell_amount.checked_sub(&base_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+        buy_order.remained_buy_amount = buy_order.remained_buy_amount.checked_sub(&quote_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+        sell_order.remained_sell_amount = sell_order.remained_sell_amount.checked_sub(&quote_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+        // the sell order may get more base than it asks for at its own price
+        sell_order.remained_buy_amount = sell_order.remained_buy_amount.saturating_sub(base_qty);
+
+        Ok(())
+    }
+
+    // exchange the amounts checked by batch_fills at the clearing price. there is no maker in an auction,
+    // the sell order is recorded as the maker
+    fn batch_fill(tp_hash: T::Hash, tp: &TradePair<T>, price: T::Price, buy_order: &mut LimitOrder<T>, sell_order: &mut LimitOrder<T>,
+                  base_qty: T::Balance, quote_qty: T::Balance) -> dispatch::DispatchResult {
+        Self::fill_batch_orders(buy_order, sell_order, base_qty, quote_qty)?;
+
+        T::Currency::repatriate_reserved(tp.base, ReserveId::Order(buy_order.hash), &buy_order.owner, &sell_order.owner, base_qty)?;
+        T::Currency::repatriate_reserved(tp.quote, ReserveId::Order(sell_order.hash), &sell_order.owner, &buy_order.owner, quote_qty)?;
+
+        Self::set_tp_market_data(tp_hash, price, base_qty, quote_qty)?;
+
+        let mut trade = Trade::new(tp.base, tp.quote, sell_order, buy_order, base_qty, quote_qty);
+        trade.price = price;
+        Self::save_trade(tp_hash, trade, sell_order, buy_order);
+
+        Ok(())
+    }
+
+    // close the order once it is filled, and take its filled amounts out of the market order list
+    fn settle_batch_order(tp_hash: T::Hash, tp: &TradePair<T>, before: &LimitOrder<T>, order: &mut LimitOrder<T>,
+                          in_market: bool) -> dispatch::DispatchResult {
+        if *order == *before {
+            return Ok(());
+        }
+
+        let (completed, sell_token_hash) = match order.otype {
+            OrderType::Buy => (order.remained_buy_amount.is_zero(), tp.base),
+            OrderType::Sell => (order.remained_sell_amount.is_zero(), tp.quote),
+        };
+
+        if completed {
+            order.status = OrderStatus::Filled;
+            if !order.remained_sell_amount.is_zero() {
+                T::Currency::unreserve(sell_token_hash, ReserveId::Order(order.hash), &order.owner, order.remained_sell_amount)?;
+                order.remained_sell_amount = Zero::zero();
+            }
+            order.remained_buy_amount = Zero::zero();
+
+            <OwnedTPOpenedOrders<T>>::remove_order(order.owner.clone(), tp_hash, order.hash);
+            <OwnedOpenedOrders<T>>::remove_order(order.owner.clone(), tp_hash, order.hash);
+            <OwnedTPClosedOrders<T>>::add_order(order.owner.clone(), tp_hash, order.hash);
+        }
+
+        if in_market {
+            <OrderLinkedItemList<T>>::update_amount(tp_hash, order.price,
+                before.remained_sell_amount - order.remained_sell_amount, before.remained_buy_amount - order.remained_buy_amount);
+            if order.is_finished() {
+                <OrderLinkedItemList<T>>::remove_order(tp_hash, order.price, order.hash, Zero::zero(), Zero::zero())?;
+            }
+        }
+
+        <Orders<T>>::insert(order.hash, order.clone());
+
+        if order.is_finished() {
+            Self::archive_order(order);
+        }
+
+        Ok(())
     }
 
     fn into_128<A: TryInto<u128>>(i: A) -> Result<u128, dispatch::DispatchError> {
@@ -812,7 +2124,7 @@ impl<T: Trait> Module<T> {
         TryFrom::<u128>::try_from(i).map_err(|_| Error::<T>::NumberCastError.into())
     }
 
-    fn calculate_ex_amount(maker_order: &LimitOrder<T>, taker_order: &LimitOrder<T>) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
+    fn calculate_ex_amount(maker_order: &LimitOrder<T>, taker_order: &LimitOrder<T>, price_factor: u128) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
         let buyer_order;
         let seller_order;
         if taker_order.otype == OrderType::Buy {
@@ -823,57 +2135,204 @@ impl<T: Trait> Module<T> {
             seller_order = taker_order;
         }
 
-        // todo: overflow checked need
-        // todo: optimization need,
-        let mut seller_order_filled = true;
-        if seller_order.remained_buy_amount <= buyer_order.remained_sell_amount { // seller_order is Filled
-            let quote_qty: u128 =
-                Self::into_128(seller_order.remained_buy_amount)? * T::PriceFactor::get() / maker_order.price.into();
-            if Self::into_128(buyer_order.remained_buy_amount)? < quote_qty {
-                seller_order_filled = false;
-            }
+        let price = U256::from(Self::into_128(maker_order.price)?);
+        let price_factor = U256::from(price_factor);
+        let seller_remained_buy_amount = U256::from(Self::into_128(seller_order.remained_buy_amount)?);
+        let buyer_remained_buy_amount = U256::from(Self::into_128(buyer_order.remained_buy_amount)?);
+        let buyer_remained_sell_amount = U256::from(Self::into_128(buyer_order.remained_sell_amount)?);
+
+        let seller_order_filled = if seller_remained_buy_amount <= buyer_remained_sell_amount {
+            let quote_qty = Self::checked_mul_div(seller_remained_buy_amount, price_factor, price)?;
+            buyer_remained_buy_amount >= quote_qty
         } else {
-            let base_qty: u128 =
-                Self::into_128(buyer_order.remained_buy_amount)? * maker_order.price.into() / T::PriceFactor::get();
-            if Self::into_128(seller_order.remained_buy_amount)? >= base_qty {
-                seller_order_filled = false;
-            }
-        }
+            let base_qty = Self::checked_mul_div(buyer_remained_buy_amount, price, price_factor)?;
+            seller_remained_buy_amount < base_qty
+        };
 
-        // if seller_order.remained_buy_amount <= buyer_order.remained_sell_amount { // seller_order is Filled
         if seller_order_filled {
-            let mut quote_qty: u128 =
-                Self::into_128(seller_order.remained_buy_amount)? * T::PriceFactor::get() / maker_order.price.into();
-            let buy_amount_v2 = quote_qty * Self::into_128(maker_order.price)? / T::PriceFactor::get();
-            if buy_amount_v2 != Self::into_128(seller_order.remained_buy_amount)? &&
-                Self::into_128(buyer_order.remained_buy_amount)? > quote_qty // have fraction, seller(Filled) give more to align
+            let mut quote_qty = Self::checked_mul_div(seller_remained_buy_amount, price_factor, price)?;
+            let buy_amount_v2 = Self::checked_mul_div(quote_qty, price, price_factor)?;
+            if buy_amount_v2 != seller_remained_buy_amount &&
+                buyer_remained_buy_amount > quote_qty // have fraction, seller(Filled) give more to align
             {
-                quote_qty = quote_qty + 1;
+                quote_qty = quote_qty + U256::one();
             }
 
-            return Ok
-                ((
-                    seller_order.remained_buy_amount,
-                    Self::from_128(quote_qty)?
-                ))
+            Ok((seller_order.remained_buy_amount, Self::u256_to_balance(quote_qty)?))
         } else { // buyer_order is Filled
-            let mut base_qty: u128 =
-                Self::into_128(buyer_order.remained_buy_amount)? * maker_order.price.into() / T::PriceFactor::get();
-            let buy_amount_v2 = base_qty * T::PriceFactor::get() / maker_order.price.into();
-            if buy_amount_v2 != Self::into_128(buyer_order.remained_buy_amount)? &&
-                Self::into_128(seller_order.remained_buy_amount)? > base_qty // have fraction, buyer(Filled) give more to align
+            let mut base_qty = Self::checked_mul_div(buyer_remained_buy_amount, price, price_factor)?;
+            let buy_amount_v2 = Self::checked_mul_div(base_qty, price_factor, price)?;
+            if buy_amount_v2 != buyer_remained_buy_amount &&
+                seller_remained_buy_amount > base_qty // have fraction, buyer(Filled) give more to align
             {
-                base_qty = base_qty + 1;
+                base_qty = base_qty + U256::one();
             }
 
-            return Ok
-                ((
-                    Self::from_128(base_qty)?,
-                    buyer_order.remained_buy_amount
-                ))
+            Ok((Self::u256_to_balance(base_qty)?, buyer_order.remained_buy_amount))
+        }
+    }
+
+    fn checked_mul_div(value: U256, mul: U256, div: U256) -> result::Result<U256, dispatch::DispatchError> {
+        value.checked_mul(mul)
+            .and_then(|v| v.checked_div(div))
+            .ok_or_else(|| Error::<T>::OverflowError.into())
+    }
+
+    fn u256_to_balance(value: U256) -> result::Result<T::Balance, dispatch::DispatchError> {
+        let value: u128 = value.try_into().map_err(|_| Error::<T>::OverflowError)?;
+        Self::from_128(value)
+    }
+
+    fn calculate_market_ex_amount(maker_order: &LimitOrder<T>, taker_order: &LimitOrder<T>, price_factor: u128) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
+        // the maker order is filled, same as a limit order which buys without a limit
+        if taker_order.remained_sell_amount >= maker_order.remained_buy_amount {
+            let mut taker_order = taker_order.clone();
+            taker_order.remained_buy_amount = T::Balance::max_value();
+            return Self::calculate_ex_amount(maker_order, &taker_order, price_factor)
+        }
+
+        // the taker order sells all of its remained sell amount, the counterparty amount is rounded down
+        let price = U256::from(Self::into_128(maker_order.price)?);
+        let remained_sell_amount = U256::from(Self::into_128(taker_order.remained_sell_amount)?);
+        let maker_remained_sell_amount = U256::from(Self::into_128(maker_order.remained_sell_amount)?);
+
+        match taker_order.otype {
+            OrderType::Buy => {
+                let quote_qty = (remained_sell_amount * U256::from(price_factor) / price).min(maker_remained_sell_amount);
+                Ok((taker_order.remained_sell_amount, Self::from_128(quote_qty.low_u128())?))
+            },
+            OrderType::Sell => {
+                let base_qty = (remained_sell_amount * price / U256::from(price_factor)).min(maker_remained_sell_amount);
+                Ok((Self::from_128(base_qty.low_u128())?, taker_order.remained_sell_amount))
+            },
+        }
+    }
+
+    // the taker order buys at most `allocation` from the maker order, in the token the maker order sells.
+    // the rounding of many fills could add up beyond the remained sell amount of the taker order, so it is capped
+    fn calculate_allocated_ex_amount(maker_order: &LimitOrder<T>, taker_order: &LimitOrder<T>, allocation: T::Balance,
+                                     price_factor: u128) -> result::Result<(T::Balance, T::Balance), dispatch::DispatchError> {
+        let mut allocated_order = taker_order.clone();
+        allocated_order.remained_buy_amount = allocation;
+
+        let (base_qty, quote_qty) = Self::calculate_ex_amount(maker_order, &allocated_order, price_factor)?;
+
+        match taker_order.otype {
+            OrderType::Buy => Ok((base_qty.min(taker_order.remained_sell_amount), quote_qty)),
+            OrderType::Sell => Ok((base_qty, quote_qty.min(taker_order.remained_sell_amount))),
         }
     }
 
+    // how much of their remained sell amounts the maker orders of a level fill, None when they fill in time
+    // priority, which is also the case when the taker order takes the whole level
+    fn level_allocations(tp: &TradePair<T>, makers: &[LimitOrder<T>], taker_order: &LimitOrder<T>, market: bool)
+        -> result::Result<Option<Vec<T::Balance>>, dispatch::DispatchError> {
+        if tp.allocation_policy == AllocationPolicy::Fifo || makers.is_empty() {
+            return Ok(None);
+        }
+
+        // in the token the maker orders sell
+        let demand = if market {
+            let price = U256::from(Self::into_128(makers[0].price)?);
+            let remained_sell_amount = U256::from(Self::into_128(taker_order.remained_sell_amount)?);
+            let demand = match taker_order.otype {
+                OrderType::Buy => remained_sell_amount * U256::from(tp.price_factor()) / price,
+                OrderType::Sell => remained_sell_amount * price / U256::from(tp.price_factor()),
+            };
+            Self::from_128(demand.min(U256::from(u128::max_value())).low_u128())?
+        } else {
+            taker_order.remained_buy_amount
+        };
+
+        let mut sizes: Vec<T::Balance> = makers.iter()
+            .map(|o| if o.is_finished() { Zero::zero() } else { o.remained_sell_amount })
+            .collect();
+        let total = sizes.iter().fold(T::Balance::zero(), |total, size| total.saturating_add(*size));
+        if total <= demand {
+            return Ok(None);
+        }
+
+        let mut top_fill = None;
+        if tp.allocation_policy == AllocationPolicy::FifoTopProRata {
+            if let Some(top) = sizes.iter().position(|size| !size.is_zero()) {
+                top_fill = Some((top, sizes[top].min(demand)));
+                sizes[top] = Zero::zero();
+            }
+        }
+
+        let remained_demand = top_fill.map_or(demand, |(_, fill)| demand - fill);
+        let mut allocations = Self::pro_rata(&sizes, remained_demand)?;
+        if let Some((top, fill)) = top_fill {
+            allocations[top] = fill;
+        }
+
+        Ok(Some(allocations))
+    }
+
+    // the fills of the maker orders of a level for the taker order, it changes no state, so the matching and
+    // the quotes fill the same amounts
+    fn match_level(tp: &TradePair<T>, makers: &[LimitOrder<T>], taker_order: &LimitOrder<T>, market: bool)
+        -> result::Result<LevelMatch<T::Balance>, dispatch::DispatchError> {
+        let allocations = Self::level_allocations(tp, makers, taker_order, market)?;
+        let mut level = LevelMatch { fills: Vec::new(), pro_rata: allocations.is_some(), dust: false };
+
+        // the remained amount of the order is too small to be allocated anything
+        if allocations.as_ref().map_or(false, |allocations| allocations.iter().all(|a| a.is_zero())) {
+            level.dust = true;
+            return Ok(level);
+        }
+
+        let mut order = taker_order.clone();
+        for (index, o) in makers.iter().enumerate() {
+            if o.is_finished() {
+                continue;
+            }
+
+            let (base_qty, quote_qty) = match allocations {
+                Some(ref allocations) if allocations[index].is_zero() => continue,
+                Some(ref allocations) => Self::calculate_allocated_ex_amount(o, &order, allocations[index], tp.price_factor())?,
+                None if market => Self::calculate_market_ex_amount(o, &order, tp.price_factor())?,
+                None => Self::calculate_ex_amount(o, &order, tp.price_factor())?,
+            };
+
+            // the remained sell amount of the market order is too small to buy anything
+            if market && (base_qty.is_zero() || quote_qty.is_zero()) {
+                level.dust = true;
+                break;
+            }
+
+            let (give_qty, have_qty) = Self::taker_fill_amounts(order.otype, base_qty, quote_qty);
+            Self::fill_taker(&mut order, give_qty, have_qty, market)?;
+            level.fills.push((index, base_qty, quote_qty));
+
+            if Self::order_completed(&order, market) {
+                break;
+            }
+        }
+
+        Ok(level)
+    }
+
+    // (the amount the taker order gives, the amount it gets) of a fill
+    fn taker_fill_amounts(otype: OrderType, base_qty: T::Balance, quote_qty: T::Balance) -> (T::Balance, T::Balance) {
+        match otype {
+            OrderType::Buy => (base_qty, quote_qty),
+            OrderType::Sell => (quote_qty, base_qty),
+        }
+    }
+
+    // a market order may buy more than its buy amount
+    fn fill_taker(order: &mut LimitOrder<T>, give_qty: T::Balance, have_qty: T::Balance, market: bool) -> dispatch::DispatchResult {
+        order.remained_sell_amount = order.remained_sell_amount.checked_sub(&give_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+        if market {
+            order.remained_buy_amount = order.remained_buy_amount.saturating_sub(have_qty);
+        } else {
+            order.remained_buy_amount = order.remained_buy_amount.checked_sub(&have_qty).ok_or(Error::<T>::OrderMatchSubstractError)?;
+        }
+
+        Ok(())
+    }
+
     fn next_match_price(item: &OrderLinkedItem<T>, otype: OrderType) -> Option<T::Price> {
         if otype == OrderType::Buy {
             item.prev
@@ -889,10 +2348,23 @@ impl<T: Trait> Module<T> {
         }
     }
 
-    pub fn set_tp_market_data(tp_hash: T::Hash, price: T::Price, amount: T::Balance) -> dispatch::DispatchResult {
+    pub fn set_tp_market_data(tp_hash: T::Hash, price: T::Price, base_amount: T::Balance, amount: T::Balance) -> dispatch::DispatchResult {
 
         let mut tp = <TradePairs<T>>::get(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
 
+        let now = <system::Module<T>>::block_number();
+        if tp.latest_matched_price.is_none() || tp.price_cumulative_block < now {
+            tp.price_cumulative = Self::price_cumulative_at(&tp, now)?;
+            tp.price_cumulative_block = now;
+
+            let mut observations = <TPPriceObservations<T>>::get(tp_hash);
+            observations.push((now, tp.price_cumulative));
+            if observations.len() > T::OracleObservationsCap::get() as usize {
+                observations.remove(0);
+            }
+            <TPPriceObservations<T>>::insert(tp_hash, observations);
+        }
+
         tp.latest_matched_price = Some(price);
 
         let mut bucket = <TPTradeDataBucket<T>>::get((tp_hash, <system::Module<T>>::block_number()));
@@ -921,13 +2393,23 @@ impl<T: Trait> Module<T> {
         }
 
         <TPTradeDataBucket<T>>::insert((tp_hash, <system::Module<T>>::block_number()), bucket);
+
+        let mut ticker_bucket = <TPTradeTickerDataBucket<T>>::get((tp_hash, <system::Module<T>>::block_number()));
+        ticker_bucket.0 = ticker_bucket.0 + base_amount;
+        ticker_bucket.1 = ticker_bucket.1 + 1;
+        if ticker_bucket.2.is_none() {
+            ticker_bucket.2 = Some(price);
+        }
+        ticker_bucket.3 = Some(price);
+        <TPTradeTickerDataBucket<T>>::insert((tp_hash, <system::Module<T>>::block_number()), ticker_bucket);
+
         <TradePairs<T>>::insert(tp_hash, tp);
 
         Ok(())
     }
 
     fn do_cancel_limit_order(sender: T::AccountId, order_hash: T::Hash) -> dispatch::DispatchResult {
-        let mut order = Self::order(order_hash).ok_or(Error::<T>::NoMatchingOrder)?;
+        let order = Self::order(order_hash).ok_or(Error::<T>::NoMatchingOrder)?;
 
         ensure!(order.owner == sender, Error::<T>::CanOnlyCancelOwnOrder);
 
@@ -935,12 +2417,28 @@ impl<T: Trait> Module<T> {
 
         let tp_hash = Self::ensure_trade_pair(order.base, order.quote)?;
 
-        <OrderLinkedItemList<T>>::remove_order(tp_hash, order.price, order.hash, order.sell_amount, order.buy_amount)?;
+        Self::cancel_order(tp_hash, order)
+    }
+
+    // take the not finished order off the market or the pending orders, and unreserve its remained sell amount
+    fn cancel_order(tp_hash: T::Hash, mut order: LimitOrder<T>) -> dispatch::DispatchResult {
+        let order_hash = order.hash;
+        let sender = order.owner.clone();
+
+        let mut pending_orders = Self::trade_pair_pending_orders(tp_hash);
+        if pending_orders.contains(&order_hash) {
+            pending_orders.retain(|&x| x != order_hash);
+            <TPPendingOrders<T>>::insert(tp_hash, pending_orders);
+        } else {
+            <OrderLinkedItemList<T>>::remove_order(tp_hash, order.price, order.hash, order.remained_sell_amount, order.remained_buy_amount)?;
+        }
 
         order.status = OrderStatus::Canceled;
         <Orders<T>>::insert(order_hash, order.clone());
+        Self::archive_order(&order);
 
         <OwnedTPOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order_hash);
+        <OwnedOpenedOrders<T>>::remove_order(sender.clone(), tp_hash, order_hash);
         <OwnedTPClosedOrders<T>>::add_order(sender.clone(), tp_hash, order_hash);
 
         let sell_hash = match order.otype {
@@ -948,13 +2446,591 @@ impl<T: Trait> Module<T> {
             OrderType::Sell => order.quote,
         };
 
-        <token::Module<T>>::do_unfreeze(sender.clone(), sell_hash, order.remained_sell_amount)?;
+        T::Currency::unreserve(sell_hash, ReserveId::Order(order_hash), &sender, order.remained_sell_amount)?;
 
         Self::deposit_event(RawEvent::OrderCanceled(sender, order_hash));
 
+        Self::update_top_of_book(tp_hash)?;
+
         Ok(())
     }
 
+    fn archive_order(order: &LimitOrder<T>) {
+        let leaf = order.using_encoded(<T as system::Trait>::Hashing::hash);
+        Self::archive(ArchiveItem::Order(order.hash), leaf);
+    }
+
+    // record the item under the current block, and append its leaf to the block's merkle tree
+    fn archive(item: ArchiveItem<T>, leaf: T::Hash) {
+        let now = <system::Module<T>>::block_number();
+
+        let index = Self::archive_items_index(now);
+        <ArchiveItems<T>>::insert((now, index), item);
+        <ArchiveItemsIndex<T>>::insert(now, index + 1);
+
+        // frontier[h] is the root of a full subtree of 2^h leaves, valid while the h-th bit of the leaf count is set
+        let mut frontier = Self::archive_merkle_frontier(now);
+        let mut node = leaf;
+        let mut height = 0;
+        let mut count = index;
+        while count & 1 == 1 {
+            node = (frontier[height], node).using_encoded(<T as system::Trait>::Hashing::hash);
+            height += 1;
+            count >>= 1;
+        }
+
+        if height < frontier.len() {
+            frontier[height] = node;
+        } else {
+            frontier.push(node);
+        }
+        <ArchiveMerkleFrontier<T>>::insert(now, frontier);
+    }
+
+    /// The root of the archive merkle tree, the subtree roots are folded from the smallest to the largest
+    pub fn archive_merkle_root(count: u64, frontier: &[T::Hash]) -> T::Hash {
+        let mut root: Option<T::Hash> = None;
+
+        for (height, node) in frontier.iter().enumerate() {
+            if count >> height & 1 == 1 {
+                root = match root {
+                    None => Some(*node),
+                    Some(root) => Some((*node, root).using_encoded(<T as system::Trait>::Hashing::hash)),
+                };
+            }
+        }
+
+        root.unwrap_or_default()
+    }
+
+    // prune the items archived before the retention window, at most ArchivePruneBatchSize storage entries per block
+    fn prune_archive(now: T::BlockNumber) -> Weight {
+        let retention: T::BlockNumber = <<T as system::Trait>::BlockNumber as From<_>>::from(T::ArchiveRetentionBlocks::get());
+
+        if now <= retention {
+            return 0
+        }
+
+        let end = now - retention;
+        let budget = T::ArchivePruneBatchSize::get() as u64;
+        let (mut block, mut index) = Self::archive_prune_cursor();
+        let mut pruned = 0u64;
+
+        while block < end && pruned < budget {
+            let count = Self::archive_items_index(block);
+
+            if index == 0 && count > 0 {
+                let frontier = <ArchiveMerkleFrontier<T>>::take(block);
+                <ArchiveRoots<T>>::insert(block, (Self::archive_merkle_root(count, &frontier), count));
+            }
+
+            while index < count && pruned < budget {
+                let (removed, done) = Self::prune_archive_item((block, index), budget - pruned);
+                pruned += removed;
+                if !done {
+                    break;
+                }
+                index += 1;
+            }
+
+            if index < count {
+                break;
+            }
+
+            <ArchiveItemsIndex<T>>::remove(block);
+            block = block + One::one();
+            index = 0;
+            pruned += 1;
+        }
+
+        <ArchivePruneCursor<T>>::put((block, index));
+
+        pruned * 10_000
+    }
+
+    // prune one archived item within the budget, the trades of an order are removed over as many blocks as needed
+    // and the order is kept until they are all gone, returns (storage entries removed, whether the item is done)
+    fn prune_archive_item(key: (T::BlockNumber, u64), budget: u64) -> (u64, bool) {
+        let item = match <ArchiveItems<T>>::get(key) {
+            Some(item) => item,
+            None => return (0, true),
+        };
+
+        match item {
+            ArchiveItem::Order(order_hash) => {
+                let trades = <OrderOwnedTradesIndex<T>>::get(order_hash);
+                let removed = trades.min(budget);
+                for i in trades - removed..trades {
+                    <OrderOwnedTrades<T>>::remove((order_hash, i));
+                }
+
+                // the order itself is removed once there is budget left for it
+                if removed < trades || removed == budget {
+                    <OrderOwnedTradesIndex<T>>::insert(order_hash, trades - removed);
+                    return (removed, false)
+                }
+
+                <OrderOwnedTradesIndex<T>>::remove(order_hash);
+                <ArchiveItems<T>>::remove(key);
+
+                if let Some(order) = <Orders<T>>::take(order_hash) {
+                    let tp_hash = Self::trade_pair_hash_by_base_quote((order.base, order.quote));
+                    if let Some((owned_index, tp_owned_index)) = <OrderIndexes<T>>::take(order_hash) {
+                        <OwnedOrders<T>>::remove((order.owner.clone(), owned_index));
+                        if let Some(tp_hash) = tp_hash {
+                            <TradePairOwnedOrders<T>>::remove((tp_hash, tp_owned_index));
+                        }
+                    }
+                    if let Some(tp_hash) = tp_hash {
+                        <OwnedTPClosedOrders<T>>::remove_order(order.owner, tp_hash, order_hash);
+                    }
+                }
+
+                (1 + removed, true)
+            },
+            ArchiveItem::Trade(trade_hash, tp_owned_index, owners) => {
+                <ArchiveItems<T>>::remove(key);
+
+                if let Some(trade) = <Trades<T>>::take(trade_hash) {
+                    if let Some(tp_hash) = Self::trade_pair_hash_by_base_quote((trade.base, trade.quote)) {
+                        <TradePairOwnedTrades<T>>::remove((tp_hash, tp_owned_index));
+                        for (account_id, owned_index, owned_tp_index) in owners {
+                            <OwnedTrades<T>>::remove((account_id.clone(), owned_index));
+                            <OwnedTPTrades<T>>::remove((account_id, tp_hash, owned_tp_index));
+                        }
+                    }
+                }
+
+                (1, true)
+            },
+        }
+    }
+
+    // list the orders opened before the index of all the opened orders in it, once
+    fn migrate_opened_orders() -> Weight {
+        if OpenedOrdersComplete::get() {
+            return 0;
+        }
+
+        let mut read: Weight = 1;
+        let mut written: Weight = 1;
+        for (order_hash, order) in <Orders<T>>::iter() {
+            read += 1;
+            if order.is_finished() {
+                continue;
+            }
+
+            read += 1;
+            let tp_hash = match Self::trade_pair_hash_by_base_quote((order.base, order.quote)) {
+                Some(tp_hash) => tp_hash,
+                None => continue,
+            };
+
+            // the position, the index of the account and the index of the account in the trade pair
+            read += 3;
+            written += 5;
+            <OwnedOpenedOrders<T>>::add_order(order.owner.clone(), tp_hash, order_hash);
+        }
+        OpenedOrdersComplete::put(true);
+
+        (read + written) * 100_000
+    }
+
+    // move the balances frozen for the open orders before the named reserves, now in the legacy reserve,
+    // to the reserves of the orders, once
+    fn migrate_order_reserves() -> Weight {
+        if OrderReservesComplete::get() {
+            return 0;
+        }
+
+        let currency_weight = T::Currency::migrate_legacy_reserves();
+        let legacy = ReserveId::Named(LEGACY_RESERVE);
+
+        // only the opened orders hold a reserve, the index of each account lists them
+        let mut read: Weight = 0;
+        let mut migrated: Weight = 0;
+        for (owner, count) in <OwnedOpenedOrdersIndex<T>>::iter() {
+            read += 1;
+            for index in 0 .. count {
+                read += 2;
+                let order = match Self::owned_opened_order((owner.clone(), index)).and_then(Self::order) {
+                    Some(order) => order,
+                    None => continue,
+                };
+                if order.is_finished() || order.remained_sell_amount.is_zero() {
+                    continue;
+                }
+
+                let sell_token_hash = match order.otype {
+                    OrderType::Buy => order.base,
+                    OrderType::Sell => order.quote,
+                };
+                read += 1;
+                if T::Currency::named_reserve(sell_token_hash, legacy, &order.owner) < order.remained_sell_amount {
+                    continue;
+                }
+
+                // the legacy reserve covers the amount, so neither fails
+                let _ = T::Currency::unreserve(sell_token_hash, legacy, &order.owner, order.remained_sell_amount);
+                let _ = T::Currency::reserve(sell_token_hash, ReserveId::Order(order.hash), &order.owner, order.remained_sell_amount);
+                migrated += 1;
+            }
+        }
+        OrderReservesComplete::put(true);
+
+        currency_weight + (read + 2 * migrated) * 100_000
+    }
+
+    // bring the trade pairs stored in an old layout to the current one, one layout change at a time
+    fn migrate_trade_pairs() -> Weight {
+        let version = Self::storage_version();
+        if version >= STORAGE_VERSION {
+            return 0;
+        }
+
+        // the version and the index
+        let mut read: Weight = 2;
+        let mut written: Weight = 1;
+        for index in 0 .. TradePairsIndex::get() {
+            read += 1;
+            let tp_hash = match Self::trade_pair_hash_by_index(index) {
+                Some(tp_hash) => tp_hash,
+                None => continue,
+            };
+
+            read += 1;
+            let key = <TradePairs<T>>::hashed_key_for(tp_hash);
+            let mut raw = match unhashed::get_raw(&key) {
+                Some(raw) => raw,
+                None => continue,
+            };
+
+            // the trade pairs created by the current runtime are up to date already
+            let input = &mut &raw[..];
+            if TradePair::<T>::decode(input).is_ok() && input.is_empty() {
+                continue;
+            }
+
+            for step in version + 1 ..= STORAGE_VERSION {
+                let (fields, reads) = Self::trade_pair_fields(step, tp_hash);
+                raw.extend(fields);
+                read += reads;
+            }
+            unhashed::put_raw(&key, &raw);
+            written += 1;
+        }
+        StorageVersion::put(STORAGE_VERSION);
+
+        (read + written) * 100_000
+    }
+
+    // the encoded fields which the layout change to `version` appended to the trade pair with their values
+    // for the existing trade pairs, and the storage reads to get them
+    fn trade_pair_fields(version: u32, tp_hash: T::Hash) -> (Vec<u8>, Weight) {
+        match version {
+            1 => (T::DefaultPriceDecimals::get().encode(), 0),
+            2 => (Self::top_of_book(tp_hash).encode(), 3),
+            3 => ((T::Balance::zero(), 0u64, None::<T::Price>, None::<T::Price>).encode(), 0),
+            4 => ((U256::zero(), T::BlockNumber::zero()).encode(), 0),
+            5 => (MatchingMode::Continuous.encode(), 0),
+            6 => (None::<T::BlockNumber>.encode(), 0),
+            _ => (AllocationPolicy::Fifo.encode(), 0),
+        }
+    }
+
+    // the latest matched price holds from the last update of the price cumulative up to the block
+    fn price_cumulative_at(tp: &TradePair<T>, block_number: T::BlockNumber) -> result::Result<U256, dispatch::DispatchError> {
+        match tp.latest_matched_price {
+            Some(price) => {
+                let price: u128 = price.into();
+                let elapsed = Self::into_128(block_number - tp.price_cumulative_block)?;
+                Ok(tp.price_cumulative + U256::from(price) * U256::from(elapsed))
+            },
+            None => Ok(tp.price_cumulative),
+        }
+    }
+
+    fn do_twap(tp_hash: T::Hash, window: T::BlockNumber) -> result::Result<T::Price, dispatch::DispatchError> {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+        ensure!(tp.latest_matched_price.is_some(), Error::<T>::OraclePriceUnavailable);
+
+        let now = <system::Module<T>>::block_number();
+        let max_age: T::BlockNumber = <<T as system::Trait>::BlockNumber as From<_>>::from(T::OracleMaxAge::get());
+        ensure!(now - tp.price_cumulative_block <= max_age, Error::<T>::OraclePriceStale);
+        ensure!(window > Zero::zero() && window <= now, Error::<T>::OracleInsufficientHistory);
+
+        // the price is constant between two observations, so the cumulative is linear in between
+        let target = now - window;
+        let observations = Self::trade_pair_price_observations(tp_hash);
+        let position = observations.iter().rposition(|&(n, _)| n <= target)
+            .ok_or(Error::<T>::OracleInsufficientHistory)?;
+        let (start_block, start_cumulative) = observations[position];
+        let target_cumulative = match observations.get(position + 1) {
+            Some(&(end_block, end_cumulative)) => {
+                let elapsed = U256::from(Self::into_128(target - start_block)?);
+                let span = U256::from(Self::into_128(end_block - start_block)?);
+                start_cumulative + (end_cumulative - start_cumulative) * elapsed / span
+            },
+            None => {
+                let tp_at_start = TradePair { price_cumulative: start_cumulative, price_cumulative_block: start_block, ..tp.clone() };
+                Self::price_cumulative_at(&tp_at_start, target)?
+            },
+        };
+
+        let twap = (Self::price_cumulative_at(&tp, now)? - target_cumulative) / U256::from(Self::into_128(window)?);
+        ensure!(twap <= U256::from(u128::max_value()), Error::<T>::NumberCastError);
+
+        Ok(T::Price::from(twap.low_u128()))
+    }
+
+    /// Simulate the order against the current market orders without changing any state
+    pub fn quote_order(tp_hash: T::Hash, otype: OrderType, price: T::Price, sell_amount: T::Balance)
+        -> result::Result<OrderQuote<T::Price, T::Balance>, dispatch::DispatchError> {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+
+        Self::ensure_bounds(price, sell_amount)?;
+        let buy_amount = Self::ensure_counterparty_amount_bounds(otype, price, sell_amount, tp.price_factor())?;
+
+        let order = LimitOrder::new(tp.base, tp.quote, Default::default(), price, sell_amount, buy_amount, otype);
+
+        Self::simulate_order(&tp, order, false)
+    }
+
+    /// Simulate the market order (see `do_create_market_order`) without changing any state
+    pub fn quote_market_order(tp_hash: T::Hash, otype: OrderType, price: T::Price, sell_amount: T::Balance)
+        -> result::Result<OrderQuote<T::Price, T::Balance>, dispatch::DispatchError> {
+        let tp = Self::trade_pair(tp_hash).ok_or(Error::<T>::NoMatchingTradePair)?;
+
+        Self::ensure_bounds(price, sell_amount)?;
+        let buy_amount = Self::counterparty_amount_floor(otype, price, sell_amount, tp.price_factor())?;
+
+        let order = LimitOrder::new(tp.base, tp.quote, Default::default(), price, sell_amount, buy_amount, otype);
+
+        Self::simulate_order(&tp, order, true)
+    }
+
+    /// The price levels which an order of `otype` would match, best first, with the amount the maker orders
+    /// of the level still buy, which is in the token the order sells
+    pub fn market_levels(tp_hash: T::Hash, otype: OrderType) -> Vec<(T::Price, T::Balance)> {
+        Self::iter_market_levels(tp_hash, otype).collect()
+    }
+
+    /// The first `max_levels` of `market_levels`
+    pub fn top_market_levels(tp_hash: T::Hash, otype: OrderType, max_levels: usize) -> Vec<(T::Price, T::Balance)> {
+        Self::iter_market_levels(tp_hash, otype).take(max_levels).collect()
+    }
+
+    /// The levels of `market_levels`, each read from the storage only once the previous one is consumed
+    pub fn iter_market_levels(tp_hash: T::Hash, otype: OrderType) -> impl Iterator<Item = (T::Price, T::Balance)> {
+        let end_item_price = match otype {
+            OrderType::Buy => Some(T::Price::max_value()),
+            OrderType::Sell => Some(T::Price::min_value()),
+        };
+
+        let mut item = <LinkedItemList<T>>::get((tp_hash, None::<T::Price>));
+        sp_std::iter::from_fn(move || {
+            let current = item.take()?;
+            let item_price = match Self::next_match_price(&current, !otype) {
+                Some(item_price) if Some(item_price) != end_item_price => item_price,
+                _ => return None,
+            };
+
+            item = <LinkedItemList<T>>::get((tp_hash, Some(item_price)));
+            item.as_ref().map(|next| (item_price, next.buy_amount))
+        })
+    }
+
+    /// The hashes of the market orders at the price, in time priority
+    pub fn market_orders_at(tp_hash: T::Hash, price: T::Price) -> Vec<T::Hash> {
+        <LinkedItemList<T>>::get((tp_hash, Some(price))).map(|item| item.orders).unwrap_or_default()
+    }
+
+    fn order_completed(order: &LimitOrder<T>, market: bool) -> bool {
+        if market {
+            order.remained_sell_amount == Zero::zero()
+        } else {
+            order.remained_buy_amount == Zero::zero()
+        }
+    }
+
+    fn simulate_order(tp: &TradePair<T>, mut order: LimitOrder<T>, market: bool)
+        -> result::Result<OrderQuote<T::Price, T::Balance>, dispatch::DispatchError> {
+        Self::ensure_continuous_matching(&tp)?;
+        T::Currency::ensure_active(tp.base)?;
+        T::Currency::ensure_active(tp.quote)?;
+
+        let tp_hash = tp.hash;
+        let otype = order.otype;
+        let price = order.price;
+
+        let end_item_price = match otype {
+            OrderType::Buy => Some(T::Price::max_value()),
+            OrderType::Sell => Some(T::Price::min_value()),
+        };
+
+        let mut levels = Vec::new();
+        let mut base_amount: T::Balance = Zero::zero();
+        let mut quote_amount: T::Balance = Zero::zero();
+
+        let mut item = <LinkedItemList<T>>::get((tp_hash, None::<T::Price>));
+        let mut dust = false;
+        while let Some(current) = item {
+            if dust || Self::order_completed(&order, market) {
+                break;
+            }
+
+            let item_price = Self::next_match_price(&current, !otype);
+            if item_price == end_item_price {
+                break;
+            }
+
+            let item_price = item_price.ok_or(Error::<T>::OrderMatchGetPriceError)?;
+            if !Self::price_matched(price, otype, item_price) {
+                break;
+            }
+
+            let next = <LinkedItemList<T>>::get((tp_hash, Some(item_price))).ok_or(Error::<T>::OrderMatchGetLinkedListItemError)?;
+            let mut level = OrderQuoteLevel {
+                price: item_price,
+                base_amount: Zero::zero(),
+                quote_amount: Zero::zero(),
+                orders: 0,
+            };
+
+            // the orders which can't be filled are canceled by the matching once reached
+            let makers = next.orders.iter()
+                .map(|o| Self::order(o).ok_or(Error::<T>::OrderMatchGetOrderError))
+                .collect::<result::Result<Vec<_>, _>>()?
+                .into_iter()
+                .filter(|o| Self::is_fillable(tp, o))
+                .collect::<Vec<_>>();
+
+            let level_match = Self::match_level(tp, &makers, &order, market)?;
+            dust = level_match.dust;
+
+            for (_, base_qty, quote_qty) in level_match.fills {
+                let (give_qty, have_qty) = Self::taker_fill_amounts(otype, base_qty, quote_qty);
+                Self::fill_taker(&mut order, give_qty, have_qty, market)?;
+
+                level.base_amount = level.base_amount + base_qty;
+                level.quote_amount = level.quote_amount + quote_qty;
+                level.orders += 1;
+            }
+
+            if level.orders > 0 {
+                base_amount = base_amount + level.base_amount;
+                quote_amount = quote_amount + level.quote_amount;
+                levels.push(level);
+            }
+
+            item = Some(next);
+        }
+
+        let average_price = if quote_amount > Zero::zero() {
+            let average_price = U256::from(Self::into_128(base_amount)?) * U256::from(tp.price_factor())
+                / U256::from(Self::into_128(quote_amount)?);
+            ensure!(average_price <= U256::from(u128::max_value()), Error::<T>::NumberCastError);
+            Some(T::Price::from(average_price.low_u128()))
+        } else {
+            None
+        };
+
+        let filled = Self::order_completed(&order, market);
+        if filled {
+            order.remained_sell_amount = Zero::zero();
+            order.remained_buy_amount = Zero::zero();
+        }
+
+        Ok(OrderQuote {
+            levels,
+            base_amount,
+            quote_amount,
+            average_price,
+            remained_sell_amount: order.remained_sell_amount,
+            remained_buy_amount: order.remained_buy_amount,
+            filled,
+        })
+    }
+
+    /// The 24h ticker of the trade pair
+    pub fn ticker(tp_hash: T::Hash) -> Option<Ticker<T::Hash, T::Price, T::Balance>> {
+        let tp = Self::trade_pair(tp_hash)?;
+
+        let price_change_bps = match (tp.latest_matched_price, tp.one_day_open_price) {
+            (Some(last), Some(open)) if open > Zero::zero() => {
+                let last: u128 = last.into();
+                let open: u128 = open.into();
+                let change = if last >= open {
+                    U256::from(last - open) * U256::from(10_000u64) / U256::from(open)
+                } else {
+                    U256::from(open - last) * U256::from(10_000u64) / U256::from(open)
+                };
+                let change = if change > U256::from(i64::max_value() as u64) { i64::max_value() } else { change.low_u64() as i64 };
+                Some(if last >= open { change } else { -change })
+            },
+            _ => None,
+        };
+
+        let base_volume: u128 = Self::into_128(tp.one_day_base_volume).ok()?;
+        let quote_volume: u128 = Self::into_128(tp.one_day_trade_volume).ok()?;
+        let weighted_avg_price = if quote_volume > 0 {
+            let vwap = U256::from(base_volume) * U256::from(tp.price_factor()) / U256::from(quote_volume);
+            if vwap > U256::from(u128::max_value()) { None } else { Some(T::Price::from(vwap.low_u128())) }
+        } else {
+            None
+        };
+
+        Some(Ticker {
+            trade_pair: tp.hash,
+            base: tp.base,
+            quote: tp.quote,
+            price_decimals: tp.price_decimals,
+            base_decimals: T::Currency::decimals(tp.base),
+            quote_decimals: T::Currency::decimals(tp.quote),
+            last_price: tp.latest_matched_price,
+            open_price: tp.one_day_open_price,
+            prev_close_price: tp.prev_close_price,
+            high_price: tp.one_day_highest_price,
+            low_price: tp.one_day_lowest_price,
+            price_change_bps,
+            weighted_avg_price,
+            base_volume: tp.one_day_base_volume,
+            quote_volume: tp.one_day_trade_volume,
+            trade_count: tp.one_day_trade_count,
+            buy_one_price: tp.buy_one_price,
+            sell_one_price: tp.sell_one_price,
+        })
+    }
+
+    /// The 24h tickers of all the trade pairs
+    pub fn tickers() -> Vec<Ticker<T::Hash, T::Price, T::Balance>> {
+        (0 .. Self::trade_pair_index())
+            .filter_map(|index| Self::trade_pair_hash_by_index(index))
+            .filter_map(|tp_hash| Self::ticker(tp_hash))
+            .collect()
+    }
+
+    /// Page through all the opened orders of the account, `count` orders starting from `start`
+    pub fn owned_opened_orders_page(account_id: T::AccountId, start: u64, count: u64) -> Vec<T::Hash> {
+        let end = Self::owned_opened_orders_index(&account_id).min(start.saturating_add(count));
+        (start..end).filter_map(|i| Self::owned_opened_order((account_id.clone(), i))).collect()
+    }
+
+    /// Page through all the opened orders of the account in the trade pair, `count` orders starting from `start`
+    pub fn owned_tp_opened_orders_page(account_id: T::AccountId, tp_hash: T::Hash, start: u64, count: u64) -> Vec<T::Hash> {
+        let end = Self::owned_tp_all_opened_orders_index((account_id.clone(), tp_hash)).min(start.saturating_add(count));
+        (start..end).filter_map(|i| Self::owned_tp_all_opened_order((account_id.clone(), tp_hash, i))).collect()
+    }
+
+    /// Page through the trades of the order, `count` trades starting from `start`. The trades pruned from the
+    /// archive while the order is still open are skipped
+    pub fn order_trades_page(order_hash: T::Hash, start: u64, count: u64) -> Vec<T::Hash> {
+        let end = Self::order_owned_trades_index(order_hash).min(start.saturating_add(count));
+        (start..end).filter_map(|i| Self::order_owned_trades((order_hash, i)))
+            .filter(|&trade_hash| <Trades<T>>::contains_key(trade_hash))
+            .collect()
+    }
+
     fn debug_log_market(tp_hash: T::Hash) {
         if_std! {
             let mut item = <OrderLinkedItemList<T>>::read_bottom(tp_hash);
@@ -55,10 +55,19 @@ impl frame_system::Trait for Test {
 
 parameter_types! {
 	pub const ExistentialDeposit: u64 = 1;
-	pub const PriceFactor: u128 = 100_000_000;
+	pub const DefaultPriceDecimals: u8 = 8;
+	pub const MaxPriceDecimals: u8 = 18;
+	pub const MaxPreOpenBlocks: u32 = 10;
+	pub const MaxPendingOrders: u32 = 10;
 	pub const BlocksPerDay: u32 = 10;
 	pub const OpenedOrdersArrayCap: u8 = 20;
 	pub const ClosedOrdersArrayCap: u8 = 100;
+	pub const ArchiveRetentionBlocks: u32 = 20;
+	pub const ArchivePruneBatchSize: u32 = 5;
+	pub const OracleObservationsCap: u32 = 5;
+	pub const OracleMaxAge: u32 = 20;
+	pub const NativeCurrencyId: H256 = H256([0xff; 32]);
+	pub const NativeDecimals: u8 = 12;
 }
 
 impl pallet_balances::Trait for Test {
@@ -71,11 +80,19 @@ impl pallet_balances::Trait for Test {
 
 impl Trait for Test {
 	type Event = ();
+	type Currency = token::Currencies<Test, NativeCurrencyId, NativeDecimals>;
 	type Price = u128;
-	type PriceFactor = PriceFactor;
+	type DefaultPriceDecimals = DefaultPriceDecimals;
+	type MaxPriceDecimals = MaxPriceDecimals;
+	type MaxPreOpenBlocks = MaxPreOpenBlocks;
+	type MaxPendingOrders = MaxPendingOrders;
 	type BlocksPerDay = BlocksPerDay;
 	type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
 	type ClosedOrdersArrayCap = ClosedOrdersArrayCap;
+	type ArchiveRetentionBlocks = ArchiveRetentionBlocks;
+	type ArchivePruneBatchSize = ArchivePruneBatchSize;
+	type OracleObservationsCap = OracleObservationsCap;
+	type OracleMaxAge = OracleMaxAge;
 }
 
 impl token::Trait for Test {
@@ -0,0 +1,236 @@
+use crate::Trait;
+use codec::Encode;
+use frame_support::{impl_outer_origin, parameter_types, ensure, dispatch, storage::unhashed, weights::Weight};
+use sp_core::H256;
+use sp_runtime::{
+	Perbill,
+	testing::Header,
+	traits::{BlakeTwo256, IdentityLookup},
+};
+use sp_std::prelude::Vec;
+use token::{MultiCurrency, MultiReservableCurrency, ReserveId};
+
+use system as frame_system;
+use balances as pallet_balances;
+
+impl_outer_origin! {
+	pub enum Origin for LedgerTest  where system = frame_system {}
+}
+
+// A mock runtime whose trade module keeps the balances in `MockLedger` instead of the token module
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LedgerTest;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+
+impl frame_system::Trait for LedgerTest {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type DbWeight = ();
+	type BlockExecutionWeight = ();
+	type ExtrinsicBaseWeight = ();
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type AccountData = pallet_balances::AccountData<u128>;
+	type OnNewAccount = ();
+	type OnKilledAccount = ();
+}
+
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const DefaultPriceDecimals: u8 = 8;
+	pub const MaxPriceDecimals: u8 = 18;
+	pub const MaxPreOpenBlocks: u32 = 10;
+	pub const MaxPendingOrders: u32 = 10;
+	pub const BlocksPerDay: u32 = 10;
+	pub const OpenedOrdersArrayCap: u8 = 20;
+	pub const ClosedOrdersArrayCap: u8 = 100;
+	pub const ArchiveRetentionBlocks: u32 = 20;
+	pub const ArchivePruneBatchSize: u32 = 5;
+	pub const OracleObservationsCap: u32 = 5;
+	pub const OracleMaxAge: u32 = 20;
+}
+
+impl pallet_balances::Trait for LedgerTest {
+	type Balance = u128;
+	type DustRemoval = ();
+	type Event = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type AccountStore = System;
+}
+
+impl Trait for LedgerTest {
+	type Event = ();
+	type Currency = MockLedger;
+	type Price = u128;
+	type DefaultPriceDecimals = DefaultPriceDecimals;
+	type MaxPriceDecimals = MaxPriceDecimals;
+	type MaxPreOpenBlocks = MaxPreOpenBlocks;
+	type MaxPendingOrders = MaxPendingOrders;
+	type BlocksPerDay = BlocksPerDay;
+	type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
+	type ClosedOrdersArrayCap = ClosedOrdersArrayCap;
+	type ArchiveRetentionBlocks = ArchiveRetentionBlocks;
+	type ArchivePruneBatchSize = ArchivePruneBatchSize;
+	type OracleObservationsCap = OracleObservationsCap;
+	type OracleMaxAge = OracleMaxAge;
+}
+
+type System = frame_system::Module<LedgerTest>;
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	frame_system::GenesisConfig::default()
+		.build_storage::<LedgerTest>()
+		.unwrap()
+		.into()
+}
+
+/// The balances of the currencies listed by `add_currency`, kept in the test storage
+pub struct MockLedger;
+
+impl MockLedger {
+	fn key(item: &[u8], currency_id: H256, who: u64) -> Vec<u8> {
+		(b"MockLedger", item, currency_id, who).encode()
+	}
+
+	fn get(item: &[u8], currency_id: H256, who: u64) -> u128 {
+		unhashed::get_or_default(&Self::key(item, currency_id, who))
+	}
+
+	fn set(item: &[u8], currency_id: H256, who: u64, amount: u128) {
+		unhashed::put(&Self::key(item, currency_id, who), &amount);
+	}
+
+	fn named_key(currency_id: H256, id: ReserveId<H256>, who: u64) -> Vec<u8> {
+		(b"MockLedger", &b"named"[..], currency_id, who, id).encode()
+	}
+
+	pub fn add_currency(currency_id: H256, owner: u64) {
+		unhashed::put(&Self::key(b"owner", currency_id, 0), &owner);
+	}
+
+	pub fn set_free_balance(currency_id: H256, who: u64, amount: u128) {
+		Self::set(b"free", currency_id, who, amount);
+	}
+}
+
+impl MultiCurrency<u64> for MockLedger {
+	type CurrencyId = H256;
+	type Balance = u128;
+
+	fn exists(currency_id: H256) -> bool {
+		Self::owner(currency_id).is_some()
+	}
+
+	fn owner(currency_id: H256) -> Option<u64> {
+		unhashed::get(&Self::key(b"owner", currency_id, 0))
+	}
+
+	fn market_admin(currency_id: H256) -> Option<u64> {
+		Self::owner(currency_id)
+	}
+
+	fn decimals(_currency_id: H256) -> u8 {
+		0
+	}
+
+	fn currency_by_symbol(_symbol: Vec<u8>) -> Option<H256> {
+		None
+	}
+
+	fn total_balance(currency_id: H256, who: &u64) -> u128 {
+		Self::get(b"free", currency_id, *who) + Self::get(b"reserved", currency_id, *who)
+	}
+
+	fn free_balance(currency_id: H256, who: &u64) -> u128 {
+		Self::get(b"free", currency_id, *who)
+	}
+
+	fn ensure_can_withdraw(currency_id: H256, who: &u64, amount: u128) -> dispatch::DispatchResult {
+		Self::ensure_active(currency_id)?;
+		ensure!(Self::free_balance(currency_id, who) >= amount, "free balance is not enough");
+
+		Ok(())
+	}
+
+	fn ensure_active(currency_id: H256) -> dispatch::DispatchResult {
+		ensure!(Self::exists(currency_id), "no such currency");
+
+		Ok(())
+	}
+
+	fn ensure_transferable(currency_id: H256, _who: &u64) -> dispatch::DispatchResult {
+		Self::ensure_active(currency_id)
+	}
+
+	fn ensure_can_deposit(currency_id: H256, _who: &u64) -> dispatch::DispatchResult {
+		Self::ensure_active(currency_id)
+	}
+
+	fn transfer(currency_id: H256, from: &u64, to: &u64, amount: u128) -> dispatch::DispatchResult {
+		Self::ensure_can_withdraw(currency_id, from, amount)?;
+
+		Self::set(b"free", currency_id, *from, Self::free_balance(currency_id, from) - amount);
+		Self::set(b"free", currency_id, *to, Self::free_balance(currency_id, to) + amount);
+
+		Ok(())
+	}
+}
+
+impl MultiReservableCurrency<u64> for MockLedger {
+	type ReserveIdentifier = ReserveId<H256>;
+
+	fn reserved_balance(currency_id: H256, who: &u64) -> u128 {
+		Self::get(b"reserved", currency_id, *who)
+	}
+
+	fn named_reserve(currency_id: H256, id: ReserveId<H256>, who: &u64) -> u128 {
+		unhashed::get_or_default(&Self::named_key(currency_id, id, *who))
+	}
+
+	fn reserve(currency_id: H256, id: ReserveId<H256>, who: &u64, amount: u128) -> dispatch::DispatchResult {
+		Self::ensure_can_withdraw(currency_id, who, amount)?;
+
+		Self::set(b"free", currency_id, *who, Self::free_balance(currency_id, who) - amount);
+		Self::set(b"reserved", currency_id, *who, Self::reserved_balance(currency_id, who) + amount);
+		unhashed::put(&Self::named_key(currency_id, id, *who), &(Self::named_reserve(currency_id, id, who) + amount));
+
+		Ok(())
+	}
+
+	fn unreserve(currency_id: H256, id: ReserveId<H256>, who: &u64, amount: u128) -> dispatch::DispatchResult {
+		ensure!(Self::named_reserve(currency_id, id, who) >= amount, "named reserve is not enough");
+
+		Self::set(b"free", currency_id, *who, Self::free_balance(currency_id, who) + amount);
+		Self::set(b"reserved", currency_id, *who, Self::reserved_balance(currency_id, who) - amount);
+		unhashed::put(&Self::named_key(currency_id, id, *who), &(Self::named_reserve(currency_id, id, who) - amount));
+
+		Ok(())
+	}
+
+	fn repatriate_reserved(currency_id: H256, id: ReserveId<H256>, from: &u64, to: &u64, amount: u128) -> dispatch::DispatchResult {
+		Self::unreserve(currency_id, id, from, amount)?;
+
+		Self::transfer(currency_id, from, to, amount)
+	}
+
+	fn migrate_legacy_reserves() -> Weight {
+		0
+	}
+}
@@ -1,7 +1,7 @@
 use super::*;
 
 use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize}};
+use frame_support::{assert_ok, assert_err, traits::{OnFinalize, OnInitialize, OnRuntimeUpgrade, Currency, ReservableCurrency}};
 use sp_core::H256;
 
 type System = system::Module<Test>;
@@ -91,7 +91,7 @@ fn output_order(tp_hash: <Test as system::Trait>::Hash) {
 
 	println!("[Trade Pair Data]");
 	let tp = TradeModule::trade_pair(tp_hash).unwrap();
-	println!("latest matched price: {:?}", tp.latest_matched_price);
+	println!("buy one: {:?}, sell one: {:?}, latest matched price: {:?}", tp.buy_one_price, tp.sell_one_price, tp.latest_matched_price);
 
 	println!();
 }
@@ -1714,10 +1714,10 @@ fn order_match_calculation_test_case() {
 
 		assert_eq!(TokenModule::balance_of((alice, quote)), 4 + 9993);
 		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 1 - 2499);
-		assert_eq!(TokenModule::freezed_balance_of((alice, base)), 1);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 1);
 		assert_eq!(TokenModule::balance_of((bob, base)), 1 + 2499);
 		assert_eq!(TokenModule::balance_of((bob, quote)), 10000000 - 4 - 9993);
-		assert_eq!(TokenModule::freezed_balance_of((bob, base)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((bob, base)), 0);
 	});
 }
 
@@ -1804,10 +1804,10 @@ fn trade_pair_bucket_test_case() {
 
 		assert_eq!(TokenModule::balance_of((alice, quote)), 4 + 9993);
 		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 1 - 2499);
-		assert_eq!(TokenModule::freezed_balance_of((alice, base)), 1);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 1);
 		assert_eq!(TokenModule::balance_of((bob, base)), 1 + 2499);
 		assert_eq!(TokenModule::balance_of((bob, quote)), 10000000 - 4 - 9993);
-		assert_eq!(TokenModule::freezed_balance_of((bob, base)), 0);
+		assert_eq!(TokenModule::reserved_balance_of((bob, base)), 0);
 
 		for i in 7..11 {
 			run_to_block(i);
@@ -1874,7 +1874,7 @@ fn calculate_ex_amount() {
 			status: OrderStatus::Created,
 		};
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 1026);
 		assert_eq!(result.1, 300);
 
@@ -1906,7 +1906,7 @@ fn calculate_ex_amount() {
 			status: OrderStatus::PartialFilled,
 		};
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 498);
 		assert_eq!(result.1, 134);
 
@@ -1938,38 +1938,38 @@ fn calculate_ex_amount() {
 			status: OrderStatus::Created,
 		};
 
-		let result = TradeModule::calculate_ex_amount(&order1, &order2).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order1, &order2, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
 		order2.price = TradeModule::from_128(25_010_000).unwrap();
-		let result = TradeModule::calculate_ex_amount(&order1, &order2).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order1, &order2, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
 		order2.price = TradeModule::from_128(33_000_000).unwrap();
-		let result = TradeModule::calculate_ex_amount(&order1, &order2).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order1, &order2, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
 		order2.price = TradeModule::from_128(35_000_000).unwrap();
-		let result = TradeModule::calculate_ex_amount(&order1, &order2).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order1, &order2, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 4);
 
-		let result = TradeModule::calculate_ex_amount(&order2, &order1).unwrap();
+		let result = TradeModule::calculate_ex_amount(&order2, &order1, 100_000_000).unwrap();
 		assert_eq!(result.0, 1);
 		assert_eq!(result.1, 3);
 	});
@@ -1981,27 +1981,27 @@ fn ensure_amount_zero_digits_test_case() {
 		let price = TradeModule::from_128(25_010_000).unwrap();
 		let amount = TradeModule::from_128(2501).unwrap();
 		let otype = OrderType::Buy;
-		assert_ok!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount), 10000u128);
+		assert_ok!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount, 100_000_000), 10000u128);
 
 		let price = TradeModule::from_128(25_010_000).unwrap(); // 0.2501
 		let amount = TradeModule::from_128(2500).unwrap();
 		let otype = OrderType::Buy;
-		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount), Error::<Test>::BoundsCheckFailed);
+		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount, 100_000_000), Error::<Test>::BoundsCheckFailed);
 
 		let price = TradeModule::from_128(25_000_000).unwrap(); // 0.25
 		let amount = TradeModule::from_128(24).unwrap();
 		let otype = OrderType::Sell;
-		assert_ok!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount), 6u128);
+		assert_ok!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount, 100_000_000), 6u128);
 
 		let price = TradeModule::from_128(25_000_000).unwrap(); // 0.25
 		let amount = TradeModule::from_128(21).unwrap();
 		let otype = OrderType::Sell;
-		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount), Error::<Test>::BoundsCheckFailed);
+		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount, 100_000_000), Error::<Test>::BoundsCheckFailed);
 
 		let price = TradeModule::from_128(200_000_000).unwrap(); // 2.0
 		let amount = TradeModule::from_128(u128::max_value() - 1).unwrap();
 		let otype = OrderType::Sell;
-		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount), Error::<Test>::BoundsCheckFailed);
+		assert_err!(TradeModule::ensure_counterparty_amount_bounds(otype, price, amount, 100_000_000), Error::<Test>::BoundsCheckFailed);
 	});
 }
 
@@ -2013,35 +2013,1592 @@ fn price_as_vec_u8_to_x_by_100m_test_case() {
 		let _price_v1 = 3.11122233f64;
 		let price_v1_vec_u8: [u8; 8] = [183, 122, 111, 136, 200, 227, 8, 64];
 		let price_v2 = 311122233u128;
-		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()), price_v2);
+		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000), price_v2);
 
 		let _price_v1 = 123.00000000f64;
 		let price_v1_vec_u8: [u8; 8] = [0, 0, 0, 0, 0, 192, 94, 64];
 		let price_v2 = 12_300_000_000;
-		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()), price_v2);
+		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000), price_v2);
 
 		let _price_v1 = 999.6789f64;
 		let price_v1_vec_u8: [u8; 8] = [9, 138, 31, 99, 110, 61, 143, 64];
 		let price_v2 = 99_967_890_000u128;
-		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()), price_v2);
+		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000), price_v2);
 
 		let _price_v1 = 0.00000001f64;
 		let price_v1_vec_u8: [u8; 8] = [58, 140, 48, 226, 142, 121, 69, 62];
 		let price_v2 = 1u128;
-		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()), price_v2);
+		assert_ok!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000), price_v2);
 
 		let price_v1_vec_u8: [u8; 7] = [255, 142, 214, 136, 200, 227, 8];
-		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()),
+		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000),
                 Error::<Test>::PriceLengthCheckFailed);
 
 		let _price_v1 = 3.111222333f64;
 		let price_v1_vec_u8: [u8; 8] = [255, 142, 214, 136, 200, 227, 8, 64];
-		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()),
+		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000),
                 Error::<Test>::PriceLengthCheckFailed);
 
 		let _price_v1 = 3.000011112222f64;
 		let price_v1_vec_u8: [u8; 8] = [101, 10, 117, 211, 5, 0, 8, 64];
-		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec()),
+		assert_err!(TradeModule::price_as_vec_u8_to_x_by_100m(price_v1_vec_u8.to_vec(), 100_000_000),
                 Error::<Test>::PriceLengthCheckFailed);
 	});
 }
+
+#[test]
+fn opened_orders_index_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		let mut orders = Vec::new();
+		for i in 0..25 {
+			assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000 * (i + 1), 100));
+			orders.push(TradeModule::owned_order((bob, i as u64)).unwrap());
+		}
+
+		// the recent view is capped, the full index is not
+		assert!(TradeModule::owned_tp_opened_orders((bob, tp_hash)).unwrap().len() < 20);
+		assert_eq!(TradeModule::owned_opened_orders_index(bob), 25);
+		assert_eq!(TradeModule::owned_tp_all_opened_orders_index((bob, tp_hash)), 25);
+		assert_eq!(TradeModule::owned_opened_orders_page(bob, 0, 100), orders);
+		assert_eq!(TradeModule::owned_tp_opened_orders_page(bob, tp_hash, 20, 10), orders[20..].to_vec());
+		assert_eq!(TradeModule::owned_tp_opened_orders_page(bob, tp_hash, 30, 10), Vec::<H256>::new());
+
+		// cancel the first order, the last order takes its slot
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), orders[0]));
+		assert_eq!(TradeModule::owned_opened_orders_index(bob), 24);
+		assert_eq!(TradeModule::owned_tp_all_opened_orders_index((bob, tp_hash)), 24);
+		assert_eq!(TradeModule::owned_opened_order((bob, 0)), Some(orders[24]));
+		assert_eq!(TradeModule::owned_opened_order((bob, 24)), None);
+		assert_eq!(TradeModule::owned_tp_all_opened_order((bob, tp_hash, 0)), Some(orders[24]));
+		assert_eq!(TradeModule::opened_order_position(orders[24]), Some((0, 0)));
+		assert_eq!(TradeModule::opened_order_position(orders[0]), None);
+
+		// fill the second order, the taker order never stays in the index
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 200_000_000, 200));
+		assert_eq!(TradeModule::order(orders[1]).unwrap().status, OrderStatus::Filled);
+		assert_eq!(TradeModule::owned_opened_orders_index(alice), 0);
+		assert_eq!(TradeModule::owned_opened_orders_index(bob), 23);
+		assert_eq!(TradeModule::owned_opened_order((bob, 1)), Some(orders[23]));
+		assert_eq!(TradeModule::opened_order_position(orders[23]), Some((1, 1)));
+		assert_eq!(TradeModule::owned_opened_orders_page(bob, 0, 100).len(), 23);
+
+		// the orders opened before the index are listed by the migration
+		let mut opened = TradeModule::owned_opened_orders_page(bob, 0, 100);
+		for (index, order_hash) in opened.iter().enumerate() {
+			OwnedOpenedOrders::<Test>::remove((bob, index as u64));
+			OwnedTPAllOpenedOrders::<Test>::remove((bob, tp_hash, index as u64));
+			OpenedOrderPositions::<Test>::remove(order_hash);
+		}
+		OwnedOpenedOrdersIndex::<Test>::remove(bob);
+		OwnedTPAllOpenedOrdersIndex::<Test>::remove((bob, tp_hash));
+		assert_eq!(TradeModule::owned_opened_orders_page(bob, 0, 100), Vec::<H256>::new());
+
+		TradeModule::on_runtime_upgrade();
+		opened.sort();
+		let mut migrated = TradeModule::owned_opened_orders_page(bob, 0, 100);
+		migrated.sort();
+		assert_eq!(migrated, opened);
+		let mut migrated = TradeModule::owned_tp_opened_orders_page(bob, tp_hash, 0, 100);
+		migrated.sort();
+		assert_eq!(migrated, opened);
+		assert_eq!(TradeModule::owned_opened_orders_index(alice), 0);
+	});
+}
+
+#[test]
+fn archive_prune_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 100));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		let order101_hash = TradeModule::owned_order((alice, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 50));
+		let order2_hash = TradeModule::owned_order((bob, 1)).unwrap();
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order2_hash));
+
+		let trade_hash = TradeModule::trade_pair_owned_trades((tp_hash, 0)).unwrap();
+
+		// taker order, maker order, trade, canceled order
+		assert_eq!(TradeModule::archive_items_index(1), 4);
+		assert_eq!(TradeModule::archive_item((1, 0)), Some(ArchiveItem::Order(order101_hash)));
+		assert_eq!(TradeModule::archive_item((1, 1)), Some(ArchiveItem::Order(order1_hash)));
+		assert_eq!(TradeModule::archive_item((1, 2)), Some(ArchiveItem::Trade(trade_hash, 0, vec![(alice, 0, 0), (bob, 0, 0)])));
+		assert_eq!(TradeModule::archive_item((1, 3)), Some(ArchiveItem::Order(order2_hash)));
+
+		fn hash<E: Encode>(x: &E) -> H256 {
+			x.using_encoded(<Test as system::Trait>::Hashing::hash)
+		}
+
+		let l0 = hash(&TradeModule::order(order101_hash).unwrap());
+		let l1 = hash(&TradeModule::order(order1_hash).unwrap());
+		let l2 = hash(&TradeModule::trade(trade_hash).unwrap());
+		let l3 = hash(&TradeModule::order(order2_hash).unwrap());
+		let root = hash(&(hash(&(l0, l1)), hash(&(l2, l3))));
+
+		// nothing pruned within the retention window
+		run_to_block(21);
+		assert!(TradeModule::order(order1_hash).is_some());
+		assert_eq!(TradeModule::archive_root(1), None);
+
+		// the root is kept, and the first batch is pruned, each order counts its trades
+		run_to_block(22);
+		assert_eq!(TradeModule::archive_root(1), Some((root, 4)));
+		assert_eq!(TradeModule::archive_prune_cursor(), (1, 3));
+		assert_eq!(TradeModule::order(order101_hash), None);
+		assert_eq!(TradeModule::order(order1_hash), None);
+		assert_eq!(TradeModule::owned_order((bob, 0)), None);
+		assert_eq!(TradeModule::trade_pair_owned_order((tp_hash, 0)), None);
+		assert_eq!(TradeModule::order_owned_trades((order1_hash, 0)), None);
+		assert_eq!(TradeModule::trade(trade_hash), None);
+		assert_eq!(TradeModule::owned_trades((alice, 0)), None);
+		assert_eq!(TradeModule::owned_tp_trades((bob, tp_hash, 0)), None);
+		assert_eq!(TradeModule::trade_pair_owned_trades((tp_hash, 0)), None);
+		assert!(TradeModule::order(order2_hash).is_some());
+
+		run_to_block(23);
+		assert_eq!(TradeModule::archive_prune_cursor(), (3, 0));
+		assert_eq!(TradeModule::order(order2_hash), None);
+		assert_eq!(TradeModule::archive_items_index(1), 0);
+		assert_eq!(TradeModule::archive_merkle_frontier(1), Vec::<H256>::new());
+		assert_eq!(TradeModule::archive_root(1), Some((root, 4)));
+
+		// the counters are not reused
+		assert_eq!(TradeModule::owned_orders_index(bob), 2);
+	});
+}
+
+#[test]
+fn archive_prune_indexes_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		// the sell order stays open after the trade
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 100));
+		let bob_order = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 40));
+		let alice_order = TradeModule::owned_order((alice, 0)).unwrap();
+
+		let trade_hash = TradeModule::trade_pair_owned_trades((tp_hash, 0)).unwrap();
+		assert_eq!(TradeModule::order_trades_page(bob_order, 0, 10), vec![trade_hash]);
+		assert_eq!(TradeModule::owned_tp_closed_orders((alice, tp_hash)), Some(vec![alice_order]));
+
+		run_to_block(23);
+		assert_eq!(TradeModule::order(alice_order), None);
+		assert_eq!(TradeModule::trade(trade_hash), None);
+
+		// the pruned order leaves the closed orders, the pruned trade is skipped by the open order
+		assert_eq!(TradeModule::owned_tp_closed_orders((alice, tp_hash)), None);
+		assert!(TradeModule::order(bob_order).is_some());
+		assert_eq!(TradeModule::order_owned_trades((bob_order, 0)), Some(trade_hash));
+		assert_eq!(TradeModule::order_trades_page(bob_order, 0, 10), vec![]);
+	});
+}
+
+#[test]
+fn archive_prune_order_trades_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+
+		for _ in 0..7 {
+			assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		}
+
+		// the sell order is filled by 7 trades, more than the batch size
+		run_to_block(2);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 700));
+		let bob_order = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_eq!(TradeModule::order_owned_trades_index(bob_order), 7);
+
+		// 6 buy orders and their trades, then the sell order, the last buy order and its trade
+		assert_eq!(TradeModule::archive_items_index(2), 15);
+		assert_eq!(TradeModule::archive_item((2, 12)), Some(ArchiveItem::Order(bob_order)));
+
+		run_to_block(23);
+		assert_eq!(TradeModule::archive_prune_cursor(), (2, 3));
+
+		run_to_block(26);
+		assert_eq!(TradeModule::archive_prune_cursor(), (2, 12));
+		assert_eq!(TradeModule::order_owned_trades_index(bob_order), 5);
+		assert!(TradeModule::order_owned_trades((bob_order, 4)).is_some());
+		assert_eq!(TradeModule::order_owned_trades((bob_order, 5)), None);
+
+		// the trades are gone, the order waits for the next batch
+		run_to_block(27);
+		assert_eq!(TradeModule::archive_prune_cursor(), (2, 12));
+		assert_eq!(TradeModule::order_owned_trades_index(bob_order), 0);
+		assert_eq!(TradeModule::order_owned_trades((bob_order, 0)), None);
+		assert!(TradeModule::order(bob_order).is_some());
+		assert!(TradeModule::archive_item((2, 12)).is_some());
+
+		run_to_block(28);
+		assert_eq!(TradeModule::archive_prune_cursor(), (3, 0));
+		assert_eq!(TradeModule::order(bob_order), None);
+		assert_eq!(TradeModule::owned_order((bob, 0)), None);
+		assert_eq!(TradeModule::archive_item((2, 12)), None);
+		assert_eq!(TradeModule::archive_items_index(2), 0);
+	});
+}
+
+#[test]
+fn decimal_price_to_price_test_case() {
+	new_test_ext().execute_with(|| {
+		fn text(t: &[u8]) -> DecimalPrice {
+			DecimalPrice::Text(t.to_vec())
+		}
+
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"3.11122233"), Rounding::Exact, 100_000_000), 311122233u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"123"), Rounding::Exact, 100_000_000), 12_300_000_000u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"999.6789"), Rounding::Exact, 100_000_000), 99_967_890_000u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"0.00000001"), Rounding::Exact, 100_000_000), 1u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b".5"), Rounding::Exact, 100_000_000), 50_000_000u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"1.50000000000000000000000000000000000000000000000000"), Rounding::Exact, 100_000_000), 150_000_000u128);
+
+		assert_err!(TradeModule::decimal_price_to_price(text(b"3.111222333"), Rounding::Exact, 100_000_000),
+			Error::<Test>::DecimalPricePrecisionExceeded);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"3.111222333"), Rounding::Down, 100_000_000), 311122233u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"3.111222333"), Rounding::Up, 100_000_000), 311122234u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"3.111222333"), Rounding::HalfUp, 100_000_000), 311122233u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"3.111222335"), Rounding::HalfUp, 100_000_000), 311122234u128);
+		assert_ok!(TradeModule::decimal_price_to_price(text(b"0.000000001"), Rounding::Down, 100_000_000), 0u128);
+
+		assert_err!(TradeModule::decimal_price_to_price(text(b""), Rounding::Exact, 100_000_000), Error::<Test>::DecimalPriceMalformed);
+		assert_err!(TradeModule::decimal_price_to_price(text(b"."), Rounding::Exact, 100_000_000), Error::<Test>::DecimalPriceMalformed);
+		assert_err!(TradeModule::decimal_price_to_price(text(b"1.2.3"), Rounding::Exact, 100_000_000), Error::<Test>::DecimalPriceMalformed);
+		assert_err!(TradeModule::decimal_price_to_price(text(b"-1.5"), Rounding::Exact, 100_000_000), Error::<Test>::DecimalPriceMalformed);
+		assert_err!(TradeModule::decimal_price_to_price(text(b"1e5"), Rounding::Exact, 100_000_000), Error::<Test>::DecimalPriceMalformed);
+		assert_err!(TradeModule::decimal_price_to_price(text(b"3402823669209384634633746074317682114560"), Rounding::Exact, 100_000_000),
+			Error::<Test>::DecimalPriceOverflow);
+
+		assert_ok!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(311122233, -8), Rounding::Exact, 100_000_000), 311122233u128);
+		assert_ok!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(5, 2), Rounding::Exact, 100_000_000), 50_000_000_000u128);
+		assert_ok!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(1, -100), Rounding::Up, 100_000_000), 1u128);
+		assert_err!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(1, -100), Rounding::Exact, 100_000_000),
+			Error::<Test>::DecimalPricePrecisionExceeded);
+		assert_err!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(u128::max_value(), 0), Rounding::Exact, 100_000_000),
+			Error::<Test>::DecimalPriceOverflow);
+		assert_err!(TradeModule::decimal_price_to_price(DecimalPrice::Scientific(1, 127), Rounding::Exact, 100_000_000),
+			Error::<Test>::DecimalPriceOverflow);
+	});
+}
+
+#[test]
+fn create_limit_order_with_decimal_price_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+
+		assert_ok!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(bob), base, quote, OrderType::Sell,
+			DecimalPrice::Text(b"0.25".to_vec()), Rounding::Exact, 24));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.price, 25_000_000);
+		assert_eq!(order1.buy_amount, 6);
+
+		assert_err!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(bob), base, quote, OrderType::Sell,
+			DecimalPrice::Text(b"0.250000001".to_vec()), Rounding::Exact, 24), Error::<Test>::DecimalPricePrecisionExceeded);
+		assert_err!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(bob), base, quote, OrderType::Sell,
+			DecimalPrice::Text(b"0.000000001".to_vec()), Rounding::Down, 24), Error::<Test>::BoundsCheckFailed);
+	});
+}
+
+#[test]
+fn trade_pair_price_decimals_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_err!(TradeModule::create_trade_pair_with_price_decimals(Origin::signed(alice), base, quote, 19),
+			Error::<Test>::PriceDecimalsTooLarge);
+		assert_ok!(TradeModule::create_trade_pair_with_price_decimals(Origin::signed(alice), base, quote, 2));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!(tp.price_decimals, 2);
+		assert_eq!(tp.price_factor(), 100);
+
+		assert_ok!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(bob), base, quote, OrderType::Sell,
+			DecimalPrice::Text(b"0.25".to_vec()), Rounding::Exact, 24));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.price, 25);
+		assert_eq!(order1.buy_amount, 6);
+
+		assert_err!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(bob), base, quote, OrderType::Sell,
+			DecimalPrice::Text(b"0.255".to_vec()), Rounding::Exact, 24), Error::<Test>::DecimalPricePrecisionExceeded);
+
+		assert_ok!(TradeModule::create_limit_order_with_decimal_price(Origin::signed(alice), base, quote, OrderType::Buy,
+			DecimalPrice::Text(b"0.25".to_vec()), Rounding::Exact, 6));
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.status, OrderStatus::Filled);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 24);
+	});
+}
+
+#[test]
+fn order_match_with_large_price_factor_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let supply: u128 = 1_000_000_000_000_000_000_000_000;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), supply));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), supply));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		// the fill amounts multiplied by the price factor do not fit in u128
+		assert_ok!(TradeModule::create_trade_pair_with_price_decimals(Origin::signed(alice), base, quote, 18));
+		let price: u128 = 2_000_000_000_000_000_000; // 2.0
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, price, 10_000_000_000_000_000_000_000));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, price, 10_000_000_000_000_000_000_000));
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.status, OrderStatus::PartialFilled);
+		assert_eq!(order1.remained_sell_amount, 5_000_000_000_000_000_000_000);
+		assert_eq!(order1.remained_buy_amount, 10_000_000_000_000_000_000_000);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, price, 10_000_000_000_000_000_000_000));
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.status, OrderStatus::Filled);
+
+		assert_eq!(TokenModule::balance_of((alice, base)), supply - 20_000_000_000_000_000_000_000);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 10_000_000_000_000_000_000_000);
+		assert_eq!(TokenModule::balance_of((bob, base)), 20_000_000_000_000_000_000_000);
+		assert_eq!(TokenModule::balance_of((bob, quote)), supply - 10_000_000_000_000_000_000_000);
+	});
+}
+
+#[test]
+fn migrate_trade_pairs_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair_with_price_decimals(Origin::signed(alice), base, quote, 2));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+
+		// the first layout, before the price decimals
+		let old = (tp.hash, tp.base, tp.quote, Some(5u128), 100u128, Some(6u128), Some(4u128));
+		unhashed::put_raw(&<TradePairs<Test>>::hashed_key_for(tp_hash), &old.encode());
+		assert_eq!(TradeModule::trade_pair(tp_hash), None);
+
+		TradeModule::on_runtime_upgrade();
+		assert_eq!(TradeModule::storage_version(), STORAGE_VERSION);
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!(tp.latest_matched_price, Some(5));
+		assert_eq!(tp.one_day_trade_volume, 100);
+		assert_eq!(tp.one_day_highest_price, Some(6));
+		assert_eq!(tp.one_day_lowest_price, Some(4));
+		assert_eq!(tp.price_decimals, 8);
+		assert_eq!((tp.matching_mode, tp.pre_open_until, tp.allocation_policy), (MatchingMode::Continuous, None, AllocationPolicy::Fifo));
+
+		// the layout of the version 5 lacks the pre-open phase and the allocation policy
+		assert_ok!(TradeModule::set_matching_mode(Origin::signed(alice), base, quote, MatchingMode::BatchAuction));
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		let raw = tp.encode();
+		let old = &raw[..raw.len() - (None::<u64>, AllocationPolicy::Fifo).encode().len()];
+		unhashed::put_raw(&<TradePairs<Test>>::hashed_key_for(tp_hash), old);
+		StorageVersion::put(5);
+
+		TradeModule::on_runtime_upgrade();
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap(), tp);
+
+		// an up to date trade pair is left untouched
+		TradeModule::on_runtime_upgrade();
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap(), tp);
+	});
+}
+
+#[test]
+fn top_of_book_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.buy_one_price, tp.buy_one_amount, tp.sell_one_price, tp.sell_one_amount), (None, 0, None, 0));
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 100));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 40_000_000, 40));
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.buy_one_price, tp.buy_one_amount, tp.sell_one_price, tp.sell_one_amount),
+			(Some(40_000_000), 100, Some(50_000_000), 100));
+
+		// a better ask becomes the top of book, and the previous one again after the cancel
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 45_000_000, 200));
+		let order3_hash = TradeModule::owned_order((bob, 1)).unwrap();
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.sell_one_price, tp.sell_one_amount), (Some(45_000_000), 200));
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order3_hash));
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.sell_one_price, tp.sell_one_amount), (Some(50_000_000), 100));
+
+		// partially fill the best ask
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 50_000_000, 25));
+		assert_eq!(TradeModule::order(order1_hash).unwrap().status, OrderStatus::PartialFilled);
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.buy_one_price, tp.buy_one_amount, tp.sell_one_price, tp.sell_one_amount),
+			(Some(40_000_000), 100, Some(50_000_000), 50));
+
+		// cancel the partially filled ask
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order1_hash));
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.buy_one_price, tp.buy_one_amount, tp.sell_one_price, tp.sell_one_amount),
+			(Some(40_000_000), 100, None, 0));
+	});
+}
+
+#[test]
+fn cancel_partially_filled_order_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		// two asks at the same price
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 100));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 60));
+		let order2_hash = TradeModule::owned_order((bob, 1)).unwrap();
+
+		// the first ask is filled by 50
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 50_000_000, 25));
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!((order1.remained_sell_amount, order1.remained_buy_amount), (50, 25));
+		let item = <OrderLinkedItemList<Test>>::read(tp_hash, Some(50_000_000));
+		assert_eq!((item.sell_amount, item.buy_amount), (110, 55));
+
+		// the cancel takes only the remained amounts of the first ask off the price level
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order1_hash));
+		let item = <OrderLinkedItemList<Test>>::read(tp_hash, Some(50_000_000));
+		assert_eq!(item.orders, vec![order2_hash]);
+		assert_eq!((item.sell_amount, item.buy_amount), (60, 30));
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.sell_one_price, tp.sell_one_amount), (Some(50_000_000), 60));
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order2_hash));
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!((tp.sell_one_price, tp.sell_one_amount), (None, 0));
+	});
+}
+
+#[test]
+fn ticker_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10;
+		let bob = 20;
+
+		// token1
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let token1_hash = TokenModule::owned_token((alice, 0)).unwrap();
+		let token1 = TokenModule::token(token1_hash).unwrap();
+
+		// token2
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let token2_hash = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::set_metadata(Origin::signed(bob), token2_hash, b"Token 77".to_vec(), 6, None, None));
+		let token2 = TokenModule::token(token2_hash).unwrap();
+
+		// tradepair
+		let base = token1.hash;
+		let quote = token2.hash;
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		// block 1: 1 base for 4 quote
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 25_000_000, 4));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 25_000_000, 1));
+
+		// block 3: 5 base for 10 quote
+		run_to_block(3);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 10));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 50_000_000, 5));
+
+		run_to_block(4);
+		let ticker = TradeModule::ticker(tp_hash).unwrap();
+		assert_eq!(ticker.base, base);
+		assert_eq!(ticker.quote, quote);
+		assert_eq!(ticker.price_decimals, 8);
+		assert_eq!(ticker.base_decimals, 0);
+		assert_eq!(ticker.quote_decimals, 6);
+		assert_eq!(ticker.last_price, Some(50_000_000));
+		assert_eq!(ticker.open_price, Some(25_000_000));
+		assert_eq!(ticker.prev_close_price, None);
+		assert_eq!(ticker.high_price, Some(50_000_000));
+		assert_eq!(ticker.low_price, Some(25_000_000));
+		assert_eq!(ticker.price_change_bps, Some(10_000));
+		assert_eq!(ticker.weighted_avg_price, Some(6 * 100_000_000 / 14));
+		assert_eq!(ticker.base_volume, 6);
+		assert_eq!(ticker.quote_volume, 14);
+		assert_eq!(ticker.trade_count, 2);
+		assert_eq!(TradeModule::tickers(), vec![ticker]);
+
+		// block 1 leaves the window
+		run_to_block(12);
+		let ticker = TradeModule::ticker(tp_hash).unwrap();
+		assert_eq!(ticker.last_price, Some(50_000_000));
+		assert_eq!(ticker.open_price, Some(50_000_000));
+		assert_eq!(ticker.prev_close_price, Some(25_000_000));
+		assert_eq!(ticker.price_change_bps, Some(0));
+		assert_eq!(ticker.weighted_avg_price, Some(50_000_000));
+		assert_eq!(ticker.base_volume, 5);
+		assert_eq!(ticker.quote_volume, 10);
+		assert_eq!(ticker.trade_count, 1);
+
+		// block 3 leaves the window
+		run_to_block(14);
+		let ticker = TradeModule::ticker(tp_hash).unwrap();
+		assert_eq!(ticker.last_price, Some(50_000_000));
+		assert_eq!(ticker.open_price, None);
+		assert_eq!(ticker.prev_close_price, Some(50_000_000));
+		assert_eq!(ticker.price_change_bps, None);
+		assert_eq!(ticker.weighted_avg_price, None);
+		assert_eq!(ticker.base_volume, 0);
+		assert_eq!(ticker.quote_volume, 0);
+		assert_eq!(ticker.trade_count, 0);
+
+		// block 14 takes the slot of block 4 in the ring of the open prices, and links to block 15
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 10));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 50_000_000, 5));
+		run_to_block(15);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 25_000_000, 4));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 25_000_000, 1));
+		run_to_block(16);
+		assert_eq!(TradeModule::trade_pair_trade_open_price_ring((tp_hash, 4)), Some((14, 50_000_000, Some(15))));
+		assert_eq!(TradeModule::trade_pair_trade_open_price_ring((tp_hash, 5)), Some((15, 25_000_000, None)));
+		assert_eq!(TradeModule::trade_pair_trade_open_price_blocks(tp_hash), Some((14, 15)));
+		assert_eq!(TradeModule::ticker(tp_hash).unwrap().open_price, Some(50_000_000));
+
+		// block 14 leaves the window, block 15 opens the day
+		run_to_block(24);
+		assert_eq!(TradeModule::trade_pair_trade_open_price_ring((tp_hash, 4)), None);
+		assert_eq!(TradeModule::trade_pair_trade_open_price_blocks(tp_hash), Some((15, 15)));
+		assert_eq!(TradeModule::ticker(tp_hash).unwrap().open_price, Some(25_000_000));
+
+		assert_eq!(TradeModule::ticker(H256::from_low_u64_be(0)), None);
+	});
+}
+
+#[test]
+fn price_oracle_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(1);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		assert_eq!(TradeModule::latest_price(tp_hash), None);
+		assert_err!(TradeModule::twap(tp_hash, 1), Error::<Test>::OraclePriceUnavailable);
+		assert_err!(TradeModule::twap(H256::from_low_u64_be(0), 1), Error::<Test>::NoMatchingTradePair);
+
+		// block 1: 25_000_000
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 25_000_000, 4));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 25_000_000, 1));
+
+		// block 5: 50_000_000
+		run_to_block(5);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 10));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 50_000_000, 5));
+
+		assert_eq!(TradeModule::trade_pair_price_observations(tp_hash),
+			vec![(1, U256::zero()), (5, U256::from(4 * 25_000_000u64))]);
+
+		run_to_block(9);
+		assert_eq!(TradeModule::latest_price(tp_hash), Some(50_000_000));
+		assert_ok!(TradeModule::twap(tp_hash, 4), 50_000_000);
+		assert_ok!(TradeModule::twap(tp_hash, 6), (2 * 25_000_000 + 4 * 50_000_000) / 6);
+		assert_ok!(TradeModule::twap(tp_hash, 8), (4 * 25_000_000 + 4 * 50_000_000) / 8);
+		assert_err!(TradeModule::twap(tp_hash, 9), Error::<Test>::OracleInsufficientHistory);
+		assert_err!(TradeModule::twap(tp_hash, 0), Error::<Test>::OracleInsufficientHistory);
+
+		run_to_block(26);
+		assert_err!(TradeModule::twap(tp_hash, 4), Error::<Test>::OraclePriceStale);
+	});
+}
+
+#[test]
+fn quote_order_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		let quote1 = TradeModule::quote_order(tp_hash, OrderType::Sell, 50_000_000, 10).unwrap();
+		assert_eq!(quote1, OrderQuote {
+			levels: Vec::new(),
+			base_amount: 0,
+			quote_amount: 0,
+			average_price: None,
+			remained_sell_amount: 10,
+			remained_buy_amount: 5,
+			filled: false,
+		});
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 10));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 50_000_000, 20));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 60_000_000, 10));
+
+		// filled within the best level, the unused sell amount is unfrozen
+		let quote2 = TradeModule::quote_order(tp_hash, OrderType::Buy, 60_000_000, 12).unwrap();
+		assert_eq!(quote2, OrderQuote {
+			levels: vec![OrderQuoteLevel { price: 50_000_000, base_amount: 10, quote_amount: 20, orders: 2 }],
+			base_amount: 10,
+			quote_amount: 20,
+			average_price: Some(50_000_000),
+			remained_sell_amount: 0,
+			remained_buy_amount: 0,
+			filled: true,
+		});
+
+		// the price limit stops at the first level
+		let quote3 = TradeModule::quote_order(tp_hash, OrderType::Buy, 55_000_000, 22).unwrap();
+		assert_eq!(quote3.levels, vec![OrderQuoteLevel { price: 50_000_000, base_amount: 15, quote_amount: 30, orders: 2 }]);
+		assert_eq!((quote3.remained_sell_amount, quote3.remained_buy_amount, quote3.filled), (7, 10, false));
+
+		// walk through two levels
+		let quote4 = TradeModule::quote_order(tp_hash, OrderType::Buy, 60_000_000, 24).unwrap();
+		assert_eq!(quote4, OrderQuote {
+			levels: vec![
+				OrderQuoteLevel { price: 50_000_000, base_amount: 15, quote_amount: 30, orders: 2 },
+				OrderQuoteLevel { price: 60_000_000, base_amount: 6, quote_amount: 10, orders: 1 },
+			],
+			base_amount: 21,
+			quote_amount: 40,
+			average_price: Some(52_500_000),
+			remained_sell_amount: 0,
+			remained_buy_amount: 0,
+			filled: true,
+		});
+
+		assert_err!(TradeModule::quote_order(H256::from_low_u64_be(0), OrderType::Buy, 60_000_000, 24), Error::<Test>::NoMatchingTradePair);
+
+		// nothing is changed by the quotes
+		let order1 = TradeModule::order(order1_hash).unwrap();
+		assert_eq!(order1.status, OrderStatus::Created);
+		assert_eq!(order1.remained_sell_amount, 10);
+		assert_eq!(TradeModule::owned_orders_index(alice), 0);
+
+		// the quote matches the executed order
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 60_000_000, 24));
+		assert_eq!(TokenModule::balance_of((alice, quote)), 40);
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 21);
+	});
+}
+
+#[test]
+fn market_order_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 110_000_000, 1000));
+
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![(100_000_000, 1000), (110_000_000, 1100)]);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Sell), vec![]);
+
+		// all of the sell amount is sold, the last level takes it at a rounded down amount
+		let quote1 = TradeModule::quote_market_order(tp_hash, OrderType::Buy, 110_000_000, 2000).unwrap();
+		assert_eq!(quote1, OrderQuote {
+			levels: vec![
+				OrderQuoteLevel { price: 100_000_000, base_amount: 1000, quote_amount: 1000, orders: 1 },
+				OrderQuoteLevel { price: 110_000_000, base_amount: 1000, quote_amount: 909, orders: 1 },
+			],
+			base_amount: 2000,
+			quote_amount: 1909,
+			average_price: Some(104_766_893),
+			remained_sell_amount: 0,
+			remained_buy_amount: 0,
+			filled: true,
+		});
+
+		assert_eq!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 110_000_000, 2000), Ok((2000, 1909)));
+		assert_eq!(TokenModule::balance_of((alice, quote)), 1909);
+		assert_eq!(TokenModule::free_balance_of((alice, base)), 21000000 - 2000);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![(110_000_000, 100)]);
+
+		let order1 = TradeModule::order(TradeModule::owned_order((alice, 0)).unwrap()).unwrap();
+		assert_eq!(order1.status, OrderStatus::Filled);
+		assert!(order1.is_finished());
+
+		// nothing at the price, the order is canceled instead of being added to the market
+		assert_eq!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 100_000_000, 500), Ok((0, 0)));
+		let order2 = TradeModule::order(TradeModule::owned_order((alice, 1)).unwrap()).unwrap();
+		assert_eq!(order2.status, OrderStatus::Canceled);
+		assert_eq!(order2.remained_sell_amount, 500);
+		assert_eq!(TokenModule::free_balance_of((alice, base)), 21000000 - 2000);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Sell), vec![]);
+
+		// too small to buy anything
+		assert_err!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 110_000_000, 1), Error::<Test>::BoundsCheckFailed);
+
+		// the market is emptied, the rest is unfrozen
+		assert_eq!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 110_000_000, 200), Ok((100, 91)));
+		let order3 = TradeModule::order(TradeModule::owned_order((alice, 2)).unwrap()).unwrap();
+		assert_eq!(order3.status, OrderStatus::Canceled);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 0);
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 2100);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 2000);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![]);
+
+		// the fills are returned even when the order matches one of the same account
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Sell, 100_000_000, 100));
+		assert_eq!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 100_000_000, 100), Ok((100, 100)));
+		assert_eq!(TokenModule::balance_of((alice, quote)), 2000);
+	});
+}
+
+#[test]
+fn batch_auction_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let charlie = 30;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, charlie, 10000, None));
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap().matching_mode(), MatchingMode::Continuous);
+
+		assert_err!(TradeModule::set_matching_mode(Origin::signed(charlie), base, quote, MatchingMode::BatchAuction),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_eq!(TradeModule::clearing_trade_pairs(), vec![]);
+		assert_ok!(TradeModule::set_matching_mode(Origin::signed(bob), base, quote, MatchingMode::BatchAuction));
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap().matching_mode(), MatchingMode::BatchAuction);
+		assert_eq!(TradeModule::clearing_trade_pairs(), vec![tp_hash]);
+
+		// the orders cross without being matched, they wait for the end of the block
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 110_000_000, 1100));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Buy, 110_000_000, 550));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 90_000_000, 450));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Buy, 100_000_000, 100));
+
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash).len(), 5);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![]);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Sell), vec![]);
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 0);
+
+		// a pending order is canceled without being cleared
+		let charlie_order2 = TradeModule::owned_order((charlie, 1)).unwrap();
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(charlie), charlie_order2));
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash).len(), 4);
+		assert_eq!(TokenModule::reserved_balance_of((charlie, base)), 550);
+
+		assert_err!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 110_000_000, 100), Error::<Test>::NotContinuousMatching);
+		assert_err!(TradeModule::quote_order(tp_hash, OrderType::Buy, 110_000_000, 1100), Error::<Test>::NotContinuousMatching);
+
+		// both prices match 1000 and leave 500 unmatched, the lower one clears
+		run_to_block(11);
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!(tp.latest_matched_price, Some(100_000_000));
+
+		// the buy orders at 1.1 share the 1000 pro rata, the remainder goes to the first one
+		let bob_order = TradeModule::order(TradeModule::owned_order((bob, 0)).unwrap()).unwrap();
+		assert_eq!(bob_order.status, OrderStatus::Filled);
+		assert!(bob_order.is_finished());
+
+		let alice_order = TradeModule::order(TradeModule::owned_order((alice, 0)).unwrap()).unwrap();
+		assert_eq!(alice_order.status, OrderStatus::PartialFilled);
+		assert_eq!((alice_order.remained_buy_amount, alice_order.remained_sell_amount), (333, 433));
+
+		let charlie_order = TradeModule::order(TradeModule::owned_order((charlie, 0)).unwrap()).unwrap();
+		assert_eq!(charlie_order.status, OrderStatus::PartialFilled);
+		assert_eq!((charlie_order.remained_buy_amount, charlie_order.remained_sell_amount), (167, 217));
+
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 2);
+		let trade1 = TradeModule::trade(TradeModule::trade_pair_owned_trades((tp_hash, 0)).unwrap()).unwrap();
+		assert_eq!((trade1.buyer, trade1.seller, trade1.price, trade1.base_amount, trade1.quote_amount), (alice, bob, 100_000_000, 667, 667));
+		let trade2 = TradeModule::trade(TradeModule::trade_pair_owned_trades((tp_hash, 1)).unwrap()).unwrap();
+		assert_eq!((trade2.buyer, trade2.seller, trade2.price, trade2.base_amount, trade2.quote_amount), (charlie, bob, 100_000_000, 333, 333));
+
+		assert_eq!(TokenModule::balance_of((bob, base)), 1000);
+		assert_eq!(TokenModule::balance_of((bob, quote)), 10000000 - 1000);
+		assert_eq!(TokenModule::reserved_balance_of((bob, quote)), 0);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 667);
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 10000 - 667);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 433 + 450);
+		assert_eq!(TokenModule::balance_of((charlie, quote)), 333);
+		assert_eq!(TokenModule::reserved_balance_of((charlie, base)), 217);
+
+		// the orders left join the market orders
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash), vec![]);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![]);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Sell), vec![(110_000_000, 500), (90_000_000, 500)]);
+		assert_eq!((tp.buy_one_price, tp.buy_one_amount, tp.sell_one_price), (Some(110_000_000), 500, None));
+
+		// nothing is crossed
+		run_to_block(12);
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 2);
+
+		// back to the continuous matching, the new order matches the first order at 1.1
+		assert_ok!(TradeModule::set_matching_mode(Origin::signed(alice), base, quote, MatchingMode::Continuous));
+		assert_eq!(TradeModule::clearing_trade_pairs(), vec![]);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 110_000_000, 100));
+
+		let alice_order = TradeModule::order(TradeModule::owned_order((alice, 0)).unwrap()).unwrap();
+		assert_eq!(alice_order.remained_buy_amount, 233);
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 3);
+	});
+}
+
+#[test]
+fn opening_auction_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_err!(TradeModule::create_trade_pair_with_opening_auction(Origin::signed(alice), base, quote, 8, 11), Error::<Test>::PreOpenBlocksTooLarge);
+
+		// pre-open in the blocks 10, 11 and 12
+		assert_ok!(TradeModule::create_trade_pair_with_opening_auction(Origin::signed(alice), base, quote, 8, 3));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		assert!(TradeModule::trade_pair(tp_hash).unwrap().is_pre_open());
+		assert_eq!(TradeModule::clearing_trade_pairs(), vec![tp_hash]);
+		assert_eq!(TradeModule::indicative_price(tp_hash), None);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 1000));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 120_000_000, 500));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 110_000_000, 550));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 105_000_000, 840));
+
+		// 1 and 1.05 both match 1000, 1 leaves less unmatched
+		assert_eq!(TradeModule::indicative_price(tp_hash), Some((100_000_000, 1000)));
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 0);
+
+		assert_err!(TradeModule::do_create_market_order(alice, base, quote, OrderType::Buy, 110_000_000, 100), Error::<Test>::TradePairNotOpened);
+		assert_err!(TradeModule::quote_order(tp_hash, OrderType::Buy, 110_000_000, 1100), Error::<Test>::TradePairNotOpened);
+
+		run_to_block(12);
+		assert!(TradeModule::trade_pair(tp_hash).unwrap().is_pre_open());
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 0);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 105_000_000, 200));
+		assert_eq!(TradeModule::indicative_price(tp_hash), Some((105_000_000, 1200)));
+
+		// the book uncrosses at 1.05 at the end of the block 12, the buy order at 1.1 fills first
+		run_to_block(13);
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert!(!tp.is_pre_open());
+		assert_eq!(TradeModule::clearing_trade_pairs(), vec![]);
+		assert_eq!(tp.latest_matched_price, Some(105_000_000));
+		assert_eq!(TradeModule::indicative_price(tp_hash), None);
+
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 3);
+		let amounts: Vec<(u128, u128, u128)> = (0..3).map(|i| {
+			let trade = TradeModule::trade(TradeModule::trade_pair_owned_trades((tp_hash, i)).unwrap()).unwrap();
+			(trade.price, trade.base_amount, trade.quote_amount)
+		}).collect();
+		assert_eq!(amounts, vec![(105_000_000, 525, 500), (105_000_000, 525, 500), (105_000_000, 210, 200)]);
+
+		let order1 = TradeModule::order(TradeModule::owned_order((alice, 0)).unwrap()).unwrap();
+		assert_eq!(order1.status, OrderStatus::Filled);
+		let order2 = TradeModule::order(TradeModule::owned_order((alice, 1)).unwrap()).unwrap();
+		assert_eq!(order2.status, OrderStatus::PartialFilled);
+		assert_eq!((order2.remained_buy_amount, order2.remained_sell_amount), (100, 105));
+
+		// the buy order at 1.1 pays less than it asked for, the rest is unfrozen
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 1260);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 105);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 1200);
+		assert_eq!(TokenModule::balance_of((bob, base)), 1260);
+		assert_eq!(TokenModule::reserved_balance_of((bob, quote)), 500);
+
+		// nothing is crossed after the opening
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Sell), vec![(105_000_000, 100)]);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![(120_000_000, 600)]);
+
+		// the continuous matching starts
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 120_000_000, 600));
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 4);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 1700);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![]);
+	});
+}
+
+#[test]
+fn pending_orders_cap_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		// pre-open in the blocks 10 and 11
+		assert_ok!(TradeModule::create_trade_pair_with_opening_auction(Origin::signed(alice), base, quote, 8, 2));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		for _ in 0..5 {
+			assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 100));
+			assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		}
+
+		// MaxPendingOrders is 10
+		assert_err!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100),
+			Error::<Test>::TooManyPendingOrders);
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash).len(), 10);
+
+		// the opening fails while the quote is paused, nothing is written and the pair stays pre-open
+		run_to_block(11);
+		assert_ok!(TokenModule::pause_token(Origin::signed(bob), quote));
+		run_to_block(12);
+
+		assert!(TradeModule::trade_pair(tp_hash).unwrap().is_pre_open());
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash).len(), 10);
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 0);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 500);
+		assert_eq!(TokenModule::reserved_balance_of((bob, quote)), 500);
+
+		// the opening is retried in the next block
+		assert_ok!(TokenModule::unpause_token(Origin::signed(bob), quote));
+		run_to_block(13);
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert!(!tp.is_pre_open());
+		assert_eq!(tp.latest_matched_price, Some(100_000_000));
+		assert_eq!(TradeModule::trade_pair_pending_orders(tp_hash), vec![]);
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 5);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 500);
+		assert_eq!(TokenModule::reserved_balance_of((bob, quote)), 0);
+	});
+}
+
+#[test]
+fn allocation_policy_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let charlie = 30;
+		let dave = 40;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), quote, charlie, 1000, None));
+		assert_ok!(TokenModule::transfer(Origin::signed(bob), quote, dave, 1000, None));
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap().allocation_policy(), AllocationPolicy::Fifo);
+
+		assert_err!(TradeModule::set_allocation_policy(Origin::signed(charlie), base, quote, AllocationPolicy::ProRata),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_ok!(TradeModule::set_allocation_policy(Origin::signed(alice), base, quote, AllocationPolicy::ProRata));
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 100));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Sell, 100_000_000, 300));
+		let bob_order = TradeModule::owned_order((bob, 0)).unwrap();
+		let charlie_order = TradeModule::owned_order((charlie, 0)).unwrap();
+
+		assert_eq!(TradeModule::quote_order(tp_hash, OrderType::Buy, 100_000_000, 200).unwrap().levels, vec![
+			OrderQuoteLevel { price: 100_000_000, base_amount: 200, quote_amount: 200, orders: 2 },
+		]);
+
+		// the level is shared in proportion to 100 and 300
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 200));
+		assert_eq!(TradeModule::order(bob_order).unwrap().remained_sell_amount, 50);
+		assert_eq!(TradeModule::order(charlie_order).unwrap().remained_sell_amount, 150);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 200);
+
+		// the earliest order fills first, the others share the rest
+		assert_ok!(TradeModule::set_allocation_policy(Origin::signed(bob), base, quote, AllocationPolicy::FifoTopProRata));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		assert_eq!(TradeModule::order(bob_order).unwrap().status, OrderStatus::Filled);
+		assert_eq!(TradeModule::order(charlie_order).unwrap().remained_sell_amount, 100);
+		assert_eq!(TradeModule::market_orders_at(tp_hash, 100_000_000), vec![charlie_order]);
+
+		// 100 * 100 / 300 and 100 * 200 / 300 are rounded down, the remained 1 goes to the earlier order
+		assert_ok!(TradeModule::set_allocation_policy(Origin::signed(alice), base, quote, AllocationPolicy::ProRata));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(dave), base, quote, OrderType::Sell, 100_000_000, 200));
+		let dave_order = TradeModule::owned_order((dave, 0)).unwrap();
+		// the quote shares the level the same way as the matching
+		let quote_result = TradeModule::quote_order(tp_hash, OrderType::Buy, 100_000_000, 100).unwrap();
+		assert_eq!(quote_result.levels, vec![
+			OrderQuoteLevel { price: 100_000_000, base_amount: 100, quote_amount: 100, orders: 2 },
+		]);
+		assert!(quote_result.filled);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		assert_eq!(TradeModule::order(charlie_order).unwrap().remained_sell_amount, 66);
+		assert_eq!(TradeModule::order(dave_order).unwrap().remained_sell_amount, 134);
+
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 6);
+		assert_eq!(TradeModule::market_levels(tp_hash, OrderType::Buy), vec![(100_000_000, 200)]);
+		assert_eq!(TokenModule::balance_of((alice, quote)), 400);
+		assert_eq!(TokenModule::balance_of((bob, base)), 100);
+		assert_eq!(TokenModule::balance_of((charlie, base)), 234);
+		assert_eq!(TokenModule::balance_of((dave, base)), 66);
+	});
+}
+
+#[test]
+fn genesis_config_test_case() {
+	let alice = 10;
+	let bob = 20;
+
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	token::GenesisConfig::<Test> {
+		tokens: vec![
+			(b"66".to_vec(), alice, 21000000, None),
+			(b"77".to_vec(), bob, 10000000, None),
+		],
+		balances: vec![
+			(b"66".to_vec(), alice, 20000000),
+			(b"66".to_vec(), bob, 1000000),
+			(b"77".to_vec(), alice, 1000000),
+			(b"77".to_vec(), bob, 9000000),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+	GenesisConfig::<Test> {
+		trade_pairs: vec![(b"66".to_vec(), b"77".to_vec(), 8)],
+		orders: vec![
+			(alice, b"66".to_vec(), b"77".to_vec(), OrderType::Buy, 90_000_000, 900),
+			(bob, b"66".to_vec(), b"77".to_vec(), OrderType::Sell, 110_000_000, 1000),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+
+	sp_io::TestExternalities::from(t).execute_with(|| {
+		let base = TokenModule::token_by_symbol(b"66".to_vec()).unwrap();
+		let quote = TokenModule::token_by_symbol(b"77".to_vec()).unwrap();
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+		assert_eq!(TradeModule::trade_pair_hash_by_index(0), Some(tp_hash));
+		assert_eq!(TradeModule::trade_pair(tp_hash).unwrap().price_decimals, 8);
+
+		assert_eq!(TokenModule::free_balance_of((alice, base)), 20000000 - 900);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 900);
+		assert_eq!(TokenModule::free_balance_of((bob, quote)), 9000000 - 1000);
+		assert_eq!(TokenModule::reserved_balance_of((bob, quote)), 1000);
+
+		let tp = TradeModule::trade_pair(tp_hash).unwrap();
+		assert_eq!(tp.buy_one_price, Some(90_000_000));
+		assert_eq!(tp.sell_one_price, Some(110_000_000));
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 0);
+
+		run_to_block(10);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 90_000_000, 500));
+		assert_eq!(TradeModule::trade_pair_owned_trades_index(tp_hash), 1);
+	});
+}
+
+#[test]
+#[should_panic(expected = "the genesis orders of a trade pair cross each other")]
+fn genesis_config_crossed_orders_test_case() {
+	let alice = 10;
+	let bob = 20;
+
+	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	token::GenesisConfig::<Test> {
+		tokens: vec![
+			(b"66".to_vec(), alice, 21000000, None),
+			(b"77".to_vec(), bob, 10000000, None),
+		],
+		balances: vec![],
+	}.assimilate_storage(&mut t).unwrap();
+	GenesisConfig::<Test> {
+		trade_pairs: vec![(b"66".to_vec(), b"77".to_vec(), 8)],
+		orders: vec![
+			(alice, b"66".to_vec(), b"77".to_vec(), OrderType::Buy, 100_000_000, 1000),
+			(bob, b"66".to_vec(), b"77".to_vec(), OrderType::Sell, 100_000_000, 1000),
+		],
+	}.assimilate_storage(&mut t).unwrap();
+}
+
+#[test]
+fn trade_pair_owner_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let charlie = 30;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		// the ownership of the base goes to charlie
+		assert_ok!(TokenModule::transfer_ownership(Origin::signed(alice), base, charlie));
+		assert_ok!(TokenModule::accept_ownership(Origin::signed(charlie), base));
+		assert_err!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(charlie), base, quote));
+		assert_err!(TradeModule::set_matching_mode(Origin::signed(alice), base, quote, MatchingMode::BatchAuction),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+
+		// the market admin of the base manages its trade pairs instead of the owner
+		assert_ok!(TokenModule::set_role(Origin::signed(charlie), base, token::TokenRole::MarketAdmin, Some(alice)));
+		assert_err!(TradeModule::set_matching_mode(Origin::signed(charlie), base, quote, MatchingMode::BatchAuction),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_ok!(TradeModule::set_matching_mode(Origin::signed(alice), base, quote, MatchingMode::BatchAuction));
+		assert_ok!(TokenModule::set_role(Origin::signed(charlie), base, token::TokenRole::MarketAdmin, None));
+
+		// a renounced token is still listed by the owner of the other token
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"88".to_vec(), 10000000));
+		let token3 = TokenModule::owned_token((alice, 1)).unwrap();
+		assert_ok!(TokenModule::renounce_ownership(Origin::signed(alice), token3));
+		assert_err!(TradeModule::create_trade_pair(Origin::signed(alice), token3, quote),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(bob), token3, quote));
+	});
+}
+
+#[test]
+fn frozen_account_and_paused_token_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let charlie = 30;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, charlie, 1000, None));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Buy, 100_000_000, 100));
+		let order1_hash = TradeModule::owned_order((charlie, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Buy, 50_000_000, 10));
+		let order3_hash = TradeModule::owned_order((charlie, 1)).unwrap();
+
+		// alice is the freezer of the base as its owner
+		assert_ok!(TokenModule::freeze_account(Origin::signed(alice), base, charlie));
+		assert_err!(
+			TradeModule::create_limit_order(Origin::signed(charlie), base, quote, OrderType::Buy, 100_000_000, 100),
+			token::Error::<Test>::AccountFrozen
+		);
+
+		// the quotes skip the order of the frozen account
+		let quote1 = TradeModule::quote_order(tp_hash, OrderType::Sell, 100_000_000, 50).unwrap();
+		assert_eq!(quote1.base_amount, 0);
+		assert!(!quote1.filled);
+
+		// the order of the frozen account is canceled instead of being matched
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 50));
+		let order2_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_eq!(TradeModule::order(order1_hash).unwrap().status, OrderStatus::Canceled);
+		// the order beyond the price of the taker is not reached
+		assert_eq!(TradeModule::order(order3_hash).unwrap().status, OrderStatus::Created);
+		assert_eq!(TokenModule::reserved_balance_of((charlie, base)), 10);
+		assert_eq!(TradeModule::order(order2_hash).unwrap().status, OrderStatus::Created);
+		assert_eq!(TokenModule::balance_of((charlie, quote)), 0);
+
+		// nothing trades while the quote is paused
+		assert_ok!(TokenModule::pause_token(Origin::signed(bob), quote));
+		assert_err!(
+			TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100),
+			token::Error::<Test>::TokenPaused
+		);
+		assert_err!(
+			TradeModule::quote_order(tp_hash, OrderType::Buy, 100_000_000, 100),
+			token::Error::<Test>::TokenPaused
+		);
+
+		assert_ok!(TokenModule::unpause_token(Origin::signed(bob), quote));
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		assert_eq!(TradeModule::order(order2_hash).unwrap().status, OrderStatus::Filled);
+	});
+}
+
+#[test]
+fn order_reserves_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		let order1_hash = TradeModule::owned_order((alice, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 90_000_000, 200));
+		let order2_hash = TradeModule::owned_order((alice, 1)).unwrap();
+
+		// each order holds its own sell amount
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 100);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order2_hash))), 200);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 300);
+
+		// the fill pays the buyer from the reserve of the filled order only
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 40));
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 60);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order2_hash))), 200);
+		assert_eq!(TokenModule::balance_of((bob, base)), 40);
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(alice), order2_hash));
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order2_hash))), 0);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 60);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 60);
+
+		// the balances frozen before the named reserves move to the open orders
+		let legacy = ReserveId::Named(LEGACY_RESERVE);
+		assert_ok!(TokenModule::unreserve(alice, base, ReserveId::Order(order1_hash), 60));
+		assert_ok!(TokenModule::reserve(alice, base, legacy, 60));
+		TradeModule::on_runtime_upgrade();
+		assert_eq!(TokenModule::named_reserve((alice, base, legacy)), 0);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 60);
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(alice), order1_hash));
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 0);
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 40);
+	});
+}
+
+#[test]
+fn migrate_order_reserves_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		assert_ok!(TokenModule::issue(Origin::signed(bob), b"77".to_vec(), 10000000));
+		let quote = TokenModule::owned_token((bob, 0)).unwrap();
+
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, quote));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, quote)).unwrap();
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		let order1_hash = TradeModule::owned_order((alice, 0)).unwrap();
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, quote, OrderType::Buy, 90_000_000, 200));
+		let order2_hash = TradeModule::owned_order((alice, 1)).unwrap();
+
+		// the old layout: the orders are not in the index of the opened orders and the legacy reserve holds their amounts
+		let legacy = ReserveId::Named(LEGACY_RESERVE);
+		for (index, (order_hash, amount)) in [(order1_hash, 100), (order2_hash, 200)].iter().enumerate() {
+			assert_ok!(TokenModule::unreserve(alice, base, ReserveId::Order(*order_hash), *amount));
+			assert_ok!(TokenModule::reserve(alice, base, legacy, *amount));
+			OwnedOpenedOrders::<Test>::remove((alice, index as u64));
+			OwnedTPAllOpenedOrders::<Test>::remove((alice, tp_hash, index as u64));
+			OpenedOrderPositions::<Test>::remove(order_hash);
+		}
+		OwnedOpenedOrdersIndex::<Test>::remove(alice);
+		OwnedTPAllOpenedOrdersIndex::<Test>::remove((alice, tp_hash));
+
+		TradeModule::on_runtime_upgrade();
+		assert_eq!(TokenModule::named_reserve((alice, base, legacy)), 0);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 100);
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order2_hash))), 200);
+		assert_eq!(TradeModule::owned_opened_orders_index(alice), 2);
+
+		// the migrated orders are filled and canceled from their own reserves
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 40));
+		assert_eq!(TokenModule::named_reserve((alice, base, ReserveId::Order(order1_hash))), 60);
+		assert_eq!(TokenModule::balance_of((bob, base)), 40);
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(alice), order1_hash));
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(alice), order2_hash));
+		assert_eq!(TradeModule::owned_opened_orders_index(alice), 0);
+		assert_eq!(TokenModule::reserved_balance_of((alice, base)), 0);
+		assert_eq!(TokenModule::balance_of((alice, base)), 21000000 - 40);
+	});
+}
+
+#[test]
+fn native_currency_test_case() {
+	new_test_ext().execute_with(|| {
+		run_to_block(10);
+
+		let alice = 10;
+		let bob = 20;
+		let native = NativeCurrencyId::get();
+
+		assert_ok!(TokenModule::issue(Origin::signed(alice), b"66".to_vec(), 21000000));
+		let base = TokenModule::owned_token((alice, 0)).unwrap();
+		Balances::make_free_balance_be(&bob, 1000);
+
+		// the owner of the token lists it against the native currency
+		assert_err!(TradeModule::create_trade_pair(Origin::signed(bob), base, native),
+			Error::<Test>::SenderNotEqualToBaseOrQuoteOwner);
+		assert_ok!(TradeModule::create_trade_pair(Origin::signed(alice), base, native));
+		let tp_hash = TradeModule::trade_pair_hash_by_base_quote((base, native)).unwrap();
+		assert_eq!(TradeModule::ticker(tp_hash).unwrap().quote_decimals, 12);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, native, OrderType::Sell, 100_000_000, 400));
+		let order1_hash = TradeModule::owned_order((bob, 0)).unwrap();
+		assert_eq!(Balances::reserved_balance(&bob), 400);
+		assert_eq!(TokenModule::named_reserve((bob, native, ReserveId::Order(order1_hash))), 400);
+		assert_err!(
+			TradeModule::create_limit_order(Origin::signed(bob), base, native, OrderType::Sell, 100_000_000, 601),
+			token::Error::<Test>::BalanceNotEnough
+		);
+
+		// the native currency is only paid to an existing account
+		assert_err!(
+			TradeModule::create_limit_order(Origin::signed(alice), base, native, OrderType::Buy, 100_000_000, 300),
+			token::Error::<Test>::DeadAccount
+		);
+		Balances::make_free_balance_be(&alice, 1);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(alice), base, native, OrderType::Buy, 100_000_000, 300));
+		assert_eq!(Balances::free_balance(&alice), 301);
+		assert_eq!(TokenModule::balance_of((bob, base)), 300);
+		assert_eq!(Balances::reserved_balance(&bob), 100);
+		assert_eq!(Balances::free_balance(&bob), 600);
+
+		// only the named reserves count, not the reserves of the other modules
+		assert_ok!(Balances::reserve(&bob, 50));
+		assert_eq!(<Test as Trait>::Currency::reserved_balance(native, &bob), 100);
+		Balances::unreserve(&bob, 50);
+
+		assert_ok!(TradeModule::cancel_limit_order(Origin::signed(bob), order1_hash));
+		assert_eq!(Balances::reserved_balance(&bob), 0);
+		assert_eq!(Balances::free_balance(&bob), 700);
+		assert_eq!(TokenModule::named_reserve((bob, native, ReserveId::Order(order1_hash))), 0);
+		assert_eq!(<Test as Trait>::Currency::reserved_balance(native, &bob), 0);
+
+		// the order of an account which doesn't exist any more is canceled instead of being paid
+		let charlie = 30;
+		assert_ok!(TokenModule::transfer(Origin::signed(alice), base, charlie, 100, None));
+		Balances::make_free_balance_be(&charlie, 1);
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(charlie), base, native, OrderType::Buy, 100_000_000, 100));
+		let order2_hash = TradeModule::owned_order((charlie, 0)).unwrap();
+		Balances::make_free_balance_be(&charlie, 0);
+
+		assert_ok!(TradeModule::create_limit_order(Origin::signed(bob), base, native, OrderType::Sell, 100_000_000, 50));
+		let order3_hash = TradeModule::owned_order((bob, 1)).unwrap();
+		assert_eq!(TradeModule::order(order2_hash).unwrap().status, OrderStatus::Canceled);
+		assert_eq!(TokenModule::free_balance_of((charlie, base)), 100);
+		assert_eq!(TradeModule::order(order3_hash).unwrap().status, OrderStatus::Created);
+		assert_eq!(Balances::reserved_balance(&bob), 50);
+	});
+}
+
+#[test]
+fn mock_ledger_test_case() {
+	use crate::mock_ledger::{self, LedgerTest, MockLedger};
+	type LedgerTrade = super::Module<LedgerTest>;
+
+	mock_ledger::new_test_ext().execute_with(|| {
+		let alice = 10;
+		let bob = 20;
+		let base = H256::from_low_u64_be(1);
+		let quote = H256::from_low_u64_be(2);
+
+		MockLedger::add_currency(base, alice);
+		MockLedger::add_currency(quote, bob);
+		MockLedger::set_free_balance(base, alice, 1000);
+		MockLedger::set_free_balance(quote, bob, 1000);
+
+		assert_ok!(LedgerTrade::create_trade_pair(mock_ledger::Origin::signed(alice), base, quote));
+		assert_ok!(LedgerTrade::create_limit_order(mock_ledger::Origin::signed(alice), base, quote, OrderType::Buy, 100_000_000, 100));
+		let order1_hash = LedgerTrade::owned_order((alice, 0)).unwrap();
+		assert_eq!(MockLedger::named_reserve(base, ReserveId::Order(order1_hash), &alice), 100);
+		assert_eq!(MockLedger::free_balance(base, &alice), 900);
+
+		assert_ok!(LedgerTrade::create_limit_order(mock_ledger::Origin::signed(bob), base, quote, OrderType::Sell, 100_000_000, 40));
+		assert_eq!(LedgerTrade::order(order1_hash).unwrap().status, OrderStatus::PartialFilled);
+		assert_eq!(MockLedger::named_reserve(base, ReserveId::Order(order1_hash), &alice), 60);
+		assert_eq!(MockLedger::total_balance(base, &bob), 40);
+		assert_eq!(MockLedger::total_balance(quote, &alice), 40);
+
+		assert_ok!(LedgerTrade::cancel_limit_order(mock_ledger::Origin::signed(alice), order1_hash));
+		assert_eq!(MockLedger::reserved_balance(base, &alice), 0);
+		assert_eq!(MockLedger::free_balance(base, &alice), 960);
+	});
+}
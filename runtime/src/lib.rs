@@ -231,10 +231,27 @@ impl balances::Trait for Runtime {
 
 parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
-	pub const PriceFactor: u128 = 100_000_000;
+	/// The native currency trades under this id next to the tokens
+	pub const NativeCurrencyId: Hash = sp_core::H256([0xff; 32]);
+	pub const NativeDecimals: u8 = 12;
+	pub const DefaultPriceDecimals: u8 = 8;
+	pub const MaxPriceDecimals: u8 = 18;
+	pub const MaxPreOpenBlocks: u32 = 6 * 60 * 24;
+	pub const MaxPendingOrders: u32 = 1_000;
     pub const BlocksPerDay: u32 = 6 * 60 * 24;
     pub const OpenedOrdersArrayCap: u8 = 20;
     pub const ClosedOrdersArrayCap: u8 = 100;
+    pub const ArchiveRetentionBlocks: u32 = 6 * 60 * 24 * 30;
+    pub const ArchivePruneBatchSize: u32 = 100;
+    pub const OracleObservationsCap: u32 = 720;
+    pub const OracleMaxAge: u32 = 6 * 60 * 24;
+    pub const LpFee: Permill = Permill::from_parts(3_000);
+    pub const MaxPathLength: u32 = 4;
+    pub const MaxRouteLevels: u32 = 50;
+    pub const MaxPathSearchQuotes: u32 = 200;
+    pub const RewardBand: Permill = Permill::from_percent(2);
+    pub const MaxSampledLevels: u32 = 50;
+    pub const MaxSampledOrders: u32 = 500;
 }
 
 impl transaction_payment::Trait for Runtime {
@@ -256,11 +273,38 @@ impl token::Trait for Runtime {
 
 impl trade::Trait for Runtime {
 	type Event = Event;
+	type Currency = token::Currencies<Runtime, NativeCurrencyId, NativeDecimals>;
 	type Price = u128;
-	type PriceFactor = PriceFactor;
+	type DefaultPriceDecimals = DefaultPriceDecimals;
+	type MaxPriceDecimals = MaxPriceDecimals;
+	type MaxPreOpenBlocks = MaxPreOpenBlocks;
+	type MaxPendingOrders = MaxPendingOrders;
 	type BlocksPerDay = BlocksPerDay;
 	type OpenedOrdersArrayCap = OpenedOrdersArrayCap;
 	type ClosedOrdersArrayCap = ClosedOrdersArrayCap;
+	type ArchiveRetentionBlocks = ArchiveRetentionBlocks;
+	type ArchivePruneBatchSize = ArchivePruneBatchSize;
+	type OracleObservationsCap = OracleObservationsCap;
+	type OracleMaxAge = OracleMaxAge;
+}
+
+impl pool::Trait for Runtime {
+	type Event = Event;
+	type LpFee = LpFee;
+}
+
+impl rewards::Trait for Runtime {
+	type Event = Event;
+	type RewardBand = RewardBand;
+	type MaxSampledLevels = MaxSampledLevels;
+	type MaxSampledOrders = MaxSampledOrders;
+}
+
+impl router::Trait for Runtime {
+	type Event = Event;
+	type MaxPathLength = MaxPathLength;
+	type MaxRouteLevels = MaxRouteLevels;
+	type MaxPathSearchQuotes = MaxPathSearchQuotes;
 }
 
 construct_runtime!(
@@ -277,8 +321,11 @@ construct_runtime!(
 		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: transaction_payment::{Module, Storage},
 		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
-		TokenModule: token::{Module, Call, Storage, Event<T>},
-		TradeModule: trade::{Module, Call, Storage, Event<T>},
+		TokenModule: token::{Module, Call, Storage, Config<T>, Event<T>},
+		TradeModule: trade::{Module, Call, Storage, Config<T>, Event<T>},
+		PoolModule: pool::{Module, Call, Storage, Event<T>},
+		RouterModule: router::{Module, Call, Event<T>},
+		RewardsModule: rewards::{Module, Call, Storage, Event<T>},
 	}
 );
 
@@ -392,6 +439,80 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl token_runtime_api::TokenApi<Block, AccountId, Hash, Balance> for Runtime {
+		fn token(token: Hash) -> Option<token_runtime_api::Token<Hash, Balance>> {
+			TokenModule::token(token)
+		}
+
+		fn token_by_symbol(symbol: Vec<u8>) -> Option<Hash> {
+			TokenModule::token_by_symbol(symbol)
+		}
+
+		fn allowance(owner: AccountId, spender: AccountId, token: Hash) -> Balance {
+			TokenModule::allowance((owner, spender, token))
+		}
+	}
+
+	impl trade_runtime_api::TradeApi<Block, AccountId, Hash, u128, Balance, BlockNumber> for Runtime {
+		fn ticker(trade_pair: Hash) -> Option<trade_runtime_api::Ticker<Hash, u128, Balance>> {
+			TradeModule::ticker(trade_pair)
+		}
+
+		fn tickers() -> Vec<trade_runtime_api::Ticker<Hash, u128, Balance>> {
+			TradeModule::tickers()
+		}
+
+		fn twap(trade_pair: Hash, window: BlockNumber) -> Result<u128, sp_runtime::DispatchError> {
+			<TradeModule as trade::PriceOracle<Hash, u128, BlockNumber>>::twap(trade_pair, window)
+		}
+
+		fn quote_order(
+			trade_pair: Hash,
+			otype: trade_runtime_api::OrderType,
+			price: u128,
+			sell_amount: Balance,
+		) -> Result<trade_runtime_api::OrderQuote<u128, Balance>, sp_runtime::DispatchError> {
+			TradeModule::quote_order(trade_pair, otype, price, sell_amount)
+		}
+
+		fn indicative_price(trade_pair: Hash) -> Option<(u128, Balance)> {
+			TradeModule::indicative_price(trade_pair)
+		}
+
+		fn owned_opened_orders(account: AccountId, start: u64, count: u64) -> Vec<Hash> {
+			TradeModule::owned_opened_orders_page(account, start, count)
+		}
+
+		fn owned_trade_pair_opened_orders(account: AccountId, trade_pair: Hash, start: u64, count: u64) -> Vec<Hash> {
+			TradeModule::owned_tp_opened_orders_page(account, trade_pair, start, count)
+		}
+
+		fn order_trades(order: Hash, start: u64, count: u64) -> Vec<Hash> {
+			TradeModule::order_trades_page(order, start, count)
+		}
+	}
+
+	impl router_runtime_api::RouterApi<Block, Hash, u128, Balance> for Runtime {
+		fn quote_swap(
+			token_in: Hash,
+			token_out: Hash,
+			amount_in: Balance,
+		) -> Result<router_runtime_api::SwapQuote<u128, Balance>, sp_runtime::DispatchError> {
+			RouterModule::quote_swap(token_in, token_out, amount_in)
+		}
+
+		fn quote_swap_path(
+			path: Vec<Hash>,
+			amount_in: Balance,
+		) -> Result<Vec<router_runtime_api::SwapQuote<u128, Balance>>, sp_runtime::DispatchError> {
+			RouterModule::quote_swap_path(path, amount_in)
+		}
+
+		fn best_path(token_in: Hash, token_out: Hash, amount_in: Balance) -> Option<(Vec<Hash>, Balance)> {
+			RouterModule::best_path(token_in, token_out, amount_in)
+		}
+	}
+
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()